@@ -0,0 +1,122 @@
+//! Throughput of `AppState::accept_advertisement`, signature verification included.
+//! Ads are signed in untimed setup; results are reported as elements (advertisements) per second.
+//!
+//!     cargo bench --bench accept_advertisement
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use uuid::Uuid;
+
+use shortwave::config::Cli;
+use shortwave::crypto::{encode_public_key_b64, encode_signature_b64, sign_bytes, signing_domain};
+use shortwave::state::AppState;
+use shortwave::types::{StationAdvertisement, AD_CANONICAL_VERSION};
+
+fn app_state() -> Arc<AppState> {
+	let cli = Cli::try_parse_from(["shortwave", "--public-url", "http://127.0.0.1:8080", "--max-registry-size", "10000000"])
+		.expect("bench config");
+	Arc::new(AppState::new(&cli.into_config().expect("bench config"), None))
+}
+
+fn random_key() -> SigningKey {
+	let mut seed = [0u8; 32];
+	OsRng.fill_bytes(&mut seed);
+	SigningKey::from_bytes(&seed)
+}
+
+fn signed_ad(sk: &SigningKey, station_id: Uuid, frequency: u64, sequence: u64) -> StationAdvertisement {
+	let mut ad = StationAdvertisement {
+		message_id: Uuid::new_v4(),
+		station_id,
+		frequency: BigDecimal::from(frequency),
+		name: "Bench FM".to_string(),
+		stream_url: format!("http://127.0.0.1:8080/stream/{frequency}"),
+		advertised_at: Utc::now(),
+		ttl_seconds: 60,
+		sequence,
+		owner_public_key: encode_public_key_b64(&sk.verifying_key()),
+		signature: String::new(),
+		program: None,
+		bitrate_kbps: Some(128),
+		codec: Some("mp3".to_string()),
+		stream_urls: Vec::new(),
+		canonical_version: AD_CANONICAL_VERSION,
+		logo_url: None,
+		location: None,
+	};
+	ad.signature = encode_signature_b64(&sign_bytes(sk, &ad.signed_bytes(&signing_domain(None)).expect("known canonical version")));
+	ad
+}
+
+fn bench_accept(c: &mut Criterion) {
+	let rt = tokio::runtime::Builder::new_multi_thread().enable_all().build().expect("runtime");
+	let mut group = c.benchmark_group("accept_advertisement");
+	group.throughput(Throughput::Elements(1));
+
+	// A new owner claiming a new frequency: verification plus an insert
+	let state = app_state();
+	let next_freq = AtomicU64::new(1);
+	group.bench_function("fresh", |b| {
+		b.to_async(&rt).iter_batched(
+			|| signed_ad(&random_key(), Uuid::new_v4(), next_freq.fetch_add(1, Ordering::Relaxed), 1),
+			|ad| {
+				let state = state.clone();
+				async move { state.accept_advertisement(&ad).await.expect("fresh ad accepted") }
+			},
+			BatchSize::SmallInput,
+		)
+	});
+
+	// The same message again, as gossip redelivers it: served from the message-id dedupe
+	let state = app_state();
+	let sk = random_key();
+	let ad = signed_ad(&sk, Uuid::new_v4(), 100, 1);
+	rt.block_on(state.accept_advertisement(&ad)).expect("seed ad accepted");
+	group.bench_function("duplicate", |b| {
+		b.to_async(&rt).iter(|| {
+			let (state, ad) = (state.clone(), ad.clone());
+			async move { state.accept_advertisement(&ad).await.expect("duplicate ad accepted") }
+		})
+	});
+
+	// The holder's next heartbeat: verification plus an update of an existing entry
+	let state = app_state();
+	let station_id = Uuid::new_v4();
+	let sequence = AtomicU64::new(1);
+	group.bench_function("refresh", |b| {
+		b.to_async(&rt).iter_batched(
+			|| signed_ad(&sk, station_id, 100, sequence.fetch_add(1, Ordering::Relaxed)),
+			|ad| {
+				let state = state.clone();
+				async move { state.accept_advertisement(&ad).await.expect("refresh accepted") }
+			},
+			BatchSize::SmallInput,
+		)
+	});
+
+	// Another owner claiming a held frequency: verified, then refused
+	let state = app_state();
+	rt.block_on(state.accept_advertisement(&signed_ad(&sk, station_id, 100, 1))).expect("holder accepted");
+	group.bench_function("conflict", |b| {
+		b.to_async(&rt).iter_batched(
+			|| signed_ad(&random_key(), Uuid::new_v4(), 100, 1),
+			|ad| {
+				let state = state.clone();
+				async move { state.accept_advertisement(&ad).await.expect_err("conflicting ad refused") }
+			},
+			BatchSize::SmallInput,
+		)
+	});
+
+	group.finish();
+}
+
+criterion_group!(benches, bench_accept);
+criterion_main!(benches);
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::state::AppState;
+use crate::types::{BlocklistBreaker, BlocklistStatus};
+
+/// First retry delay after a failed fetch; doubles per failure, capped at the refresh interval
+const RETRY_BASE: Duration = Duration::from_secs(30);
+/// Consecutive failed fetches after which the breaker opens
+const BREAKER_THRESHOLD: u32 = 5;
+/// How long an open breaker stops fetching (at least one refresh interval); a single probe fetch
+/// then closes or re-opens it
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(1800);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// One IP per line, `#` comments. CIDR lines are recognised but not enforced. A body with entries
+/// but nothing recognisable (an HTML error page served with 200, say) is garbage, not an empty
+/// blocklist.
+fn parse_blocklist(body: &str) -> Result<HashSet<IpAddr>, String> {
+	let mut set = HashSet::new();
+	let (mut entries, mut recognised) = (0usize, 0usize);
+	for line in body.lines() {
+		let s = line.split_once('#').map_or(line, |(left, _)| left).trim();
+		if s.is_empty() {
+			continue;
+		}
+		entries += 1;
+		if let Ok(ip) = s.parse::<IpAddr>() {
+			set.insert(ip.to_canonical());
+			recognised += 1;
+		} else if s.split_once('/').is_some_and(|(ip, len)| ip.parse::<IpAddr>().is_ok() && len.parse::<u8>().is_ok()) {
+			recognised += 1;
+		}
+	}
+	if entries > 0 && recognised == 0 {
+		return Err(format!("no IP address in {} entries", entries));
+	}
+	Ok(set)
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<HashSet<IpAddr>, String> {
+	let resp = client.get(url).send().await.map_err(|e| e.to_string())?;
+	if !resp.status().is_success() {
+		return Err(format!("blocklist URL returned {}", resp.status()));
+	}
+	let body = resp.text().await.map_err(|e| e.to_string())?;
+	parse_blocklist(&body)
+}
+
+/// Refresh the IP blocklist from `url` every `refresh`. Failures retry with exponential backoff;
+/// after `BREAKER_THRESHOLD` in a row the breaker opens and fetching pauses for a cooldown.
+/// The last good blocklist stays in force throughout, and the breaker state is published for stats.
+pub fn spawn_blocklist_fetcher(state: Arc<AppState>, url: String, refresh: Duration) {
+	tokio::spawn(async move {
+		let client = match reqwest::Client::builder().no_proxy().timeout(FETCH_TIMEOUT).build() {
+			Ok(c) => c,
+			Err(err) => {
+				warn!(error=%err, "failed to build http client for blocklist");
+				return;
+			}
+		};
+		let cooldown = BREAKER_COOLDOWN.max(refresh);
+		let mut status = BlocklistStatus::default();
+		loop {
+			let delay = match fetch(&client, &url).await {
+				Ok(set) => {
+					if status.consecutive_failures > 0 {
+						info!(failures = status.consecutive_failures, entries = set.len(), "blocklist fetch recovered");
+					}
+					status.breaker = BlocklistBreaker::Closed;
+					status.entries = set.len();
+					status.consecutive_failures = 0;
+					status.last_success_at = Some(Utc::now());
+					status.last_error = None;
+					state.set_blocklist(set).await;
+					refresh
+				}
+				Err(err) => {
+					status.consecutive_failures += 1;
+					status.last_error = Some(err.clone());
+					if status.consecutive_failures >= BREAKER_THRESHOLD {
+						if status.breaker != BlocklistBreaker::Open {
+							warn!(
+								failures = status.consecutive_failures, error=%err, cooldown_secs = cooldown.as_secs(),
+								"blocklist fetch keeps failing; pausing fetches and keeping the last good blocklist"
+							);
+						} else {
+							warn!(error=%err, "blocklist probe fetch failed; staying paused");
+						}
+						status.breaker = BlocklistBreaker::Open;
+						cooldown
+					} else {
+						let backoff = RETRY_BASE.saturating_mul(1 << (status.consecutive_failures - 1)).min(refresh);
+						warn!(failures = status.consecutive_failures, error=%err, retry_secs = backoff.as_secs(), "blocklist fetch failed");
+						status.breaker = BlocklistBreaker::BackingOff;
+						backoff
+					}
+				}
+			};
+			status.stale = status.consecutive_failures > 0;
+			status.next_fetch_at = Some(Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default());
+			state.set_blocklist_status(status.clone());
+			tokio::time::sleep(delay).await;
+		}
+	});
+}
@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::fs::{self, File};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::state::AppState;
+
+/// Tee the broadcast audio to `path` for offline inspection. It is its own subscriber, so a slow
+/// disk only makes the capture lag (and skip), never listeners. When the file reaches `max_bytes`
+/// or `max_age` it is moved to `<path>.1`, replacing the previous one, and a new file is started,
+/// so at most two segments are ever on disk.
+pub fn spawn_audio_capture(state: Arc<AppState>, path: String, max_bytes: u64, max_age: Option<Duration>) {
+	tokio::spawn(async move {
+		let mut rx = state.audio_tx.subscribe();
+		let mut segment = match Segment::create(&path).await {
+			Ok(s) => s,
+			Err(err) => {
+				warn!(error=%err, path, "failed to open audio debug capture; capture disabled");
+				return;
+			}
+		};
+		info!(path, max_bytes, "capturing broadcast audio");
+		loop {
+			let chunk = match rx.recv().await {
+				Ok(chunk) => chunk,
+				Err(broadcast::error::RecvError::Lagged(n)) => {
+					warn!(skipped = n, path, "audio debug capture fell behind; the capture has a gap");
+					continue;
+				}
+				Err(broadcast::error::RecvError::Closed) => break,
+			};
+			// Low disk (see resources.rs): drop audio, leaving a gap, rather than fill the disk
+			if state.is_capture_paused() {
+				continue;
+			}
+			if let Err(err) = segment.write(&chunk).await {
+				warn!(error=%err, path, "audio debug capture write failed; capture stopped");
+				return;
+			}
+			if segment.written >= max_bytes || max_age.is_some_and(|age| segment.started.elapsed() >= age) {
+				segment = match segment.rotate(&path).await {
+					Ok(s) => s,
+					Err(err) => {
+						warn!(error=%err, path, "failed to rotate audio debug capture; capture stopped");
+						return;
+					}
+				};
+			}
+		}
+		let _ = segment.file.flush().await;
+	});
+}
+
+struct Segment {
+	file: BufWriter<File>,
+	written: u64,
+	started: Instant,
+}
+
+impl Segment {
+	async fn create(path: &str) -> std::io::Result<Self> {
+		Ok(Self { file: BufWriter::new(File::create(path).await?), written: 0, started: Instant::now() })
+	}
+
+	async fn write(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+		self.file.write_all(chunk).await?;
+		self.written += chunk.len() as u64;
+		Ok(())
+	}
+
+	async fn rotate(mut self, path: &str) -> std::io::Result<Self> {
+		self.file.flush().await?;
+		drop(self.file);
+		let mut previous = PathBuf::from(path).into_os_string();
+		previous.push(".1");
+		fs::rename(path, &previous).await?;
+		info!(path, bytes = self.written, "rotated audio debug capture");
+		Self::create(path).await
+	}
+}
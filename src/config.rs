@@ -1,59 +1,197 @@
-use clap::{ArgAction, Parser};
+use clap::parser::ValueSource;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, ValueEnum};
  use uuid::Uuid;
 use bigdecimal::BigDecimal;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use ed25519_dalek::SigningKey;
 use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
+use crate::crypto::parse_public_key_b64;
+use crate::types::{normalize_frequency_key, parse_frequency, validate_genre, validate_logo_url, Frequency, validate_stream_endpoints, ProgramInfo, StationLocation, StreamEndpoint, AD_CANONICAL_VERSION};
+use crate::source::SourceSpec;
+use crate::store::RegistryStoreSpec;
 
- #[derive(Clone, Debug)]
+ #[derive(Clone, Debug, Serialize)]
  pub struct LocalStationConfig {
  	pub station_id: Uuid,
  	pub name: String,
+	#[serde(serialize_with = "serialize_decimal")]
 	pub frequency: BigDecimal,
  	pub stream_url: String,
+	/// station_id came from config rather than being generated at startup
+	pub station_id_explicit: bool,
+	pub stream_url_template: String,
+	/// Weighted edges advertised alongside `stream_url`
+	pub stream_endpoints: Vec<StreamEndpoint>,
+	pub logo_url: Option<String>,
+	pub location: Option<StationLocation>,
+	/// Sent to players as `icy-genre`; local to this node, not advertised
+	pub genre: Option<String>,
  }
 
- #[derive(Clone, Debug)]
+impl LocalStationConfig {
+	/// Switch to another station id (e.g. one restored from a hold file), re-rendering the stream URL
+	pub fn with_station_id(&self, station_id: Uuid, public_url: &str) -> anyhow::Result<Self> {
+		let stream_url = render_stream_url(&self.stream_url_template, public_url, &self.frequency, station_id)?;
+		Ok(Self { station_id, stream_url, ..self.clone() })
+	}
+
+	/// The stream URL this station gets when hosted behind another node's public URL
+	pub fn stream_url_at(&self, public_url: &str) -> anyhow::Result<String> {
+		render_stream_url(&self.stream_url_template, &normalize_public_url(public_url)?, &self.frequency, self.station_id)
+	}
+}
+
+ /// Behaviour when a new frequency arrives and the registry is at capacity
+ #[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+ #[serde(rename_all = "kebab-case")]
+ pub enum RegistryFullPolicy {
+ 	/// Refuse the new advertisement
+ 	Reject,
+ 	/// Evict the assignment that would expire soonest to make room
+ 	EvictSoonest,
+ }
+
+ /// Handling of a listener whose audio receiver lagged behind the broadcast channel
+ #[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+ #[serde(rename_all = "kebab-case")]
+ pub enum SlowListenerPolicy {
+ 	/// End the response; the player reconnects and resumes from the burst buffer without corrupt frames
+ 	Disconnect,
+ 	/// Drop the missed chunks and keep streaming; no reconnect, but the decoder sees a gap mid-frame
+ 	Skip,
+ }
+
+ /// Which libp2p transports to listen and dial on
+ #[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+ #[serde(rename_all = "kebab-case")]
+ pub enum P2PTransport {
+ 	Tcp,
+ 	Quic,
+ 	/// TCP and QUIC side by side
+ 	Both,
+	/// In-process only: nodes in the same process reach each other at `/memory/<port>`; for tests
+	#[value(hide = true)]
+	Memory,
+ }
+
+ /// Serializes for --print-config; secrets go through `redact*` so the output is safe to share
+ #[derive(Clone, Debug, Serialize)]
  pub struct Config {
  	pub node_id: Uuid,
- 	pub bind: String,
+ 	pub bind: Vec<String>,
  	pub public_url: String,
  	pub peers: Vec<String>,
+ 	#[serde(serialize_with = "redact")]
  	pub source_token: Option<String>,
+	#[serde(serialize_with = "redact")]
+	pub admin_token: Option<String>,
+	#[serde(serialize_with = "redact")]
+	pub cluster_secret: Option<String>,
  	pub local_station: Option<LocalStationConfig>,
  	pub advertise_ttl_secs: u32,
+	pub listener_heartbeat_secs: u32,
+	pub heartbeat_jitter: f64,
+	pub release_without_source_secs: Option<u64>,
+ 	#[serde(serialize_with = "redact_signing_key")]
  	pub owner_signing_key: Option<SigningKey>,
+	pub owner_public_key: Option<String>,
+	pub signer_socket: Option<String>,
  	pub max_frequencies_per_owner: u32,
+	/// Per-owner overrides of max_frequencies_per_owner
+	pub owner_caps: HashMap<String, u32>,
+	pub owner_caps_file: Option<String>,
 	pub ipc_socket: Option<String>,
+	pub ipc_max_connections: usize,
+	pub ipc_required: bool,
 	pub audio_ipc_socket: Option<String>,
+	pub audio_ipc_chunk_bytes: usize,
+	pub audio_tcp_ingest: Option<String>,
+	#[serde(serialize_with = "redact")]
+	pub audio_tcp_token: Option<String>,
 	pub blocklist_url: Option<String>,
 	pub blocklist_refresh_secs: u32,
+	pub rate_limit_per_sec: Option<f64>,
+	pub rate_limit_burst: u32,
+	pub now_webhook: Option<String>,
+	pub now_webhook_concurrency: usize,
+	pub event_webhook: Option<String>,
+	pub event_webhook_events: Vec<String>,
+	pub event_webhook_queue: usize,
+	pub allowed_owners: Vec<String>,
+	pub allowed_owners_file: Option<String>,
+	pub owner_name: Option<String>,
+	pub owner_url: Option<String>,
+	pub owner_avatar_url: Option<String>,
+	pub sources: Vec<SourceSpec>,
+	pub source_hold_down_secs: u32,
+	pub burst_bytes: usize,
+	pub dvr_secs: u32,
+	pub dvr_max_bytes: usize,
+	pub audio_slow_listener_policy: SlowListenerPolicy,
+	pub audio_debug_capture: Option<String>,
+	pub audio_debug_capture_max_bytes: u64,
+	pub audio_debug_capture_max_secs: u64,
+	pub min_free_memory_mb: u64,
+	pub min_free_disk_mb: u64,
+	pub dead_peer_timeout_secs: u64,
+	pub stream_healthcheck_secs: u64,
+	pub source_ws_max_kbps: u32,
+	pub max_ttl_secs: u32,
+	pub max_ad_age_fraction: f64,
+	pub min_canonical_version: u32,
+	pub ad_ack_sample: f64,
+	pub verify_concurrency: usize,
+	pub sign_concurrency: usize,
+	pub max_registry_size: usize,
+	pub max_owners: usize,
+	pub registry_full_policy: RegistryFullPolicy,
+	pub registry_store: RegistryStoreSpec,
+	pub max_now_playing_bytes: usize,
+	pub request_timeout_secs: u32,
+	#[serde(serialize_with = "serialize_opt_decimal")]
+	pub sync_min: Option<BigDecimal>,
+	#[serde(serialize_with = "serialize_opt_decimal")]
+	pub sync_max: Option<BigDecimal>,
+	pub allow_ephemeral_owner_key: bool,
+	pub network_id: Option<String>,
+	pub network_time: bool,
+	pub http_dscp: Option<u8>,
+	pub station_hold_path: Option<String>,
+	pub program_file: Option<String>,
+	pub web_ui_dir: Option<String>,
  	pub p2p_listen: Vec<String>,
  	pub p2p_bootstrap: Vec<String>,
  	pub p2p_mdns: bool,
+	pub p2p_transport: P2PTransport,
 	pub p2p_key_path: Option<String>,
+	#[serde(serialize_with = "redact")]
+	pub p2p_key_seed: Option<String>,
+	pub p2p_ban_path: Option<String>,
  }
 
  #[derive(Parser, Debug, Clone)]
  #[command(author, version, about = "Shortwave P2P Internet Radio Node", long_about = None)]
  pub struct Cli {
-	/// Path to YAML config file (if provided, overrides CLI)
+	/// Path to YAML config file; flags and environment variables that are set take precedence over it
 	#[arg(long = "config", env = "SHORTWAVE_CONFIG")]
 	pub config_path: Option<String>,
- 	/// Bind address for the HTTP API (e.g. 0.0.0.0:8080)
- 	#[arg(long, env = "SHORTWAVE_BIND", default_value = "0.0.0.0:8080")]
- 	pub bind: String,
+ 	/// Bind addresses for the HTTP API (comma-separated or repeated), e.g. 0.0.0.0:8080. `[::]:8080`
+ 	/// alone is dual-stack and also accepts IPv4; listed next to an IPv4 address on the same port it
+ 	/// takes IPv6 only
+ 	#[arg(long, env = "SHORTWAVE_BIND", value_delimiter = ',', action = ArgAction::Append, default_value = "0.0.0.0:8080")]
+ 	pub bind: Vec<String>,
 
- 	/// Public base URL of this node (e.g. https://radio.example.com)
+ 	/// Public base URL of this node (e.g. https://radio.example.com); required here or in the config file
  	#[arg(long, env = "SHORTWAVE_PUBLIC_URL")]
- 	pub public_url: String,
+ 	pub public_url: Option<String>,
 
  	/// Optional node ID. If omitted, a random UUID v4 is generated each start.
  	#[arg(long, env = "SHORTWAVE_NODE_ID")]
  	pub node_id: Option<String>,
 
- 	/// Peer API base URL(s) to initially register with (repeat flag for multiple peers)
+ 	/// Peer API base URL(s) to pull incremental registry sync from (repeat flag for multiple peers)
  	#[arg(long = "peer", env = "SHORTWAVE_PEERS", action = ArgAction::Append)]
  	pub peers: Vec<String>,
 
@@ -61,6 +199,19 @@ use serde::Deserialize;
  	#[arg(long, env = "SHORTWAVE_SOURCE_TOKEN")]
  	pub source_token: Option<String>,
 
+	/// Read the source token from this systemd credential ($CREDENTIALS_DIRECTORY/<name>) instead
+	#[arg(long, env = "SHORTWAVE_SOURCE_TOKEN_CREDENTIAL")]
+	pub source_token_credential: Option<String>,
+
+	/// Bearer token for /api/v1/admin routes; omit to disable the admin API
+	#[arg(long, env = "SHORTWAVE_ADMIN_TOKEN")]
+	pub admin_token: Option<String>,
+
+	/// Shared secret for private clusters: peer endpoints (/api/v1/sync) require it as a bearer
+	/// token and outgoing peer requests send it. The public read API is unaffected.
+	#[arg(long, env = "SHORTWAVE_CLUSTER_SECRET")]
+	pub cluster_secret: Option<String>,
+
  	/// Station display name (enable station mode when set)
  	#[arg(long, env = "SHORTWAVE_STATION_NAME")]
  	pub name: Option<String>,
@@ -77,22 +228,79 @@ use serde::Deserialize;
  	#[arg(long, env = "SHORTWAVE_TTL_SECS", default_value_t = 60)]
  	pub ttl_secs: u32,
 
- 	/// Base64-encoded 32-byte Ed25519 secret key for signing station ads/releases
+	/// Heartbeat interval in seconds while the local station has /stream listeners, so an expiring
+	/// advertisement is noticed (and re-sent) quickly while people are tuned in; 0 keeps the normal TTL/2
+	#[arg(long, env = "SHORTWAVE_LISTENER_HEARTBEAT_SECS", default_value_t = 10)]
+	pub listener_heartbeat_secs: u32,
+
+	/// Shorten each heartbeat interval by a random share of up to this fraction (at most 0.5), so
+	/// stations started together drift apart instead of gossiping and signing in lockstep
+	#[arg(long, env = "SHORTWAVE_HEARTBEAT_JITTER", default_value_t = 0.1)]
+	pub heartbeat_jitter: f64,
+
+	/// Only advertise the station while a source is sending audio: release the frequency after this
+	/// many seconds without any, and claim it again when audio returns. Unset always advertises
+	#[arg(long, env = "SHORTWAVE_RELEASE_WITHOUT_SOURCE_SECS")]
+	pub release_without_source_secs: Option<u64>,
+
+ 	/// Base64-encoded 32-byte Ed25519 secret key (or 64-byte secret+public keypair) for signing station ads/releases
  	#[arg(long, env = "SHORTWAVE_OWNER_SECRET_KEY")]
  	pub owner_secret_key: Option<String>,
 
+	/// Read the owner secret key from this systemd credential ($CREDENTIALS_DIRECTORY/<name>), e.g. one
+	/// set up with `LoadCredential=`, so the key never appears in the environment or on the command line
+	#[arg(long, env = "SHORTWAVE_OWNER_KEY_CREDENTIAL")]
+	pub owner_key_credential: Option<String>,
+
+	/// Base64 Ed25519 owner public key to advertise; required with --signer-socket, which holds the secret half
+	#[arg(long, env = "SHORTWAVE_OWNER_PUBLIC_KEY")]
+	pub owner_public_key: Option<String>,
+
+	/// Unix socket of an external signer (HSM or agent bridge) that signs station ads and releases,
+	/// so the owner secret key never lives in this process; replaces --owner-secret-key
+	#[arg(long, env = "SHORTWAVE_SIGNER_SOCKET")]
+	pub signer_socket: Option<String>,
+
  	/// Maximum concurrent frequencies per owner public key
  	#[arg(long, env = "SHORTWAVE_MAX_FREQS_PER_OWNER", default_value_t = 3)]
  	pub max_freqs_per_owner: u32,
 
+	/// Per-owner cap override as <pubkey>=<cap> (repeatable)
+	#[arg(long = "owner-cap", env = "SHORTWAVE_OWNER_CAPS", value_delimiter = ',', action = ArgAction::Append)]
+	pub owner_caps: Vec<String>,
+
+	/// YAML map of owner public key to cap; re-read periodically
+	#[arg(long = "owner-caps-file", env = "SHORTWAVE_OWNER_CAPS_FILE")]
+	pub owner_caps_file: Option<String>,
+
  	/// Unix domain socket path to receive NowPlaying JSON lines
  	#[arg(long, env = "SHORTWAVE_IPC_SOCKET")]
  	pub ipc_socket: Option<String>,
 
+	/// Concurrent connections accepted on --ipc-socket; further clients are closed immediately
+	#[arg(long, env = "SHORTWAVE_IPC_MAX_CONNECTIONS", default_value_t = 16)]
+	pub ipc_max_connections: usize,
+
+	/// Exit at startup if --ipc-socket or --audio-ipc-socket can't be bound, instead of running without it
+	#[arg(long, env = "SHORTWAVE_IPC_REQUIRED")]
+	pub ipc_required: bool,
+
 	/// Unix domain socket path to receive raw audio bytes (MPEG/OGG/Opus)
 	#[arg(long, env = "SHORTWAVE_AUDIO_IPC_SOCKET")]
 	pub audio_ipc_socket: Option<String>,
 
+	/// Read size and coalescing target for audio IPC chunks (512 to 1048576)
+	#[arg(long, env = "SHORTWAVE_AUDIO_IPC_CHUNK_BYTES", default_value_t = DEFAULT_AUDIO_IPC_CHUNK_BYTES)]
+	pub audio_ipc_chunk_bytes: usize,
+
+	/// ip:port to accept a raw TCP audio stream from a hardware encoder (one connection at a time)
+	#[arg(long, env = "SHORTWAVE_AUDIO_TCP_INGEST")]
+	pub audio_tcp_ingest: Option<String>,
+
+	/// Token the TCP encoder must send as its first line
+	#[arg(long, env = "SHORTWAVE_AUDIO_TCP_TOKEN")]
+	pub audio_tcp_token: Option<String>,
+
 	/// URL to fetch IP blocklist (one IP or CIDR per line, '#' comments allowed)
 	#[arg(long, env = "SHORTWAVE_BLOCKLIST_URL")]
 	pub blocklist_url: Option<String>,
@@ -101,6 +309,56 @@ use serde::Deserialize;
 	#[arg(long, env = "SHORTWAVE_BLOCKLIST_REFRESH_SECS", default_value_t = 600)]
 	pub blocklist_refresh_secs: u32,
 
+	/// Requests per second each client IP may make to the JSON API (sustained); over it they get
+	/// 429 with Retry-After. Streams, SSE, source ingest and /api/v1/healthz are exempt. Unset: no limit
+	#[arg(long, env = "SHORTWAVE_RATE_LIMIT_PER_SEC")]
+	pub rate_limit_per_sec: Option<f64>,
+
+	/// Requests a client may make back to back before --rate-limit-per-sec applies
+	#[arg(long, env = "SHORTWAVE_RATE_LIMIT_BURST", default_value_t = 20)]
+	pub rate_limit_burst: u32,
+
+	/// URL to POST now-playing JSON to on every change
+	#[arg(long, env = "SHORTWAVE_NOW_WEBHOOK")]
+	pub now_webhook: Option<String>,
+
+	/// Maximum concurrent deliveries to --now-webhook
+	#[arg(long, env = "SHORTWAVE_NOW_WEBHOOK_CONCURRENCY", default_value_t = 4)]
+	pub now_webhook_concurrency: usize,
+
+	/// URL to POST registry events (upserts, and deletes with their reason) to
+	#[arg(long, env = "SHORTWAVE_EVENT_WEBHOOK")]
+	pub event_webhook: Option<String>,
+
+	/// Registry event types to forward to --event-webhook: upsert, delete (comma-separated or repeated); empty forwards all
+	#[arg(long = "event-webhook-events", env = "SHORTWAVE_EVENT_WEBHOOK_EVENTS", value_delimiter = ',', action = ArgAction::Append)]
+	pub event_webhook_events: Vec<String>,
+
+	/// Events queued for --event-webhook while it is slow; further events are dropped
+	#[arg(long, env = "SHORTWAVE_EVENT_WEBHOOK_QUEUE", default_value_t = 1024)]
+	pub event_webhook_queue: usize,
+
+	/// Base64 owner public keys allowed to advertise (comma-separated or repeated); empty allows any owner
+	#[arg(long = "allowed-owners", env = "SHORTWAVE_ALLOWED_OWNERS", value_delimiter = ',', action = ArgAction::Append)]
+	pub allowed_owners: Vec<String>,
+
+	/// File of allowed owner public keys (one per line, '#' comments allowed); re-read periodically.
+	/// An empty file with no --allowed-owners admits no owner.
+	#[arg(long = "allowed-owners-file", env = "SHORTWAVE_ALLOWED_OWNERS_FILE")]
+	pub allowed_owners_file: Option<String>,
+
+	/// Display name published in this owner's signed profile (station mode)
+	#[arg(long, env = "SHORTWAVE_OWNER_NAME")]
+	pub owner_name: Option<String>,
+
+	/// Homepage URL for the owner profile
+	#[arg(long, env = "SHORTWAVE_OWNER_URL")]
+	pub owner_url: Option<String>,
+
+	/// Avatar image URL for the owner profile
+	#[arg(long, env = "SHORTWAVE_OWNER_AVATAR_URL")]
+	pub owner_avatar_url: Option<String>,
+
  	/// libp2p listen multiaddrs (repeatable)
  	#[arg(long = "p2p-listen", env = "SHORTWAVE_P2P_LISTEN", action = ArgAction::Append)]
  	pub p2p_listen: Vec<String>,
@@ -113,21 +371,263 @@ use serde::Deserialize;
  	#[arg(long = "p2p-mdns", env = "SHORTWAVE_P2P_MDNS", default_value_t = true)]
  	pub p2p_mdns: bool,
 
+	/// Prioritized ingest source as name:priority[:token] (repeatable; lower priority wins).
+	/// Use the name "audio-ipc" to include the Unix audio socket. HTTP and WebSocket sources
+	/// authenticate with their token, so an entry without one is only reachable over local sockets.
+	/// Omit for single-source mode.
+	#[arg(long = "source", env = "SHORTWAVE_SOURCES", value_delimiter = ',', action = ArgAction::Append)]
+	pub sources: Vec<SourceSpec>,
+
+	/// Seconds a recovered higher-priority source must stay healthy before failing back to it
+	#[arg(long, env = "SHORTWAVE_SOURCE_HOLD_DOWN_SECS", default_value_t = 10)]
+	pub source_hold_down_secs: u32,
+
+	/// Bytes of recent audio sent to a new listener at once before live pacing (0 disables)
+	#[arg(long, env = "SHORTWAVE_BURST_BYTES", default_value_t = 0)]
+	pub burst_bytes: usize,
+
+	/// Seconds of recent audio kept so listeners can start back in time with /stream?rewind=N (0 disables)
+	#[arg(long, env = "SHORTWAVE_DVR_SECS", default_value_t = 0)]
+	pub dvr_secs: u32,
+
+	/// Memory cap for the rewind buffer in bytes; at high bitrates it holds less than --dvr-secs
+	#[arg(long, env = "SHORTWAVE_DVR_MAX_BYTES", default_value_t = 16 * 1024 * 1024)]
+	pub dvr_max_bytes: usize,
+
+	/// What to do with a listener that falls behind the live audio: disconnect it (default; it reconnects
+	/// cleanly, starting from the burst buffer) or skip the missed chunks (keeps playing, with an audible glitch)
+	#[arg(long, env = "SHORTWAVE_AUDIO_SLOW_LISTENER_POLICY", value_enum, default_value_t = SlowListenerPolicy::Disconnect)]
+	pub audio_slow_listener_policy: SlowListenerPolicy,
+
+	/// Debugging aid: also write the broadcast audio to this file, exactly as sent to listeners
+	#[arg(long, env = "SHORTWAVE_AUDIO_DEBUG_CAPTURE")]
+	pub audio_debug_capture: Option<String>,
+
+	/// Size at which the capture file is rotated to <path>.1 (replacing the previous one)
+	#[arg(long, env = "SHORTWAVE_AUDIO_DEBUG_CAPTURE_MAX_BYTES", default_value_t = 64 * 1024 * 1024)]
+	pub audio_debug_capture_max_bytes: u64,
+
+	/// Age in seconds at which the capture file is rotated (0 rotates on size only)
+	#[arg(long, env = "SHORTWAVE_AUDIO_DEBUG_CAPTURE_MAX_SECS", default_value_t = 3600)]
+	pub audio_debug_capture_max_secs: u64,
+
+	/// Suspend the burst/rewind buffer while the host has less available memory than this, in MiB (0 disables the check)
+	#[arg(long, env = "SHORTWAVE_MIN_FREE_MEMORY_MB", default_value_t = 64)]
+	pub min_free_memory_mb: u64,
+
+	/// Pause the audio debug capture while its filesystem has less free space than this, in MiB (0 disables the check)
+	#[arg(long, env = "SHORTWAVE_MIN_FREE_DISK_MB", default_value_t = 256)]
+	pub min_free_disk_mb: u64,
+
+	/// Drop a listener or WebSocket source whose peer has stopped acknowledging data for this long
+	/// (TCP keepalive + TCP_USER_TIMEOUT on HTTP connections, ping/pong on WebSockets); 0 disables
+	#[arg(long, env = "SHORTWAVE_DEAD_PEER_TIMEOUT_SECS", default_value_t = 60)]
+	pub dead_peer_timeout_secs: u64,
+
+	/// Request every listed station's stream this often and mark those that don't answer with a
+	/// success status as unreachable (`?status=unreachable`); 0 disables
+	#[arg(long, env = "SHORTWAVE_STREAM_HEALTHCHECK_SECS", default_value_t = 0)]
+	pub stream_healthcheck_secs: u64,
+
+	/// Most a WebSocket source may push, audio and now-playing frames together, in kbit/s; a faster
+	/// source is read more slowly (0 disables)
+	#[arg(long, env = "SHORTWAVE_SOURCE_WS_MAX_KBPS", default_value_t = 2 * crate::types::MAX_BITRATE_KBPS)]
+	pub source_ws_max_kbps: u32,
+
+	/// Upper bound applied to advertised TTLs when computing expires_at
+	#[arg(long, env = "SHORTWAVE_MAX_TTL_SECS", default_value_t = 3600)]
+	pub max_ttl_secs: u32,
+
+	/// Refuse advertisements whose advertised_at is older than this fraction of their TTL (0-1),
+	/// so assignments that would expire almost at once aren't imported; 0 disables
+	#[arg(long, env = "SHORTWAVE_MAX_AD_AGE_FRACTION", default_value_t = 0.0)]
+	pub max_ad_age_fraction: f64,
+
+	/// Refuse advertisements signed with an older canonical layout than this. Versions before 3
+	/// use the `key=value;` layout, where separators inside a name or URL aren't escaped; set 3
+	/// once every station on the network runs a build that signs canonical JSON
+	#[arg(long, env = "SHORTWAVE_MIN_CANONICAL_VERSION", default_value_t = 0)]
+	pub min_canonical_version: u32,
+
+	/// Fraction of accepted gossiped advertisements this node acknowledges to their owner (0-1);
+	/// conflicts are always reported, at most once per station every few minutes. 0 sends none
+	#[arg(long, env = "SHORTWAVE_AD_ACK_SAMPLE", default_value_t = 0.25)]
+	pub ad_ack_sample: f64,
+
+	/// Advertisement signatures checked at once, off the async runtime; gossiped advertisements
+	/// arriving while this many are in flight are dropped rather than queued
+	#[arg(long, env = "SHORTWAVE_VERIFY_CONCURRENCY", default_value_t = 4)]
+	pub verify_concurrency: usize,
+
+	/// Owner signatures (heartbeats, releases, transfers, profile) produced at once; the rest wait
+	/// their turn, which also keeps a shared external signer from being hit in bursts
+	#[arg(long, env = "SHORTWAVE_SIGN_CONCURRENCY", default_value_t = 2)]
+	pub sign_concurrency: usize,
+
+	/// Maximum number of assignments held in the registry
+	#[arg(long, env = "SHORTWAVE_MAX_REGISTRY_SIZE", default_value_t = 10000)]
+	pub max_registry_size: usize,
+
+	/// Maximum number of distinct owner keys tracked at once; new owners are refused beyond it (0 = unlimited)
+	#[arg(long, env = "SHORTWAVE_MAX_OWNERS", default_value_t = 0)]
+	pub max_owners: usize,
+
+	/// What to do with a new frequency when the registry is full
+	#[arg(long, env = "SHORTWAVE_REGISTRY_FULL_POLICY", value_enum, default_value_t = RegistryFullPolicy::Reject)]
+	pub registry_full_policy: RegistryFullPolicy,
+
+	/// Where the registry is persisted across restarts: memory (default; rebuilt from gossip) or json:<path>
+	/// (sqlite:<path> is accepted but not implemented yet, and fails at startup)
+	#[arg(long, env = "SHORTWAVE_REGISTRY_STORE", default_value = "memory")]
+	pub registry_store: RegistryStoreSpec,
+
+	/// Persist the registry to this JSON file; shorthand for --registry-store json:<path>
+	#[arg(long, env = "SHORTWAVE_REGISTRY_PATH")]
+	pub registry_path: Option<String>,
+
+	/// Largest accepted now-playing update, in bytes of serialized JSON
+	#[arg(long, env = "SHORTWAVE_MAX_NOW_PLAYING_BYTES", default_value_t = DEFAULT_MAX_NOW_PLAYING_BYTES)]
+	pub max_now_playing_bytes: usize,
+
+	/// Timeout in seconds for non-streaming HTTP requests (streams and SSE are exempt)
+	#[arg(long, env = "SHORTWAVE_REQUEST_TIMEOUT_SECS", default_value_t = 30)]
+	pub request_timeout_secs: u32,
+
+	/// Lower frequency bound when syncing the registry from --peer nodes
+	#[arg(long, env = "SHORTWAVE_SYNC_MIN", value_parser = parse_frequency)]
+	pub sync_min: Option<BigDecimal>,
+
+	/// Upper frequency bound when syncing the registry from --peer nodes
+	#[arg(long, env = "SHORTWAVE_SYNC_MAX", value_parser = parse_frequency)]
+	pub sync_max: Option<BigDecimal>,
+
+	/// Allow station mode without --owner-secret-key (a new owner identity every restart)
+	#[arg(long, env = "SHORTWAVE_ALLOW_EPHEMERAL_OWNER_KEY")]
+	pub allow_ephemeral_owner_key: bool,
+
+	/// Print the effective configuration (after file/CLI/env merging, secrets masked) as YAML and exit
+	#[arg(long)]
+	pub print_config: bool,
+
+	/// Name of a separate shortwave network (letters, digits, "-", "_", "."; up to 64). Gossip topics and
+	/// every signature are scoped to it, so nodes and signed messages from other networks are never accepted
+	#[arg(long, env = "SHORTWAVE_NETWORK_ID")]
+	pub network_id: Option<String>,
+
+	/// Exchange clocks with gossip peers and judge advertisement timestamps by the bounded median
+	#[arg(long, env = "SHORTWAVE_NETWORK_TIME")]
+	pub network_time: bool,
+
+	/// File holding this station's last advertisement, so restarts keep the station_id and sequence
+	#[arg(long, env = "SHORTWAVE_STATION_HOLD_PATH")]
+	pub station_hold_path: Option<String>,
+
+	/// YAML/JSON file with the current/next show (ProgramInfo); re-read before every advertisement
+	#[arg(long, env = "SHORTWAVE_PROGRAM_FILE")]
+	pub program_file: Option<String>,
+
+	/// Directory of static files served at `/` (index.html fallback for SPA routes); API and /stream routes take precedence
+	#[arg(long, env = "SHORTWAVE_WEB_UI_DIR")]
+	pub web_ui_dir: Option<String>,
+
+	/// DSCP class to mark HTTP traffic with (name like EF, AF41, CS5 or a number 0-63). The mark is
+	/// set on the listening sockets, so every HTTP connection carries it: /stream audio, and API
+	/// requests too.
+	#[arg(long = "http-dscp", alias = "stream-dscp", env = "SHORTWAVE_HTTP_DSCP", value_parser = parse_dscp)]
+	pub http_dscp: Option<u8>,
+
+	/// Template for the advertised stream URL; supports {public_url}, {frequency}, {station_id}
+	#[arg(long = "stream-url-template", env = "SHORTWAVE_STREAM_URL_TEMPLATE", default_value = DEFAULT_STREAM_URL_TEMPLATE)]
+	pub stream_url_template: String,
+
+	/// Extra edge serving the station's stream, as "url[ region=<region>][ weight=<n>]" (repeatable).
+	/// Advertised with the primary stream URL so clients can pick the nearest.
+	#[arg(long = "stream-endpoint", env = "SHORTWAVE_STREAM_ENDPOINTS", value_delimiter = ',', action = ArgAction::Append)]
+	pub stream_endpoints: Vec<StreamEndpoint>,
+
+	/// Station logo advertised with the station (http(s), public host)
+	#[arg(long, env = "SHORTWAVE_LOGO_URL")]
+	pub logo_url: Option<String>,
+
+	/// Station genre, sent to players in the `icy-genre` header of /stream
+	#[arg(long, env = "SHORTWAVE_GENRE")]
+	pub genre: Option<String>,
+
+	/// Station latitude in degrees, advertised for map directories (with --longitude)
+	#[arg(long, env = "SHORTWAVE_LATITUDE", allow_negative_numbers = true)]
+	pub latitude: Option<f64>,
+
+	/// Station longitude in degrees (with --latitude)
+	#[arg(long, env = "SHORTWAVE_LONGITUDE", allow_negative_numbers = true)]
+	pub longitude: Option<f64>,
+
+	/// Station country as an ISO 3166-1 alpha-2 code (e.g. US), advertised and filterable with ?country=
+	#[arg(long, env = "SHORTWAVE_COUNTRY")]
+	pub country: Option<String>,
+
+	/// libp2p transports to use; QUIC listen/bootstrap addrs look like /ip4/.../udp/N/quic-v1
+	#[arg(long = "p2p-transport", env = "SHORTWAVE_P2P_TRANSPORT", value_enum, default_value_t = P2PTransport::Both)]
+	pub p2p_transport: P2PTransport,
+
 	/// Path to persist libp2p Ed25519 private key (stable PeerId)
 	#[arg(long = "p2p-key-path", env = "SHORTWAVE_P2P_KEY_PATH")]
 	pub p2p_key_path: Option<String>,
+
+	/// Seed for a deterministic libp2p identity: base64 32-byte seed, or a passphrase (SHA-256 hashed)
+	#[arg(long = "p2p-key-seed", env = "SHORTWAVE_P2P_KEY_SEED")]
+	pub p2p_key_seed: Option<String>,
+
+	/// File persisting libp2p peers banned through the admin API; without it bans last until restart
+	#[arg(long = "p2p-ban-path", env = "SHORTWAVE_P2P_BAN_PATH")]
+	pub p2p_ban_path: Option<String>,
+
+	/// Read the libp2p identity seed (as for --p2p-key-seed) from this systemd credential
+	#[arg(long = "p2p-key-credential", env = "SHORTWAVE_P2P_KEY_CREDENTIAL")]
+	pub p2p_key_credential: Option<String>,
+
+	/// Arg ids given on the command line or via environment (as opposed to clap defaults)
+	#[arg(skip)]
+	explicit: HashSet<String>,
  }
 
  impl Cli {
  	pub fn parse() -> Self {
- 		<Self as Parser>::parse()
+		Self::from_matches(<Self as CommandFactory>::command().get_matches()).unwrap_or_else(|e| e.exit())
  	}
 
- 	pub fn into_config(self) -> anyhow::Result<Config> {
-		// If a config file is provided, prefer loading from it.
+	/// `parse` over the given arguments, returning clap's error instead of exiting. Shadows the
+	/// derived `Parser::try_parse_from`, which wouldn't record which settings were given explicitly.
+	pub fn try_parse_from<I, T>(args: I) -> Result<Self, clap::Error>
+	where
+		I: IntoIterator<Item = T>,
+		T: Into<std::ffi::OsString> + Clone,
+	{
+		Self::from_matches(<Self as CommandFactory>::command().try_get_matches_from(args)?)
+	}
+
+	fn from_matches(matches: clap::ArgMatches) -> Result<Self, clap::Error> {
+		let mut cli = <Self as FromArgMatches>::from_arg_matches(&matches)?;
+		cli.explicit = matches.ids()
+			.filter(|id| matches!(matches.value_source(id.as_str()), Some(ValueSource::CommandLine | ValueSource::EnvVariable)))
+			.map(|id| id.to_string())
+			.collect();
+		Ok(cli)
+	}
+
+ 	/// Resolve the effective config: clap defaults, then the config file, then explicit flags/env on top
+ 	pub fn into_config(mut self) -> anyhow::Result<Config> {
 		if let Some(path) = self.config_path.clone() {
-			return load_config_file(&path);
+			self.layer_config_file(&path)?;
 		}
+		resolve_credential("--source-token-credential", self.source_token_credential.as_deref(), &mut self.source_token)?;
+		resolve_credential("--owner-key-credential", self.owner_key_credential.as_deref(), &mut self.owner_secret_key)?;
+		resolve_credential("--p2p-key-credential", self.p2p_key_credential.as_deref(), &mut self.p2p_key_seed)?;
+		let bind = normalize_binds(self.bind)?;
+		let public_url = self.public_url.as_deref()
+			.ok_or_else(|| anyhow::anyhow!("public URL is required: set --public-url (SHORTWAVE_PUBLIC_URL) or public_url in the config file"))?;
+		let public_url = normalize_public_url(public_url)?;
+		let p2p_listen = normalize_multiaddrs("--p2p-listen", self.p2p_listen, self.p2p_transport)?;
+		let p2p_bootstrap = normalize_multiaddrs("--p2p-bootstrap", self.p2p_bootstrap, self.p2p_transport)?;
  		let node_id = match self.node_id {
  			Some(s) => Uuid::from_str(&s)?,
  			None => Uuid::new_v4(),
@@ -135,53 +635,335 @@ use serde::Deserialize;
 
 		let local_station = match (self.name.clone(), self.frequency.clone()) {
  			(Some(name), Some(frequency)) => {
-				let freq = BigDecimal::from_str(&frequency)?;
- 				let station_id = match self.station_id {
- 					Some(id) => Uuid::from_str(&id)?,
+				let freq = parse_frequency(&frequency).map_err(|e| anyhow::anyhow!("invalid --frequency: {}", e))?;
+ 				let station_id = match &self.station_id {
+ 					Some(id) => Uuid::from_str(id)?,
  					None => Uuid::new_v4(),
  				};
- 				let stream_url = format!("{}/stream", self.public_url.trim_end_matches('/'));
-				Some(LocalStationConfig { station_id, name, frequency: freq, stream_url })
+ 				let stream_url = render_stream_url(&self.stream_url_template, &public_url, &freq, station_id)?;
+				validate_stream_endpoints(&self.stream_endpoints)
+					.map_err(|e| anyhow::anyhow!("invalid --stream-endpoint: {}", e))?;
+				if let Some(logo) = &self.logo_url {
+					validate_logo_url(logo).map_err(|e| anyhow::anyhow!("invalid --logo-url: {}", e))?;
+				}
+				let location = (self.latitude.is_some() || self.longitude.is_some() || self.country.is_some()).then(|| StationLocation {
+					latitude: self.latitude,
+					longitude: self.longitude,
+					country: self.country.as_deref().map(|c| c.trim().to_ascii_uppercase()),
+				});
+				if let Some(location) = &location {
+					location.validate().map_err(|e| anyhow::anyhow!("invalid --latitude/--longitude/--country: {}", e))?;
+				}
+				if let Some(genre) = &self.genre {
+					validate_genre(genre).map_err(|e| anyhow::anyhow!("invalid --genre: {}", e))?;
+				}
+				Some(LocalStationConfig {
+					station_id,
+					name,
+					frequency: freq,
+					stream_url,
+					station_id_explicit: self.station_id.is_some(),
+					stream_url_template: self.stream_url_template.clone(),
+					stream_endpoints: self.stream_endpoints.clone(),
+					logo_url: self.logo_url.clone(),
+					genre: self.genre.clone(),
+					location,
+				})
  			}
  			_ => None,
  		};
 
- 		let owner_signing_key = match self.owner_secret_key {
- 			Some(sk_b64) => {
- 				let bytes = B64.decode(sk_b64)?;
- 				let sk = SigningKey::from_bytes(bytes.as_slice().try_into()?);
- 				Some(sk)
- 			}
- 			None => None,
- 		};
+ 		let owner_signing_key = self.owner_secret_key.as_deref().map(parse_owner_secret_key).transpose()?;
+		let owner_public_key = self.owner_public_key.as_deref().map(|k| parse_owner_public_key(k, owner_signing_key.as_ref())).transpose()?;
 
  		Ok(Config {
  			node_id,
- 			bind: self.bind,
- 			public_url: self.public_url,
+ 			bind,
+ 			public_url,
  			peers: self.peers,
  			source_token: self.source_token,
+			admin_token: self.admin_token,
+			cluster_secret: self.cluster_secret.filter(|s| !s.is_empty()),
  			local_station,
  			advertise_ttl_secs: self.ttl_secs.max(10),
+			listener_heartbeat_secs: self.listener_heartbeat_secs,
+			heartbeat_jitter: validate_heartbeat_jitter(self.heartbeat_jitter)?,
+			release_without_source_secs: self.release_without_source_secs,
  			owner_signing_key,
+			owner_public_key,
+			signer_socket: self.signer_socket,
  			max_frequencies_per_owner: self.max_freqs_per_owner.max(1),
+			owner_caps: validate_owner_caps(self.owner_caps.iter().map(|s| parse_owner_cap(s)).collect::<anyhow::Result<_>>()?)?,
+			owner_caps_file: self.owner_caps_file,
  			ipc_socket: self.ipc_socket,
+			ipc_max_connections: self.ipc_max_connections.max(1),
+			ipc_required: self.ipc_required,
 			audio_ipc_socket: self.audio_ipc_socket,
+			audio_ipc_chunk_bytes: self.audio_ipc_chunk_bytes.clamp(512, 1024 * 1024),
+			audio_tcp_ingest: self.audio_tcp_ingest.as_deref().map(normalize_bind).transpose()?,
+			audio_tcp_token: self.audio_tcp_token,
 			blocklist_url: self.blocklist_url,
 			blocklist_refresh_secs: self.blocklist_refresh_secs.max(30),
- 			p2p_listen: self.p2p_listen,
- 			p2p_bootstrap: self.p2p_bootstrap,
+			rate_limit_per_sec: self.rate_limit_per_sec.map(validate_rate_limit).transpose()?,
+			rate_limit_burst: self.rate_limit_burst.max(1),
+			now_webhook: self.now_webhook.as_deref().map(|u| normalize_webhook_url("--now-webhook", u)).transpose()?,
+			now_webhook_concurrency: self.now_webhook_concurrency.max(1),
+			event_webhook: self.event_webhook.as_deref().map(|u| normalize_webhook_url("--event-webhook", u)).transpose()?,
+			event_webhook_events: validate_event_types(self.event_webhook_events)?,
+			event_webhook_queue: self.event_webhook_queue.max(1),
+			allowed_owners: validate_owner_keys(self.allowed_owners)?,
+			allowed_owners_file: self.allowed_owners_file,
+			owner_name: self.owner_name,
+			owner_url: self.owner_url,
+			owner_avatar_url: self.owner_avatar_url,
+			sources: self.sources,
+			source_hold_down_secs: self.source_hold_down_secs,
+			burst_bytes: self.burst_bytes,
+			dvr_secs: self.dvr_secs,
+			dvr_max_bytes: self.dvr_max_bytes,
+			audio_slow_listener_policy: self.audio_slow_listener_policy,
+			audio_debug_capture: self.audio_debug_capture,
+			audio_debug_capture_max_bytes: self.audio_debug_capture_max_bytes.max(64 * 1024),
+			audio_debug_capture_max_secs: self.audio_debug_capture_max_secs,
+			min_free_memory_mb: self.min_free_memory_mb,
+			min_free_disk_mb: self.min_free_disk_mb,
+			dead_peer_timeout_secs: self.dead_peer_timeout_secs,
+			stream_healthcheck_secs: self.stream_healthcheck_secs,
+			source_ws_max_kbps: self.source_ws_max_kbps,
+			max_ttl_secs: self.max_ttl_secs.max(10),
+			max_ad_age_fraction: validate_fraction("--max-ad-age-fraction", self.max_ad_age_fraction)?,
+			min_canonical_version: match self.min_canonical_version {
+				v if v <= AD_CANONICAL_VERSION => v,
+				v => anyhow::bail!("--min-canonical-version {} is newer than this build signs ({})", v, AD_CANONICAL_VERSION),
+			},
+			ad_ack_sample: validate_fraction("--ad-ack-sample", self.ad_ack_sample)?,
+			verify_concurrency: self.verify_concurrency.max(1),
+			sign_concurrency: self.sign_concurrency.max(1),
+			max_registry_size: self.max_registry_size.max(1),
+			max_owners: self.max_owners,
+			registry_full_policy: self.registry_full_policy,
+			registry_store: match (self.registry_path, self.registry_store) {
+				(None, store) => store,
+				(Some(path), RegistryStoreSpec::Memory) => RegistryStoreSpec::Json(path),
+				(Some(path), RegistryStoreSpec::Json(p)) if p == path => RegistryStoreSpec::Json(p),
+				(Some(path), _) => anyhow::bail!("--registry-path {} conflicts with a different --registry-store; set only one of them", path),
+			},
+			max_now_playing_bytes: self.max_now_playing_bytes,
+			request_timeout_secs: self.request_timeout_secs.max(1),
+			sync_min: self.sync_min,
+			sync_max: self.sync_max,
+			allow_ephemeral_owner_key: self.allow_ephemeral_owner_key,
+			network_id: validate_network_id(self.network_id)?,
+			network_time: self.network_time,
+			station_hold_path: self.station_hold_path,
+			program_file: self.program_file,
+			web_ui_dir: self.web_ui_dir,
+			http_dscp: self.http_dscp,
+ 			p2p_listen,
+ 			p2p_bootstrap,
  			p2p_mdns: self.p2p_mdns,
+			p2p_transport: self.p2p_transport,
 			p2p_key_path: self.p2p_key_path,
+			p2p_key_seed: self.p2p_key_seed,
+			p2p_ban_path: self.p2p_ban_path,
  		})
  	}
+
+	/// Fill every setting not given explicitly from the YAML file at `path`
+	fn layer_config_file(&mut self, path: &str) -> anyhow::Result<()> {
+		let text = std::fs::read_to_string(path)
+			.map_err(|e| anyhow::anyhow!("failed to read config file '{}': {}", path, e))?;
+		let cfg: FileConfig = serde_yaml::from_str(&text)
+			.map_err(|e| anyhow::anyhow!("invalid config file '{}': {}", path, e))?;
+		let ex = std::mem::take(&mut self.explicit);
+		layer(&ex, "bind", &mut self.bind, cfg.bind.map(FileBind::into_vec));
+		layer(&ex, "public_url", &mut self.public_url, cfg.public_url.map(Some));
+		layer(&ex, "node_id", &mut self.node_id, cfg.node_id.map(|id| Some(id.to_string())));
+		layer(&ex, "peers", &mut self.peers, cfg.peers);
+		layer(&ex, "source_token", &mut self.source_token, cfg.source_token.map(Some));
+		layer(&ex, "source_token_credential", &mut self.source_token_credential, cfg.source_token_credential.map(Some));
+		layer(&ex, "admin_token", &mut self.admin_token, cfg.admin_token.map(Some));
+		layer(&ex, "cluster_secret", &mut self.cluster_secret, cfg.cluster_secret.map(Some));
+		if let Some(st) = cfg.station {
+			layer(&ex, "name", &mut self.name, Some(Some(st.name)));
+			layer(&ex, "frequency", &mut self.frequency, Some(Some(st.frequency.0.to_string())));
+			layer(&ex, "station_id", &mut self.station_id, st.station_id.map(|id| Some(id.to_string())));
+			layer(&ex, "stream_url_template", &mut self.stream_url_template, st.stream_url);
+			layer(&ex, "stream_endpoints", &mut self.stream_endpoints, st.stream_endpoints);
+			layer(&ex, "logo_url", &mut self.logo_url, st.logo_url.map(Some));
+			layer(&ex, "genre", &mut self.genre, st.genre.map(Some));
+			layer(&ex, "latitude", &mut self.latitude, st.latitude.map(Some));
+			layer(&ex, "longitude", &mut self.longitude, st.longitude.map(Some));
+			layer(&ex, "country", &mut self.country, st.country.map(Some));
+		}
+		layer(&ex, "stream_url_template", &mut self.stream_url_template, cfg.stream_url_template);
+		layer(&ex, "ttl_secs", &mut self.ttl_secs, cfg.advertise_ttl_secs);
+		layer(&ex, "listener_heartbeat_secs", &mut self.listener_heartbeat_secs, cfg.listener_heartbeat_secs);
+		layer(&ex, "heartbeat_jitter", &mut self.heartbeat_jitter, cfg.heartbeat_jitter);
+		layer(&ex, "release_without_source_secs", &mut self.release_without_source_secs, cfg.release_without_source_secs.map(Some));
+		layer(&ex, "owner_secret_key", &mut self.owner_secret_key, cfg.owner_secret_key.map(Some));
+		layer(&ex, "owner_key_credential", &mut self.owner_key_credential, cfg.owner_key_credential.map(Some));
+		layer(&ex, "owner_public_key", &mut self.owner_public_key, cfg.owner_public_key.map(Some));
+		layer(&ex, "signer_socket", &mut self.signer_socket, cfg.signer_socket.map(Some));
+		layer(&ex, "max_freqs_per_owner", &mut self.max_freqs_per_owner, cfg.max_frequencies_per_owner);
+		layer(&ex, "owner_caps", &mut self.owner_caps, cfg.owner_caps.map(|caps| caps.into_iter().map(|(k, v)| format!("{}={}", k, v)).collect()));
+		layer(&ex, "owner_caps_file", &mut self.owner_caps_file, cfg.owner_caps_file.map(Some));
+		layer(&ex, "ipc_socket", &mut self.ipc_socket, cfg.ipc_socket.map(Some));
+		layer(&ex, "ipc_max_connections", &mut self.ipc_max_connections, cfg.ipc_max_connections);
+		layer(&ex, "ipc_required", &mut self.ipc_required, cfg.ipc_required);
+		layer(&ex, "audio_ipc_socket", &mut self.audio_ipc_socket, cfg.audio_ipc_socket.map(Some));
+		layer(&ex, "audio_ipc_chunk_bytes", &mut self.audio_ipc_chunk_bytes, cfg.audio_ipc_chunk_bytes);
+		layer(&ex, "audio_tcp_ingest", &mut self.audio_tcp_ingest, cfg.audio_tcp_ingest.map(Some));
+		layer(&ex, "audio_tcp_token", &mut self.audio_tcp_token, cfg.audio_tcp_token.map(Some));
+		layer(&ex, "blocklist_url", &mut self.blocklist_url, cfg.blocklist_url.map(Some));
+		layer(&ex, "blocklist_refresh_secs", &mut self.blocklist_refresh_secs, cfg.blocklist_refresh_secs);
+		layer(&ex, "rate_limit_per_sec", &mut self.rate_limit_per_sec, cfg.rate_limit_per_sec.map(Some));
+		layer(&ex, "rate_limit_burst", &mut self.rate_limit_burst, cfg.rate_limit_burst);
+		layer(&ex, "now_webhook", &mut self.now_webhook, cfg.now_webhook.map(Some));
+		layer(&ex, "now_webhook_concurrency", &mut self.now_webhook_concurrency, cfg.now_webhook_concurrency);
+		layer(&ex, "event_webhook", &mut self.event_webhook, cfg.event_webhook.map(Some));
+		layer(&ex, "event_webhook_events", &mut self.event_webhook_events, cfg.event_webhook_events);
+		layer(&ex, "event_webhook_queue", &mut self.event_webhook_queue, cfg.event_webhook_queue);
+		layer(&ex, "allowed_owners", &mut self.allowed_owners, cfg.allowed_owners);
+		layer(&ex, "allowed_owners_file", &mut self.allowed_owners_file, cfg.allowed_owners_file.map(Some));
+		layer(&ex, "owner_name", &mut self.owner_name, cfg.owner_name.map(Some));
+		layer(&ex, "owner_url", &mut self.owner_url, cfg.owner_url.map(Some));
+		layer(&ex, "owner_avatar_url", &mut self.owner_avatar_url, cfg.owner_avatar_url.map(Some));
+		layer(&ex, "sources", &mut self.sources, cfg.sources);
+		layer(&ex, "source_hold_down_secs", &mut self.source_hold_down_secs, cfg.source_hold_down_secs);
+		layer(&ex, "burst_bytes", &mut self.burst_bytes, cfg.burst_bytes);
+		layer(&ex, "dvr_secs", &mut self.dvr_secs, cfg.dvr_secs);
+		layer(&ex, "dvr_max_bytes", &mut self.dvr_max_bytes, cfg.dvr_max_bytes);
+		layer(&ex, "audio_slow_listener_policy", &mut self.audio_slow_listener_policy, cfg.audio_slow_listener_policy);
+		layer(&ex, "audio_debug_capture", &mut self.audio_debug_capture, cfg.audio_debug_capture.map(Some));
+		layer(&ex, "audio_debug_capture_max_bytes", &mut self.audio_debug_capture_max_bytes, cfg.audio_debug_capture_max_bytes);
+		layer(&ex, "audio_debug_capture_max_secs", &mut self.audio_debug_capture_max_secs, cfg.audio_debug_capture_max_secs);
+		layer(&ex, "min_free_memory_mb", &mut self.min_free_memory_mb, cfg.min_free_memory_mb);
+		layer(&ex, "min_free_disk_mb", &mut self.min_free_disk_mb, cfg.min_free_disk_mb);
+		layer(&ex, "dead_peer_timeout_secs", &mut self.dead_peer_timeout_secs, cfg.dead_peer_timeout_secs);
+		layer(&ex, "stream_healthcheck_secs", &mut self.stream_healthcheck_secs, cfg.stream_healthcheck_secs);
+		layer(&ex, "source_ws_max_kbps", &mut self.source_ws_max_kbps, cfg.source_ws_max_kbps);
+		layer(&ex, "max_ttl_secs", &mut self.max_ttl_secs, cfg.max_ttl_secs);
+		layer(&ex, "max_ad_age_fraction", &mut self.max_ad_age_fraction, cfg.max_ad_age_fraction);
+		layer(&ex, "min_canonical_version", &mut self.min_canonical_version, cfg.min_canonical_version);
+		layer(&ex, "ad_ack_sample", &mut self.ad_ack_sample, cfg.ad_ack_sample);
+		layer(&ex, "verify_concurrency", &mut self.verify_concurrency, cfg.verify_concurrency);
+		layer(&ex, "sign_concurrency", &mut self.sign_concurrency, cfg.sign_concurrency);
+		layer(&ex, "max_registry_size", &mut self.max_registry_size, cfg.max_registry_size);
+		layer(&ex, "max_owners", &mut self.max_owners, cfg.max_owners);
+		layer(&ex, "registry_full_policy", &mut self.registry_full_policy, cfg.registry_full_policy);
+		layer(&ex, "registry_store", &mut self.registry_store, cfg.registry_store.as_deref().map(str::parse).transpose()?);
+		layer(&ex, "registry_path", &mut self.registry_path, cfg.registry_path.map(Some));
+		layer(&ex, "max_now_playing_bytes", &mut self.max_now_playing_bytes, cfg.max_now_playing_bytes);
+		layer(&ex, "request_timeout_secs", &mut self.request_timeout_secs, cfg.request_timeout_secs);
+		layer(&ex, "sync_min", &mut self.sync_min, cfg.sync_min.map(|f| Some(f.0)));
+		layer(&ex, "sync_max", &mut self.sync_max, cfg.sync_max.map(|f| Some(f.0)));
+		layer(&ex, "allow_ephemeral_owner_key", &mut self.allow_ephemeral_owner_key, cfg.allow_ephemeral_owner_key);
+		layer(&ex, "network_id", &mut self.network_id, cfg.network_id.map(Some));
+		layer(&ex, "network_time", &mut self.network_time, cfg.network_time);
+		layer(&ex, "station_hold_path", &mut self.station_hold_path, cfg.station_hold_path.map(Some));
+		layer(&ex, "program_file", &mut self.program_file, cfg.program_file.map(Some));
+		layer(&ex, "web_ui_dir", &mut self.web_ui_dir, cfg.web_ui_dir.map(Some));
+		layer(&ex, "http_dscp", &mut self.http_dscp, cfg.http_dscp.as_deref().map(parse_dscp).transpose()?.map(Some));
+		if let Some(p2p) = cfg.p2p {
+			layer(&ex, "p2p_listen", &mut self.p2p_listen, p2p.listen);
+			layer(&ex, "p2p_bootstrap", &mut self.p2p_bootstrap, p2p.bootstrap);
+			layer(&ex, "p2p_mdns", &mut self.p2p_mdns, p2p.mdns);
+			layer(&ex, "p2p_transport", &mut self.p2p_transport, p2p.transport);
+			layer(&ex, "p2p_key_path", &mut self.p2p_key_path, p2p.key_path.map(Some));
+			layer(&ex, "p2p_key_seed", &mut self.p2p_key_seed, p2p.key_seed.map(Some));
+			layer(&ex, "p2p_key_credential", &mut self.p2p_key_credential, p2p.key_credential.map(Some));
+			layer(&ex, "p2p_ban_path", &mut self.p2p_ban_path, p2p.ban_path.map(Some));
+		}
+		self.explicit = ex;
+		Ok(())
+	}
  }
 
+impl Config {
+	/// The effective configuration as YAML with secrets masked, for --print-config
+	pub fn to_redacted_yaml(&self) -> anyhow::Result<String> {
+		Ok(serde_yaml::to_string(self)?)
+	}
+
+	/// Startup checks that span several settings
+	pub fn validate(&self) -> anyhow::Result<()> {
+		if let Some(path) = &self.signer_socket {
+			if self.owner_signing_key.is_some() {
+				anyhow::bail!("--signer-socket replaces --owner-secret-key; set only one of them");
+			}
+			if self.owner_public_key.is_none() {
+				anyhow::bail!("--signer-socket {} needs --owner-public-key, the public half of the key the signer holds", path);
+			}
+		} else if self.owner_public_key.is_some() && self.owner_signing_key.is_none() {
+			anyhow::bail!("--owner-public-key needs --signer-socket (or a matching --owner-secret-key) to sign with");
+		}
+		if self.local_station.is_some() && self.owner_signing_key.is_none() && self.signer_socket.is_none() && !self.allow_ephemeral_owner_key {
+			anyhow::bail!(
+				"station mode requires a persistent owner key: set --owner-secret-key (SHORTWAVE_OWNER_SECRET_KEY) \
+				 or --signer-socket so the station keeps its identity across restarts, or pass --allow-ephemeral-owner-key to run with a throwaway key"
+			);
+		}
+		if self.release_without_source_secs.is_some() && self.local_station.is_none() {
+			anyhow::bail!("--release-without-source-secs only applies in station mode (--name and --frequency)");
+		}
+		if let Some(dir) = &self.web_ui_dir {
+			if !std::path::Path::new(dir).join("index.html").is_file() {
+				anyhow::bail!("--web-ui-dir {} has no index.html", dir);
+			}
+		}
+		Ok(())
+	}
+
+	/// Feature flags derived from the effective config, sorted for stable output
+	pub fn capabilities(&self) -> Vec<String> {
+		let mut caps: Vec<&str> = vec!["sse-events", "now-playing", "source-put", "ws-source", "p2p-gossip", "owner-profiles", "metrics"];
+		if self.local_station.is_some() { caps.push("station"); }
+		if self.source_token.is_some() { caps.push("source-auth"); }
+		if self.admin_token.is_some() { caps.push("admin-api"); caps.push("peer-ban"); }
+		if self.cluster_secret.is_some() { caps.push("cluster-auth"); }
+		if self.ipc_socket.is_some() { caps.push("ipc-now-playing"); }
+		if self.audio_ipc_socket.is_some() { caps.push("audio-ipc"); }
+		if self.audio_tcp_ingest.is_some() { caps.push("audio-tcp"); }
+		if self.blocklist_url.is_some() { caps.push("ip-blocklist"); }
+		if self.rate_limit_per_sec.is_some() { caps.push("rate-limit"); }
+		if self.now_webhook.is_some() { caps.push("now-webhook"); }
+		if self.event_webhook.is_some() { caps.push("event-webhook"); }
+		if !self.sources.is_empty() { caps.push("source-failover"); }
+		if self.burst_bytes > 0 { caps.push("stream-burst"); }
+		if self.dvr_secs > 0 && self.dvr_max_bytes > 0 { caps.push("stream-rewind"); }
+		if !self.allowed_owners.is_empty() || self.allowed_owners_file.is_some() { caps.push("owner-allowlist"); }
+		if self.ad_ack_sample > 0.0 { caps.push("ad-ack"); }
+		if self.release_without_source_secs.is_some() { caps.push("source-gated-advertising"); }
+		if self.p2p_mdns { caps.push("mdns"); }
+		if self.network_id.is_some() { caps.push("network-id"); }
+		if self.network_time { caps.push("network-time"); }
+		if self.stream_healthcheck_secs > 0 { caps.push("stream-healthcheck"); }
+		if self.local_station.is_some() && self.program_file.is_some() { caps.push("program-info"); }
+		if self.local_station.as_ref().is_some_and(|ls| !ls.stream_endpoints.is_empty()) { caps.push("stream-endpoints"); }
+		if self.web_ui_dir.is_some() { caps.push("web-ui"); }
+		if self.registry_store != RegistryStoreSpec::Memory { caps.push("registry-persistence"); }
+		if matches!(self.p2p_transport, P2PTransport::Quic | P2PTransport::Both) { caps.push("p2p-quic"); }
+		let mut caps: Vec<String> = caps.into_iter().map(String::from).collect();
+		caps.sort();
+		caps
+	}
+}
+
 #[derive(Debug, Deserialize, Clone)]
 struct FileStation {
 	pub name: String,
-	pub frequency: BigDecimal,
+	pub frequency: Frequency,
 	pub station_id: Option<Uuid>,
+	/// Per-station override of the stream URL template (same placeholders)
+	pub stream_url: Option<String>,
+	/// Extra weighted edges serving the stream
+	pub stream_endpoints: Option<Vec<StreamEndpoint>>,
+	pub logo_url: Option<String>,
+	pub genre: Option<String>,
+	pub latitude: Option<f64>,
+	pub longitude: Option<f64>,
+	pub country: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -189,76 +971,397 @@ struct FileP2P {
 	pub listen: Option<Vec<String>>,
 	pub bootstrap: Option<Vec<String>>,
 	pub mdns: Option<bool>,
+	pub transport: Option<P2PTransport>,
 	pub key_path: Option<String>,
+	pub key_seed: Option<String>,
+	pub key_credential: Option<String>,
+	pub ban_path: Option<String>,
+}
+
+/// `bind:` takes one address or a list
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+enum FileBind {
+	One(String),
+	Many(Vec<String>),
+}
+
+impl FileBind {
+	fn into_vec(self) -> Vec<String> {
+		match self {
+			FileBind::One(s) => vec![s],
+			FileBind::Many(v) => v,
+		}
+	}
 }
 
 #[derive(Debug, Deserialize, Clone)]
 struct FileConfig {
-	pub bind: Option<String>,
-	pub public_url: String,
+	pub bind: Option<FileBind>,
+	pub public_url: Option<String>,
 	pub node_id: Option<Uuid>,
 	pub source_token: Option<String>,
+	pub source_token_credential: Option<String>,
+	pub admin_token: Option<String>,
+	pub cluster_secret: Option<String>,
 	pub station: Option<FileStation>,
+	pub stream_url_template: Option<String>,
 	pub advertise_ttl_secs: Option<u32>,
+	pub listener_heartbeat_secs: Option<u32>,
+	pub heartbeat_jitter: Option<f64>,
+	pub release_without_source_secs: Option<u64>,
 	pub owner_secret_key: Option<String>,
+	pub owner_key_credential: Option<String>,
+	pub owner_public_key: Option<String>,
+	pub signer_socket: Option<String>,
 	pub max_frequencies_per_owner: Option<u32>,
+	pub owner_caps: Option<HashMap<String, u32>>,
+	pub owner_caps_file: Option<String>,
 	pub ipc_socket: Option<String>,
+	pub ipc_max_connections: Option<usize>,
+	pub ipc_required: Option<bool>,
 	pub audio_ipc_socket: Option<String>,
+	pub audio_ipc_chunk_bytes: Option<usize>,
+	pub audio_tcp_ingest: Option<String>,
+	pub audio_tcp_token: Option<String>,
 	pub blocklist_url: Option<String>,
 	pub blocklist_refresh_secs: Option<u32>,
+	pub rate_limit_per_sec: Option<f64>,
+	pub rate_limit_burst: Option<u32>,
+	pub now_webhook: Option<String>,
+	pub now_webhook_concurrency: Option<usize>,
+	pub event_webhook: Option<String>,
+	pub event_webhook_events: Option<Vec<String>>,
+	pub event_webhook_queue: Option<usize>,
+	pub allowed_owners: Option<Vec<String>>,
+	pub allowed_owners_file: Option<String>,
+	pub owner_name: Option<String>,
+	pub owner_url: Option<String>,
+	pub owner_avatar_url: Option<String>,
+	pub sources: Option<Vec<SourceSpec>>,
+	pub source_hold_down_secs: Option<u32>,
+	pub burst_bytes: Option<usize>,
+	pub dvr_secs: Option<u32>,
+	pub dvr_max_bytes: Option<usize>,
+	pub audio_slow_listener_policy: Option<SlowListenerPolicy>,
+	pub audio_debug_capture: Option<String>,
+	pub audio_debug_capture_max_bytes: Option<u64>,
+	pub audio_debug_capture_max_secs: Option<u64>,
+	pub min_free_memory_mb: Option<u64>,
+	pub min_free_disk_mb: Option<u64>,
+	pub dead_peer_timeout_secs: Option<u64>,
+	pub stream_healthcheck_secs: Option<u64>,
+	pub source_ws_max_kbps: Option<u32>,
+	pub max_ttl_secs: Option<u32>,
+	pub max_ad_age_fraction: Option<f64>,
+	pub min_canonical_version: Option<u32>,
+	pub ad_ack_sample: Option<f64>,
+	pub verify_concurrency: Option<usize>,
+	pub sign_concurrency: Option<usize>,
+	pub max_registry_size: Option<usize>,
+	pub max_owners: Option<usize>,
+	pub registry_full_policy: Option<RegistryFullPolicy>,
+	pub registry_store: Option<String>,
+	pub registry_path: Option<String>,
+	pub max_now_playing_bytes: Option<usize>,
+	pub request_timeout_secs: Option<u32>,
+	pub peers: Option<Vec<String>>,
+	pub sync_min: Option<Frequency>,
+	pub sync_max: Option<Frequency>,
+	pub allow_ephemeral_owner_key: Option<bool>,
+	pub network_id: Option<String>,
+	pub network_time: Option<bool>,
+	pub station_hold_path: Option<String>,
+	pub program_file: Option<String>,
+	pub web_ui_dir: Option<String>,
+	pub http_dscp: Option<String>,
 	pub p2p: Option<FileP2P>,
 }
 
-fn load_config_file(path: &str) -> anyhow::Result<Config> {
-	let text = std::fs::read_to_string(path)?;
-	let cfg: FileConfig = serde_yaml::from_str(&text)?;
-	let node_id = cfg.node_id.unwrap_or_else(Uuid::new_v4);
-	let bind = cfg.bind.unwrap_or_else(|| "0.0.0.0:8080".to_string());
-	let public_url = cfg.public_url;
-	let local_station = match cfg.station {
-		Some(fs) => {
-			let station_id = fs.station_id.unwrap_or_else(Uuid::new_v4);
-			let stream_url = format!("{}/stream", public_url.trim_end_matches('/'));
-			Some(LocalStationConfig {
-				station_id,
-				name: fs.name,
-				frequency: fs.frequency,
-				stream_url,
-			})
+const REDACTED: &str = "<redacted>";
+
+fn redact<S: Serializer>(value: &Option<String>, s: S) -> Result<S::Ok, S::Error> {
+	value.as_ref().map(|_| REDACTED).serialize(s)
+}
+
+/// The secret stays masked; the public half is what operators compare against the registry
+fn redact_signing_key<S: Serializer>(key: &Option<SigningKey>, s: S) -> Result<S::Ok, S::Error> {
+	key.as_ref()
+		.map(|k| format!("{} (public key {})", REDACTED, B64.encode(k.verifying_key().to_bytes())))
+		.serialize(s)
+}
+
+fn serialize_decimal<S: Serializer>(value: &BigDecimal, s: S) -> Result<S::Ok, S::Error> {
+	s.serialize_str(&value.to_string())
+}
+
+fn serialize_opt_decimal<S: Serializer>(value: &Option<BigDecimal>, s: S) -> Result<S::Ok, S::Error> {
+	value.as_ref().map(|v| v.to_string()).serialize(s)
+}
+
+/// Use `value` from the config file unless the setting was given on the command line or in the environment
+fn layer<T>(explicit: &HashSet<String>, id: &str, slot: &mut T, value: Option<T>) {
+	if let Some(v) = value {
+		if !explicit.contains(id) {
+			*slot = v;
 		}
-		None => None,
+	}
+}
+
+/// Parse a DSCP codepoint from its PHB name (EF, AFxy, CSx, DF) or a number 0-63
+fn parse_dscp(s: &str) -> anyhow::Result<u8> {
+	let s = s.trim().to_ascii_uppercase();
+	let v = match s.as_str() {
+		"DF" | "BE" => 0,
+		"EF" => 46,
+		_ if s.starts_with("CS") => s[2..].parse::<u8>().ok().filter(|n| *n <= 7).map(|n| n << 3)
+			.ok_or_else(|| anyhow::anyhow!("invalid DSCP class '{}'", s))?,
+		_ if s.starts_with("AF") && s.len() == 4 => {
+			let (class, drop) = (s.as_bytes()[2].wrapping_sub(b'0'), s.as_bytes()[3].wrapping_sub(b'0'));
+			if !(1..=4).contains(&class) || !(1..=3).contains(&drop) {
+				anyhow::bail!("invalid DSCP class '{}'", s);
+			}
+			(class << 3) | (drop << 1)
+		}
+		_ => s.parse::<u8>().map_err(|_| anyhow::anyhow!("invalid DSCP '{}'", s))?,
 	};
-	let owner_signing_key = match cfg.owner_secret_key {
-		Some(sk_b64) => {
-			let bytes = B64.decode(sk_b64)?;
-			let sk = SigningKey::from_bytes(bytes.as_slice().try_into()?);
-			Some(sk)
+	if v > 63 {
+		anyhow::bail!("DSCP must be 0-63, got {}", v);
+	}
+	Ok(v)
+}
+
+const DEFAULT_AUDIO_IPC_CHUNK_BYTES: usize = 16 * 1024;
+
+const DEFAULT_MAX_NOW_PLAYING_BYTES: usize = 16 * 1024;
+
+/// `bind` must be a literal ip:port (hostnames aren't resolved for listening)
+fn normalize_bind(bind: &str) -> anyhow::Result<String> {
+	let addr: std::net::SocketAddr = bind.trim().parse()
+		.map_err(|_| anyhow::anyhow!("invalid bind address '{}': expected ip:port, e.g. 0.0.0.0:8080 or [::]:8080", bind))?;
+	Ok(addr.to_string())
+}
+
+/// Every HTTP bind address, normalized and deduplicated in the order given
+fn normalize_binds(binds: Vec<String>) -> anyhow::Result<Vec<String>> {
+	let mut out: Vec<String> = Vec::with_capacity(binds.len());
+	for b in binds {
+		if b.trim().is_empty() { continue; }
+		let addr = normalize_bind(&b)?;
+		if !out.contains(&addr) { out.push(addr); }
+	}
+	if out.is_empty() {
+		anyhow::bail!("invalid --bind: at least one address is required");
+	}
+	Ok(out)
+}
+
+/// Absolute http(s) base URL without query or fragment; the trailing slash is dropped
+fn normalize_public_url(url: &str) -> anyhow::Result<String> {
+	let parsed = reqwest::Url::parse(url.trim())
+		.map_err(|e| anyhow::anyhow!("invalid public_url '{}': {}", url, e))?;
+	if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+		anyhow::bail!("invalid public_url '{}': expected an http(s) URL with a host", url);
+	}
+	if parsed.query().is_some() || parsed.fragment().is_some() {
+		anyhow::bail!("invalid public_url '{}': must not have a query or fragment", url);
+	}
+	Ok(parsed.as_str().trim_end_matches('/').to_string())
+}
+
+fn normalize_webhook_url(option: &str, url: &str) -> anyhow::Result<String> {
+	let parsed = reqwest::Url::parse(url.trim())
+		.map_err(|e| anyhow::anyhow!("invalid {} '{}': {}", option, url, e))?;
+	if !matches!(parsed.scheme(), "http" | "https") {
+		anyhow::bail!("invalid {} '{}': expected an http(s) URL", option, url);
+	}
+	Ok(parsed.to_string())
+}
+
+fn validate_fraction(flag: &str, fraction: f64) -> anyhow::Result<f64> {
+	if !(0.0..=1.0).contains(&fraction) {
+		anyhow::bail!("invalid {} {}: expected a value between 0 and 1", flag, fraction);
+	}
+	Ok(fraction)
+}
+
+fn validate_rate_limit(rate: f64) -> anyhow::Result<f64> {
+	if !(rate.is_finite() && rate > 0.0) {
+		anyhow::bail!("invalid --rate-limit-per-sec {}: expected a positive number", rate);
+	}
+	Ok(rate)
+}
+
+/// `--heartbeat-jitter`; past half the interval a heartbeat could come twice as often as configured
+fn validate_heartbeat_jitter(jitter: f64) -> anyhow::Result<f64> {
+	if !(0.0..=0.5).contains(&jitter) {
+		anyhow::bail!("invalid --heartbeat-jitter {}: expected a value between 0 and 0.5", jitter);
+	}
+	Ok(jitter)
+}
+
+/// Registry event type names for --event-webhook-events, lowercased and deduplicated
+fn validate_event_types(types: Vec<String>) -> anyhow::Result<Vec<String>> {
+	let mut out: Vec<String> = Vec::new();
+	for raw in types {
+		let t = raw.trim().to_ascii_lowercase();
+		if t.is_empty() {
+			continue;
+		}
+		if !matches!(t.as_str(), "upsert" | "delete") {
+			anyhow::bail!("invalid --event-webhook-events entry '{}': expected upsert or delete", raw);
+		}
+		if !out.contains(&t) {
+			out.push(t);
+		}
+	}
+	Ok(out)
+}
+
+/// Parse each multiaddr (naming the bad entry) and check it is usable with the enabled transports
+fn normalize_multiaddrs(option: &str, addrs: Vec<String>, transport: P2PTransport) -> anyhow::Result<Vec<String>> {
+	use libp2p::multiaddr::{Multiaddr, Protocol};
+	addrs.into_iter().map(|raw| {
+		let ma: Multiaddr = raw.trim().parse()
+			.map_err(|e| anyhow::anyhow!("invalid {} multiaddr '{}': {}", option, raw, e))?;
+		let quic = ma.iter().any(|p| matches!(p, Protocol::QuicV1 | Protocol::Quic));
+		let tcp = ma.iter().any(|p| matches!(p, Protocol::Tcp(_)));
+		let memory = ma.iter().any(|p| matches!(p, Protocol::Memory(_)));
+		match (memory, transport) {
+			(true, P2PTransport::Memory) => return Ok(ma.to_string()),
+			(false, P2PTransport::Memory) => anyhow::bail!("invalid {} multiaddr '{}': --p2p-transport memory expects /memory/<port>", option, raw),
+			(true, _) => anyhow::bail!("{} multiaddr '{}' is in-memory but --p2p-transport isn't memory", option, raw),
+			(false, _) => {}
+		}
+		match (tcp, quic, transport) {
+			(false, false, _) => anyhow::bail!("invalid {} multiaddr '{}': expected /tcp/<port> or /udp/<port>/quic-v1", option, raw),
+			(_, true, P2PTransport::Tcp) => anyhow::bail!("{} multiaddr '{}' is QUIC but --p2p-transport is tcp", option, raw),
+			(true, false, P2PTransport::Quic) => anyhow::bail!("{} multiaddr '{}' is TCP but --p2p-transport is quic", option, raw),
+			_ => Ok(ma.to_string()),
+		}
+	}).collect()
+}
+
+const DEFAULT_STREAM_URL_TEMPLATE: &str = "{public_url}/stream";
+
+/// `--owner-secret-key`: base64 of the 32-byte Ed25519 secret, or of the 64-byte secret+public
+/// keypair, whose public half must then match (a key pasted from the wrong pair fails here)
+fn parse_owner_secret_key(b64: &str) -> anyhow::Result<SigningKey> {
+	let bytes = B64.decode(b64.trim()).map_err(|e| anyhow::anyhow!("invalid --owner-secret-key: not base64: {}", e))?;
+	match bytes.len() {
+		32 => Ok(SigningKey::from_bytes(bytes.as_slice().try_into()?)),
+		64 => SigningKey::from_keypair_bytes(bytes.as_slice().try_into()?)
+			.map_err(|_| anyhow::anyhow!("invalid --owner-secret-key: its public half doesn't belong to its secret half")),
+		n => anyhow::bail!("invalid --owner-secret-key: expected a 32-byte Ed25519 secret key (or 64-byte keypair), got {} bytes", n),
+	}
+}
+
+/// `--owner-public-key`; when a secret key is configured too it has to be that key's public half
+fn parse_owner_public_key(b64: &str, secret: Option<&SigningKey>) -> anyhow::Result<String> {
+	let vk = parse_public_key_b64(b64.trim()).map_err(|e| anyhow::anyhow!("invalid --owner-public-key: {}", e))?;
+	if secret.is_some_and(|sk| sk.verifying_key() != vk) {
+		anyhow::bail!("--owner-public-key doesn't match --owner-secret-key");
+	}
+	Ok(b64.trim().to_string())
+}
+
+/// Expand a stream URL template for a local station and check the result is an absolute http(s) URL
+fn render_stream_url(template: &str, public_url: &str, frequency: &BigDecimal, station_id: Uuid) -> anyhow::Result<String> {
+	let url = template
+		.replace("{public_url}", public_url.trim_end_matches('/'))
+		.replace("{frequency}", &normalize_frequency_key(frequency))
+		.replace("{station_id}", &station_id.to_string());
+	let parsed = reqwest::Url::parse(&url)
+		.map_err(|e| anyhow::anyhow!("invalid stream URL '{}' from template '{}': {}", url, template, e))?;
+	if !matches!(parsed.scheme(), "http" | "https") {
+		anyhow::bail!("stream URL '{}' must use http or https", url);
+	}
+	Ok(url)
+}
+
+fn validate_owner_keys(keys: Vec<String>) -> anyhow::Result<Vec<String>> {
+	let mut out = Vec::with_capacity(keys.len());
+	for k in keys {
+		let k = k.trim().to_string();
+		if k.is_empty() { continue; }
+		parse_public_key_b64(&k).map_err(|e| anyhow::anyhow!("invalid allowed owner key '{}': {}", k, e))?;
+		out.push(k);
+	}
+	Ok(out)
+}
+
+/// Parses `<pubkey>=<cap>`; the key's base64 padding also uses '=', so split on the last one
+fn parse_owner_cap(s: &str) -> anyhow::Result<(String, u32)> {
+	let (key, cap) = s.rsplit_once('=')
+		.ok_or_else(|| anyhow::anyhow!("invalid owner cap '{}': expected <pubkey>=<cap>", s))?;
+	let cap = cap.trim().parse::<u32>()
+		.map_err(|e| anyhow::anyhow!("invalid owner cap '{}': {}", s, e))?;
+	Ok((key.trim().to_string(), cap))
+}
+
+fn validate_owner_caps(caps: HashMap<String, u32>) -> anyhow::Result<HashMap<String, u32>> {
+	let mut out = HashMap::with_capacity(caps.len());
+	for (k, cap) in caps {
+		let k = k.trim().to_string();
+		parse_public_key_b64(&k).map_err(|e| anyhow::anyhow!("invalid owner cap key '{}': {}", k, e))?;
+		out.insert(k, cap);
+	}
+	Ok(out)
+}
+
+fn validate_network_id(id: Option<String>) -> anyhow::Result<Option<String>> {
+	let Some(id) = id else { return Ok(None) };
+	if id.is_empty() || id.len() > 64 || !id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+		anyhow::bail!("invalid --network-id '{}': use 1-64 letters, digits, '-', '_' or '.'", id);
+	}
+	Ok(Some(id))
+}
+
+/// Replace `value` with the systemd credential `name` when one is given. Outside systemd
+/// ($CREDENTIALS_DIRECTORY unset) an explicitly configured value is kept, otherwise it's an error.
+fn resolve_credential(flag: &str, name: Option<&str>, value: &mut Option<String>) -> anyhow::Result<()> {
+	let Some(name) = name else { return Ok(()) };
+	if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+		anyhow::bail!("invalid {} '{}': expected a credential name, not a path", flag, name);
+	}
+	let Some(dir) = std::env::var_os("CREDENTIALS_DIRECTORY") else {
+		if value.is_some() {
+			tracing::warn!("{} set but $CREDENTIALS_DIRECTORY is not (not running under systemd with LoadCredential=?); using the directly configured value", flag);
+			return Ok(());
 		}
-		None => None,
+		anyhow::bail!("{} requires $CREDENTIALS_DIRECTORY, which systemd sets for units with LoadCredential=/SetCredential=; outside systemd pass the secret directly", flag);
 	};
-	let p2p_listen = cfg.p2p.as_ref().and_then(|p| p.listen.clone()).unwrap_or_default();
-	let p2p_bootstrap = cfg.p2p.as_ref().and_then(|p| p.bootstrap.clone()).unwrap_or_default();
-	let p2p_mdns = cfg.p2p.as_ref().and_then(|p| p.mdns).unwrap_or(true);
-	let p2p_key_path = cfg.p2p.and_then(|p| p.key_path);
-	Ok(Config {
-		node_id,
-		bind,
-		public_url,
-		peers: Vec::new(),
-		source_token: cfg.source_token,
-		local_station,
-		advertise_ttl_secs: cfg.advertise_ttl_secs.unwrap_or(60).max(10),
-		owner_signing_key,
-		max_frequencies_per_owner: cfg.max_frequencies_per_owner.unwrap_or(3).max(1),
-		ipc_socket: cfg.ipc_socket,
-		audio_ipc_socket: cfg.audio_ipc_socket,
-		blocklist_url: cfg.blocklist_url,
-		blocklist_refresh_secs: cfg.blocklist_refresh_secs.unwrap_or(600).max(30),
-		p2p_listen,
-		p2p_bootstrap,
-		p2p_mdns,
-		p2p_key_path,
-	})
+	let path = std::path::Path::new(&dir).join(name);
+	let secret = std::fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("{}: cannot read credential {}: {}", flag, path.display(), e))?;
+	// Credential files usually end with a newline; the secret itself must not
+	*value = Some(secret.trim_end_matches(['\r', '\n']).to_string());
+	Ok(())
+}
+
+pub fn read_owner_caps_file(path: &str) -> anyhow::Result<HashMap<String, u32>> {
+	let text = std::fs::read_to_string(path)?;
+	let caps: Option<HashMap<String, u32>> = serde_yaml::from_str(&text)?;
+	validate_owner_caps(caps.unwrap_or_default())
 }
 
+/// An empty file means no program is on
+pub fn read_program_file(path: &str) -> anyhow::Result<Option<ProgramInfo>> {
+	let text = std::fs::read_to_string(path)?;
+	let program: Option<ProgramInfo> = serde_yaml::from_str(&text)?;
+	if let Some(p) = &program {
+		p.validate().map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+	}
+	Ok(program)
+}
 
+/// Read an allowed-owners file: one base64 public key per line, '#' comments allowed
+pub fn read_allowed_owners_file(path: &str) -> anyhow::Result<Vec<String>> {
+	let text = std::fs::read_to_string(path)?;
+	let mut keys = Vec::new();
+	for line in text.lines() {
+		let s = match line.split_once('#') { Some((left, _)) => left, None => line }.trim();
+		if s.is_empty() { continue; }
+		keys.push(s.to_string());
+	}
+	validate_owner_keys(keys)
+}
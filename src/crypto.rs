@@ -1,5 +1,6 @@
  use ed25519_dalek::{Signature, SigningKey, VerifyingKey, Signer, Verifier};
  use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use sha2::{Digest, Sha256};
 
 pub fn parse_public_key_b64(b64: &str) -> anyhow::Result<VerifyingKey> {
 	let bytes = B64.decode(b64)?;
@@ -26,26 +27,195 @@ pub fn parse_sig_b64(b64: &str) -> anyhow::Result<Signature> {
  	sk.sign(data)
  }
 
+ /// Compare a presented secret (bearer token, cluster secret) with the configured one. Digests are
+ /// compared so the time taken doesn't depend on how much of the secret a guess got right.
+ pub fn tokens_match(given: &str, expected: &str) -> bool {
+ 	Sha256::digest(given.as_bytes()) == Sha256::digest(expected.as_bytes())
+ }
+
  pub fn verify_bytes(vk: &VerifyingKey, data: &[u8], sig: &Signature) -> anyhow::Result<()> {
  	vk.verify(data, sig).map_err(|e| anyhow::anyhow!("signature verification failed: {}", e))
  }
 
+ /// Round-trip a signature the way peers check it (base64 key and signature, parsed back and
+ /// verified), and make sure a changed message is refused, so a broken key fails at startup
+ /// instead of producing advertisements nobody accepts
+ pub fn check_signing_key(sk: &SigningKey) -> anyhow::Result<()> {
+ 	let probe = self_test_probe(&sk.verifying_key());
+ 	let vk = parse_public_key_b64(&encode_public_key_b64(&sk.verifying_key()))?;
+ 	let sig = parse_sig_b64(&encode_signature_b64(&sign_bytes(sk, &probe)))?;
+ 	verify_bytes(&vk, &probe, &sig)?;
+ 	let mut tampered = probe;
+ 	tampered.push(b'!');
+ 	if verify_bytes(&vk, &tampered, &sig).is_ok() {
+ 		anyhow::bail!("signature over a modified message verified");
+ 	}
+ 	Ok(())
+ }
+
+ /// Message signed by startup self-tests; domain-separated so it can't be replayed as anything else
+ pub fn self_test_probe(vk: &VerifyingKey) -> Vec<u8> {
+ 	format!("{}:self-test:{}", signing_domain(None), encode_public_key_b64(vk)).into_bytes()
+ }
+
+ /// Leading domain of every signed payload: `shortwave` on the default network, `shortwave@<id>`
+ /// on one named by `--network-id`, so a signature captured on one network never verifies on
+ /// another. Nodes keep theirs in `AppState::signing_domain`.
+ pub fn signing_domain(network_id: Option<&str>) -> String {
+ 	match network_id {
+ 		Some(id) => format!("shortwave@{id}"),
+ 		None => "shortwave".to_string(),
+ 	}
+ }
+
+ /// Namespace of an advertisement's signed bytes per canonical version; None for versions this build
+ /// doesn't know. Version 0 is the original layout, 2 adds the message id, 3 replaces the
+ /// `key=value;` layout with canonical JSON (`canonicalize_ad_json`).
+ pub fn ad_namespace(canonical_version: u32) -> Option<&'static str> {
+ 	match canonical_version {
+ 		0 => Some("advertise"),
+ 		2 => Some("advertise.v2"),
+ 		3 => Some("advertise.v3"),
+ 		_ => None,
+ 	}
+ }
+
+ /// Canonical version 3: the signed fields as one JSON object with keys sorted and no whitespace.
+ /// Every value is JSON-encoded, so a `;`, `=` or quote inside a name or URL stays inside its field.
+ pub fn canonicalize_ad_json(domain: &str, namespace: &str, fields: &serde_json::Value) -> Vec<u8> {
+ 	let mut out = format!("{domain}:{namespace}:").into_bytes();
+ 	write_canonical_json(fields, &mut out);
+ 	out
+ }
+
+ /// Sorts object keys itself rather than trusting the map type, since a dependency enabling
+ /// serde_json's `preserve_order` would otherwise change what gets signed
+ fn write_canonical_json(value: &serde_json::Value, out: &mut Vec<u8>) {
+ 	use serde_json::Value;
+ 	match value {
+ 		Value::Object(map) => {
+ 			let mut keys: Vec<&String> = map.keys().collect();
+ 			keys.sort();
+ 			out.push(b'{');
+ 			for (i, key) in keys.into_iter().enumerate() {
+ 				if i > 0 {
+ 					out.push(b',');
+ 				}
+ 				let _ = serde_json::to_writer(&mut *out, key);
+ 				out.push(b':');
+ 				write_canonical_json(&map[key], out);
+ 			}
+ 			out.push(b'}');
+ 		}
+ 		Value::Array(items) => {
+ 			out.push(b'[');
+ 			for (i, item) in items.iter().enumerate() {
+ 				if i > 0 {
+ 					out.push(b',');
+ 				}
+ 				write_canonical_json(item, out);
+ 			}
+ 			out.push(b']');
+ 		}
+ 		scalar => {
+ 			let _ = serde_json::to_writer(&mut *out, scalar);
+ 		}
+ 	}
+ }
+
+ /// Binds the advertisement's message id (canonical v2), so a signature can't be replayed under another id
+ pub fn append_ad_message_id(msg: &mut Vec<u8>, message_id: &str) {
+ 	msg.extend_from_slice(b";mid=");
+ 	msg.extend_from_slice(message_id.as_bytes());
+ }
+
+ /// A `sequence` of 0 means a legacy (unsequenced) advertisement and keeps the original byte layout
+ #[allow(clippy::too_many_arguments)]
  pub fn canonicalize_ad_bytes(
+ 	domain: &str,
  	namespace: &str,
  	frequency_key: &str,
  	station_id: &str,
  	stream_url: &str,
  	advertised_at_rfc3339: &str,
  	ttl_seconds: u32,
+ 	sequence: u64,
  ) -> Vec<u8> {
+ 	let mut s = format!(
+ 		"{domain}:{namespace}:freq={frequency_key};station={station_id};url={stream_url};at={advertised_at_rfc3339};ttl={ttl_seconds}",
+ 	);
+ 	if sequence > 0 {
+ 		s.push_str(&format!(";seq={sequence}"));
+ 	}
+ 	s.into_bytes()
+ }
+
+ /// Optional advertisement extensions are appended after `seq` only when present, so ads
+ /// without them keep their original bytes and still verify. `program_json` is `ProgramInfo::canonical_json`,
+ /// `format_json` is `types::stream_format_json` (versioned, as its fields may grow), `endpoints_json`
+ /// is `types::stream_endpoints_json`; the logo URL is JSON-quoted like profile URLs, and
+ /// `location_json` is `StationLocation::canonical_json`.
+ pub fn append_ad_extensions(
+ 	msg: &mut Vec<u8>,
+ 	program_json: Option<&str>,
+ 	format_json: Option<&str>,
+ 	endpoints_json: Option<&str>,
+ 	logo_url: Option<&str>,
+ 	location_json: Option<&str>,
+ ) {
+ 	if let Some(program) = program_json {
+ 		msg.extend_from_slice(b";program=");
+ 		msg.extend_from_slice(program.as_bytes());
+ 	}
+ 	if let Some(format) = format_json {
+ 		msg.extend_from_slice(b";format.v1=");
+ 		msg.extend_from_slice(format.as_bytes());
+ 	}
+ 	if let Some(endpoints) = endpoints_json {
+ 		msg.extend_from_slice(b";endpoints.v1=");
+ 		msg.extend_from_slice(endpoints.as_bytes());
+ 	}
+ 	if let Some(logo) = logo_url {
+ 		msg.extend_from_slice(b";logo.v1=");
+ 		msg.extend_from_slice(serde_json::to_string(logo).unwrap_or_default().as_bytes());
+ 	}
+ 	if let Some(location) = location_json {
+ 		msg.extend_from_slice(b";location.v1=");
+ 		msg.extend_from_slice(location.as_bytes());
+ 	}
+ }
+
+ /// Values are JSON-quoted since names and URLs may contain the `;`/`=` separators
+ pub fn canonicalize_profile_bytes(
+ 	domain: &str,
+ 	owner_public_key: &str,
+ 	name: &str,
+ 	url: Option<&str>,
+ 	avatar_url: Option<&str>,
+ 	updated_at_rfc3339: &str,
+ ) -> Vec<u8> {
+ 	let q = |v: Option<&str>| serde_json::to_string(&v).unwrap_or_default();
  	format!(
- 		"shortwave:{namespace}:freq={frequency_key};station={station_id};url={stream_url};at={advertised_at_rfc3339};ttl={ttl_seconds}"
- 	)
- 	.into_bytes()
+ 		"{domain}:owner-profile:owner={owner_public_key};name={};url={};avatar={};at={updated_at_rfc3339}",
+ 		q(Some(name)),
+ 		q(url),
+ 		q(avatar_url),
+ 	).into_bytes()
  }
 
- pub fn canonicalize_release_bytes(namespace: &str, frequency_key: &str, station_id: &str) -> Vec<u8> {
- 	format!("shortwave:{namespace}:freq={frequency_key};station={station_id}").into_bytes()
+ /// Binds the station's sequence and the release time, so a captured release can't be replayed
+ /// once the station has advertised again
+ pub fn canonicalize_release_bytes(
+ 	domain: &str,
+ 	namespace: &str,
+ 	frequency_key: &str,
+ 	station_id: &str,
+ 	sequence: u64,
+ 	released_at_rfc3339: &str,
+ ) -> Vec<u8> {
+ 	format!(
+ 		"{domain}:{namespace}:freq={frequency_key};station={station_id};seq={sequence};at={released_at_rfc3339}",
+ 	).into_bytes()
  }
 
 
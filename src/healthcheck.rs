@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tracing::{debug, warn};
+
+use crate::state::AppState;
+
+/// How long a stream gets to answer with a status line and headers
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Streams requested at once during a round
+const CHECK_CONCURRENCY: usize = 16;
+
+pub fn client() -> reqwest::Result<reqwest::Client> {
+	reqwest::Client::builder().timeout(CHECK_TIMEOUT).build()
+}
+
+/// Request each listed station's stream once. Reachable means a success status; the body is never
+/// read, so the station sees a listener connect and leave. The stations that failed replace the
+/// previous round's set, so a station that recovers (or leaves the registry) is cleared.
+pub async fn check_streams(state: &AppState, client: &reqwest::Client) {
+	let stations = state.snapshot_registry().await;
+	let checked = stations.len();
+	let unreachable: HashMap<_, _> = futures_util::stream::iter(stations)
+		.map(|a| async move {
+			let reachable = match client.get(&a.stream_url).send().await {
+				Ok(resp) if resp.status().is_success() => true,
+				Ok(resp) => {
+					debug!(station_id=%a.station_id, url=%a.stream_url, status=%resp.status(), "stream healthcheck failed");
+					false
+				}
+				Err(err) => {
+					debug!(station_id=%a.station_id, url=%a.stream_url, error=%err, "stream healthcheck failed");
+					false
+				}
+			};
+			(!reachable).then_some((a.station_id, a.stream_url))
+		})
+		.buffer_unordered(CHECK_CONCURRENCY)
+		.filter_map(std::future::ready)
+		.collect()
+		.await;
+	debug!(checked, unreachable = unreachable.len(), "stream healthcheck round done");
+	state.set_unreachable_streams(unreachable);
+}
+
+/// Run `check_streams` every `interval`
+pub fn spawn_stream_healthcheck(state: Arc<AppState>, interval: Duration) {
+	tokio::spawn(async move {
+		let client = match client() {
+			Ok(c) => c,
+			Err(err) => {
+				warn!(error=%err, "failed to build http client for stream healthchecks");
+				return;
+			}
+		};
+		let mut ticker = tokio::time::interval(interval);
+		loop {
+			ticker.tick().await;
+			check_streams(&state, &client).await;
+		}
+	});
+}
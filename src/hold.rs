@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::types::{normalize_frequency_key, StationAdvertisement};
+
+/// This node's last advertisement, persisted so a restarted station resumes its claim
+/// (same station_id, continuing sequence) instead of looking like a fresh one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationHold {
+	pub station_id: Uuid,
+	/// Normalized frequency key
+	pub frequency: String,
+	pub owner_public_key: String,
+	pub sequence: u64,
+	pub message_id: Uuid,
+	pub advertised_at: DateTime<Utc>,
+}
+
+impl StationHold {
+	pub fn from_advertisement(ad: &StationAdvertisement) -> Self {
+		Self {
+			station_id: ad.station_id,
+			frequency: normalize_frequency_key(&ad.frequency),
+			owner_public_key: ad.owner_public_key.clone(),
+			sequence: ad.sequence,
+			message_id: ad.message_id,
+			advertised_at: ad.advertised_at,
+		}
+	}
+
+	/// The hold was written by the same owner key for the same frequency
+	pub fn matches(&self, owner_public_key: &str, frequency_key: &str) -> bool {
+		self.owner_public_key == owner_public_key && self.frequency == frequency_key
+	}
+}
+
+/// Read a hold file; a missing file is not an error, an unreadable one is logged and ignored
+pub async fn load(path: &str) -> Option<StationHold> {
+	let data = match fs::read(path).await {
+		Ok(d) => d,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+		Err(err) => {
+			warn!(error=%err, path, "failed to read station hold file");
+			return None;
+		}
+	};
+	match serde_json::from_slice(&data) {
+		Ok(hold) => Some(hold),
+		Err(err) => {
+			warn!(error=%err, path, "ignoring invalid station hold file");
+			None
+		}
+	}
+}
+
+/// Write the hold atomically (temp file + rename) so a crash mid-write can't corrupt it
+pub async fn save(path: &str, hold: &StationHold) -> anyhow::Result<()> {
+	let tmp = format!("{}.tmp", path);
+	fs::write(&tmp, serde_json::to_vec_pretty(hold)?).await?;
+	fs::rename(&tmp, path).await?;
+	Ok(())
+}
@@ -9,61 +9,580 @@ use std::net::SocketAddr;
  	Json,
  };
 	use axum::response::sse::Event;
-use axum::middleware::Next;
+use axum::middleware::{self, Next};
+use axum::routing::{any, get, post, put};
+use axum::Router;
+use tower_http::cors::CorsLayer;
+use tower_http::services::{ServeDir, ServeFile};
+use tower_http::timeout::TimeoutLayer;
 use axum::http::Request;
 use axum::extract::connect_info::ConnectInfo;
  use serde::Deserialize;
-use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+use tokio_stream::{wrappers::{errors::BroadcastStreamRecvError, BroadcastStream}, StreamExt};
 use tokio_stream::once;
 use std::pin::Pin;
 use futures_core::Stream;
-use tracing::error;
+use tracing::{debug, error, info, warn};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 
-use crate::state::{AppState};
+use crate::config::{Config, SlowListenerPolicy};
+use crate::state::{AppState, ListenerGuard, RegistryError};
+use crate::ratelimit::ByteThrottle;
 use crate::types::{
-    normalize_frequency_key, ErrorResponse, NodeInfo,
+    normalize_frequency_key, parse_frequency, MAX_BITRATE_KBPS, AdvertiseResponse, AdvertiseState, StationsPage, PeerBanRequest, PeerBans, RegistryEvent, StationAdvertisement, StationTransfer, TransferRequest, ErrorResponse, FeedEvent, FrequencyAvailable, MyStation, SharedEvent, NodeInfo, SpectrumOccupant, SpectrumSlot, StationAssignment, StationStatus, Stats, StationsCount, SyncResponse,
 };
-use bigdecimal::BigDecimal;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use libp2p::PeerId;
 use std::str::FromStr;
+use sha2::{Digest, Sha256};
 
- pub async fn healthz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+/// A listening socket for `addr`; for IPv6, `v6_only` decides whether IPv4-mapped connections are
+/// accepted too rather than leaving it to the platform default (net.ipv6.bindv6only on Linux)
+pub fn bind_http_listener(addr: SocketAddr, v6_only: bool) -> std::io::Result<tokio::net::TcpListener> {
+	let sock = socket2::Socket::new(socket2::Domain::for_address(addr), socket2::Type::STREAM, Some(socket2::Protocol::TCP))?;
+	if addr.is_ipv6() {
+		sock.set_only_v6(v6_only)?;
+	}
+	// Same as tokio's TcpListener::bind, so a restart doesn't trip over TIME_WAIT
+	#[cfg(unix)]
+	sock.set_reuse_address(true)?;
+	sock.bind(&addr.into())?;
+	sock.listen(1024)?;
+	sock.set_nonblocking(true)?;
+	tokio::net::TcpListener::from_std(sock.into())
+}
+
+/// The node's whole HTTP surface: API, streams, ingest and the optional web UI, behind the
+/// blocklist and CORS. Shared by the binary and in-process nodes (see `testing`).
+pub fn router(state: Arc<AppState>, config: &Config) -> Router {
+ 	// Request/response routes get a timeout (408 on expiry)...
+ 	let api = Router::new()
+ 		.route("/api/v1/stations", get(get_stations))
+ 		.route("/api/v1/stations/count", get(get_stations_count))
+ 		.route("/api/v1/stations/:frequency", get(get_station_by_frequency))
+		.route("/api/v1/stations/:frequency/watch", get(watch_frequency_sse))
+		.route("/api/v1/now", get(now_playing))
+		.route("/api/v1/source/status", get(source_status))
+		.route("/api/v1/stats", get(get_stats))
+		.route("/api/v1/peers", get(get_peers))
+		.route("/api/v1/metrics", get(get_metrics))
+		.route("/api/v1/spectrum", get(get_spectrum))
+		.route("/api/v1/sync", get(get_sync))
+		.route("/api/v1/me/stations", get(my_stations))
+		.route("/api/v1/owners/:pubkey", get(get_owner_profile))
+		.route("/api/v1/advertise", post(post_advertise))
+		.route("/api/v1/admin/advertise/pause", post(pause_advertise))
+		.route("/api/v1/admin/advertise/resume", post(resume_advertise))
+		.route("/api/v1/admin/transfer", post(transfer_stations))
+		.route("/api/v1/admin/peers/ban", post(ban_peer))
+		.route("/api/v1/admin/peers/unban", post(unban_peer))
+		.route("/api/v1/admin/peers/bans", get(list_peer_bans))
+		// Everything above is rate limited per client; health checks below are not
+		.route_layer(middleware::from_fn_with_state(state.clone(), rate_limit_middleware))
+		.route("/api/v1/healthz", get(healthz))
+		.layer(TimeoutLayer::new(std::time::Duration::from_secs(config.request_timeout_secs as u64)));
+	// ...while long-lived routes are explicitly exempt: audio out, SSE, and source ingest
+	let streaming = Router::new()
+ 		.route("/api/v1/events", get(events_sse))
+		.route("/api/v1/now/events", get(now_events_sse))
+		.route("/api/v1/feed", get(feed_sse))
+ 		.route("/stream", get(stream_audio))
+		.route("/stream/:frequency", get(stream_frequency))
+ 		.route("/api/v1/source", put(put_source))
+		.route("/api/v1/source/ws", get(source_ws));
+	// Static web UI (or a JSON pointer at `/`) only sees what no API or stream route matched
+	let app = Router::new()
+		.merge(api)
+		.merge(streaming)
+		.route("/api/*rest", any(api_not_found));
+	let app = match &config.web_ui_dir {
+		Some(dir) => {
+			let index = std::path::Path::new(dir).join("index.html");
+			info!(dir = %dir, "serving web UI");
+			app.fallback_service(ServeDir::new(dir).fallback(ServeFile::new(index)))
+		}
+		None => app.route("/", get(root_index)),
+	};
+	let app = app
+		// P2P HTTP routes removed (libp2p in use)
+		.with_state(state.clone())
+		.layer(middleware::from_fn_with_state(state, blocklist_middleware))
+		.layer(CorsLayer::permissive());
+	// Wrapped around the whole router: method routers add `Allow` to their 405s outside route layers
+	Router::new().fallback_service(app).layer(middleware::from_fn(allow_header_middleware))
+}
+
+ /// `/` when no web UI is configured: point clients at the API
+pub async fn root_index() -> impl IntoResponse {
+	Json(serde_json::json!({ "api": "/api/v1", "health": "/api/v1/healthz", "stream": "/stream" }))
+}
+
+/// Unknown `/api/...` paths stay JSON 404s even when the web UI's SPA fallback is enabled
+pub async fn api_not_found(uri: axum::http::Uri) -> Response {
+	(StatusCode::NOT_FOUND, Json(ErrorResponse { error: format!("no API route for {}", uri.path()) })).into_response()
+}
+
+pub async fn healthz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
  	let node = NodeInfo {
  		node_id: state.node_id,
  		api_base_url: state.public_url.clone(),
  		version: env!("CARGO_PKG_VERSION").to_string(),
+		capabilities: state.capabilities.clone(),
  	};
  	Json(node)
  }
 
- pub async fn get_stations(State(state): State<Arc<AppState>>) -> impl IntoResponse {
- 	let stations = state.snapshot_registry().await;
- 	Json(stations)
+#[derive(Debug, Deserialize)]
+pub struct StationsQuery {
+    /// Sort key: "frequency" (default), "name" or "last_seen"
+    sort: Option<String>,
+    /// "asc" (default) or "desc"
+    order: Option<String>,
+    /// Only stations with this liveness: "live", "stale" or, with the stream healthcheck on,
+    /// "unreachable" (see `StationStatus`)
+    status: Option<String>,
+    /// Only stations advertising this ISO 3166-1 alpha-2 country (case-insensitive)
+    country: Option<String>,
+    /// Only stations at or above this frequency (MHz, or with a unit like the path parameter)
+    min: Option<String>,
+    /// Only stations at or below this frequency
+    max: Option<String>,
+    /// "1"/"true": answer with a `StationsPage` envelope; implied by `limit` or `offset`
+    paginate: Option<String>,
+    /// Page size, clamped to MAX_STATIONS_PAGE (default DEFAULT_STATIONS_PAGE)
+    limit: Option<usize>,
+    /// Stations to skip; past the end gives an empty page
+    offset: Option<usize>,
+}
+
+const DEFAULT_STATIONS_PAGE: usize = 100;
+const MAX_STATIONS_PAGE: usize = 500;
+
+ pub async fn get_stations(State(state): State<Arc<AppState>>, Query(q): Query<StationsQuery>, headers: HeaderMap) -> Response {
+ 	let mut stations = state.snapshot_registry().await;
+    // Exact decimal bounds, inclusive at both ends, so a station on a band edge is never lost to rounding
+    let (min, max) = match (parse_frequency_param("min", q.min.as_deref()), parse_frequency_param("max", q.max.as_deref())) {
+        (Ok(min), Ok(max)) => (min, max),
+        (Err(msg), _) | (_, Err(msg)) => return bad_request(msg),
+    };
+    if let (Some(min), Some(max)) = (&min, &max) {
+        if min > max {
+            return bad_request("min must not exceed max");
+        }
+    }
+    stations.retain(|a| min.as_ref().is_none_or(|m| a.frequency >= *m) && max.as_ref().is_none_or(|m| a.frequency <= *m));
+    if let Some(status) = q.status.as_deref() {
+        let status = match StationStatus::from_str(status) {
+            Ok(StationStatus::Unreachable) if state.stream_healthcheck.is_none() => {
+                return bad_request("status 'unreachable' needs the stream healthcheck (--stream-healthcheck-secs)");
+            }
+            Ok(s) => s,
+            Err(e) => return bad_request(e),
+        };
+        let now = chrono::Utc::now();
+        stations.retain(|a| state.station_status(a, now) == status);
+    }
+    if let Some(country) = q.country.as_deref() {
+        stations.retain(|a| a.location.as_ref().and_then(|l| l.country.as_deref()).is_some_and(|c| c.eq_ignore_ascii_case(country.trim())));
+    }
+    state.annotate_assignments(&mut stations).await;
+    // snapshot_registry is already ordered by frequency; other keys use a stable sort on top of it
+    match q.sort.as_deref().unwrap_or("frequency") {
+        "frequency" => {}
+        "name" => stations.sort_by(|a, b| a.name.cmp(&b.name)),
+        "last_seen" => stations.sort_by_key(|a| a.last_seen),
+        other => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: format!("invalid sort '{}'", other) })).into_response(),
+    }
+    match q.order.as_deref().unwrap_or("asc") {
+        "asc" => {}
+        "desc" => stations.reverse(),
+        other => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: format!("invalid order '{}'", other) })).into_response(),
+    }
+    // Without paging parameters the whole list stays a bare array, as before
+    if !query_flag(q.paginate.as_deref()) && q.limit.is_none() && q.offset.is_none() {
+        return json_with_etag(&headers, &stations);
+    }
+    let total = stations.len();
+    let offset = q.offset.unwrap_or(0);
+    let limit = q.limit.unwrap_or(DEFAULT_STATIONS_PAGE).min(MAX_STATIONS_PAGE);
+    let stations = stations.into_iter().skip(offset).take(limit).collect();
+ 	json_with_etag(&headers, &StationsPage { stations, total, offset, limit })
  }
 
-pub async fn get_station_by_frequency(State(state): State<Arc<AppState>>, Path(frequency): Path<String>) -> impl IntoResponse {
-    let key = match BigDecimal::from_str(&frequency) {
+/// JSON with a strong ETag over the serialized body, or a bare 304 when `If-None-Match` already has it.
+/// Hashing the bytes rather than using the registry generation also covers what the generation
+/// doesn't see: owner names, liveness going stale over time, and each query's own filtering.
+fn json_with_etag<T: serde::Serialize>(headers: &HeaderMap, value: &T) -> Response {
+    let body = match serde_json::to_vec(value) {
+        Ok(b) => b,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: err.to_string() })).into_response(),
+    };
+    let digest = Sha256::digest(&body);
+    let etag = format!("\"{}\"", digest[..16].iter().map(|b| format!("{:02x}", b)).collect::<String>());
+    let hit = if_none_match(headers, &etag);
+    let validators = [
+        (header::ETAG, HeaderValue::try_from(etag).expect("hex ETag is a valid header value")),
+        // Caches may keep the list but must revalidate before reusing it
+        (header::CACHE_CONTROL, HeaderValue::from_static("no-cache")),
+    ];
+    if hit {
+        return (StatusCode::NOT_MODIFIED, validators).into_response();
+    }
+    (validators, [(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))], body).into_response()
+}
+
+/// RFC 9110 If-None-Match: `*` or a list of tags, compared weakly (a `W/` prefix is ignored)
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers.get_all(header::IF_NONE_MATCH).iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|t| t.trim())
+        .any(|t| t == "*" || t.trim_start_matches("W/") == etag)
+}
+
+pub async fn get_stations_count(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    // Read generation first so a concurrent mutation yields a newer generation on the next poll
+    let generation = state.registry_generation();
+    let count = state.count_live().await;
+    Json(StationsCount { count, generation })
+}
+
+pub async fn get_stats(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(Stats {
+        active_source: state.sources.active(),
+        sources: state.sources.status(),
+        network_time_offset_ms: state.clock.is_enabled().then(|| state.clock.offset_ms()),
+        stream_listeners: state.stream_listener_count(),
+        gossip_ads_shed: state.gossip_ads_shed(),
+        blocklist: state.blocklist_status(),
+        resources: state.resource_status(),
+        signing_failure: state.signing_failure(),
+    })
+}
+
+/// Known peer nodes, most recently seen first; an empty array when there are none
+pub async fn get_peers(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.list_peers().await)
+}
+
+/// Prometheus text exposition, or OpenMetrics (with exemplars) when the Accept header asks for it
+pub async fn get_metrics(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let openmetrics = crate::metrics::wants_openmetrics(headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()));
+    let content_type = if openmetrics { crate::metrics::OPENMETRICS_CONTENT_TYPE } else { crate::metrics::PROMETHEUS_CONTENT_TYPE };
+    ([(header::CONTENT_TYPE, content_type)], crate::metrics::render(&state, openmetrics).await).into_response()
+}
+
+// Upper bound on slots per spectrum response
+const MAX_SPECTRUM_SLOTS: u64 = 2000;
+
+fn bad_request(msg: impl Into<String>) -> Response {
+    (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: msg.into() })).into_response()
+}
+
+/// Frequency-valued query parameter; units are accepted as in `parse_frequency`
+fn parse_frequency_param(name: &str, value: Option<&str>) -> Result<Option<BigDecimal>, String> {
+    match value {
+        None => Ok(None),
+        Some(v) => parse_frequency(v).map(Some).map_err(|e| format!("invalid {}: {}", name, e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpectrumQuery {
+    min: Option<String>,
+    max: Option<String>,
+    step: Option<String>,
+}
+
+fn parse_spectrum_query(q: &SpectrumQuery) -> Result<(BigDecimal, BigDecimal, BigDecimal), String> {
+    let min = parse_frequency_param("min", q.min.as_deref())?.ok_or("min is required")?;
+    let max = parse_frequency_param("max", q.max.as_deref())?.ok_or("max is required")?;
+    let step = parse_frequency_param("step", q.step.as_deref())?.ok_or("step is required")?;
+    Ok((min, max, step))
+}
+
+pub async fn source_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.ingest.status(state.sources.active()))
+}
+
+pub async fn get_spectrum(State(state): State<Arc<AppState>>, Query(q): Query<SpectrumQuery>) -> Response {
+    let (min, max, step) = match parse_spectrum_query(&q) {
+        Ok(v) => v,
+        Err(msg) => return bad_request(msg),
+    };
+    if step <= BigDecimal::from(0) {
+        return bad_request("step must be positive");
+    }
+    if min > max {
+        return bad_request("min must not exceed max");
+    }
+    // floor((max - min) / step) + 1 slots, bounded by a multiplication before anything is divided
+    if &max - &min >= &step * BigDecimal::from(MAX_SPECTRUM_SLOTS) {
+        return bad_request(format!("range/step yields more than {} slots", MAX_SPECTRUM_SLOTS));
+    }
+    let slots = ((&max - &min) / &step).with_scale_round(0, bigdecimal::RoundingMode::Floor) + BigDecimal::from(1);
+    let slots = slots.to_u64().unwrap_or(0);
+
+    // Snapshot is sorted by frequency, so a single forward sweep assigns stations to slots
+    let stations = state.snapshot_registry().await;
+    let mut idx = stations.partition_point(|a| a.frequency < min);
+    let mut out = Vec::with_capacity(slots as usize);
+    let mut start = min.clone();
+    for _ in 0..slots {
+        let end = &start + &step;
+        let mut occupants = Vec::new();
+        while idx < stations.len() && stations[idx].frequency < end {
+            let a = &stations[idx];
+            if a.frequency <= max {
+                occupants.push(SpectrumOccupant { frequency: a.frequency.clone(), name: a.name.clone(), owner_public_key: a.owner_public_key.clone() });
+            }
+            idx += 1;
+        }
+        out.push(SpectrumSlot { frequency: start, occupied: !occupants.is_empty(), stations: occupants });
+        start = end;
+    }
+    Json(out).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    min: Option<String>,
+    max: Option<String>,
+    /// RFC 3339; only assignments whose last advertisement is newer are returned
+    since: Option<String>,
+}
+
+/// Peer sync: assignments in [min, max] advertised after `since`, each carrying its owner signature
+pub async fn get_sync(State(state): State<Arc<AppState>>, headers: HeaderMap, Query(q): Query<SyncQuery>) -> Response {
+    if let Err(msg) = authenticate_peer(&state, &headers) {
+        return unauthorized(msg);
+    }
+    let min = match parse_frequency_param("min", q.min.as_deref()) { Ok(v) => v, Err(msg) => return bad_request(msg) };
+    let max = match parse_frequency_param("max", q.max.as_deref()) { Ok(v) => v, Err(msg) => return bad_request(msg) };
+    let since = match q.since.as_deref().map(chrono::DateTime::parse_from_rfc3339) {
+        None => None,
+        Some(Ok(t)) => Some(t.with_timezone(&chrono::Utc)),
+        Some(Err(_)) => return bad_request("invalid since (expected RFC 3339)"),
+    };
+    let server_time = chrono::Utc::now();
+    let assignments = state.snapshot_registry().await
+        .into_iter()
+        .filter(|a| min.as_ref().map(|m| &a.frequency >= m).unwrap_or(true))
+        .filter(|a| max.as_ref().map(|m| &a.frequency <= m).unwrap_or(true))
+        .filter(|a| since.map(|s| a.last_seen > s).unwrap_or(true))
+        .collect();
+    Json(SyncResponse { assignments, server_time }).into_response()
+}
+
+pub async fn my_stations(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let now = chrono::Utc::now();
+    let next_heartbeat_at = *state.next_heartbeat_at.read().await;
+    let signing_error = state.signing_failure().map(|f| f.error);
+    let out: Vec<MyStation> = state.my_assignments().await
+        .into_iter()
+        .map(|a| MyStation {
+            remaining_ttl_secs: (a.expires_at - now).num_seconds().max(0),
+            next_heartbeat_at,
+            signing_error: signing_error.clone(),
+            peer_acks: state.ad_ack_summary(a.station_id),
+            assignment: a,
+        })
+        .collect();
+    Json(out)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StationQuery {
+    /// "1"/"true": answer a non-canonical frequency (e.g. `88.50`) with a 301 to its canonical path
+    redirect: Option<String>,
+}
+
+/// Boolean query flags accept `1`/`true`/`yes`
+fn query_flag(value: Option<&str>) -> bool {
+    matches!(value, Some("1" | "true" | "yes"))
+}
+
+pub async fn get_station_by_frequency(State(state): State<Arc<AppState>>, Path(frequency): Path<String>, Query(q): Query<StationQuery>) -> impl IntoResponse {
+    let key = match parse_frequency(&frequency) {
         Ok(d) => normalize_frequency_key(&d),
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: "invalid frequency".into() })).into_response(),
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
     };
+    if key != frequency && query_flag(q.redirect.as_deref()) {
+        let location = format!("/api/v1/stations/{}", key);
+        return (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, location)]).into_response();
+    }
     match state.get_assignment_by_key(&key).await {
-        Some(a) => (StatusCode::OK, Json(a)).into_response(),
+        Some(mut a) => {
+            state.annotate_assignments(std::slice::from_mut(&mut a)).await;
+            (StatusCode::OK, Json(a)).into_response()
+        }
         None => (StatusCode::NOT_FOUND, Json(ErrorResponse { error: format!("frequency '{}' not found", frequency) })).into_response(),
     }
  }
 
- pub async fn events_sse(State(state): State<Arc<AppState>>) -> impl IntoResponse {
- 	let rx = state.events_tx.subscribe();
-    let stream = BroadcastStream::new(rx).filter_map(|evt| {
-        match evt {
-            Ok(e) => {
-                let json = serde_json::to_string(&e).unwrap_or_else(|_| "{}".into());
-                Some(Ok::<Event, Infallible>(Event::default().data(json)))
+/// Submit a signed advertisement over HTTP (for stations without their own gossip node).
+/// Accepted ads are gossiped on; refusals carry a stable `code` alongside the reason.
+pub async fn post_advertise(State(state): State<Arc<AppState>>, Json(ad): Json<StationAdvertisement>) -> Response {
+    match state.accept_advertisement(&ad).await {
+        Ok(assignment) => {
+            if let Some(gossip) = state.gossip.get() {
+                gossip.publish_advertisement(ad).await;
             }
-            Err(_) => None,
+            (StatusCode::OK, Json(AdvertiseResponse::accepted(assignment))).into_response()
+        }
+        Err(err) => {
+            let holder = if err.is_conflict() {
+                state.get_assignment_by_key(&normalize_frequency_key(&ad.frequency)).await
+            } else {
+                None
+            };
+            let status = match &err {
+                _ if err.is_conflict() => StatusCode::CONFLICT,
+                RegistryError::StaleSequence(..) => StatusCode::CONFLICT,
+                RegistryError::OwnerNotAllowed => StatusCode::FORBIDDEN,
+                RegistryError::OwnerCapExceeded | RegistryError::OwnerLimitReached | RegistryError::RegistryFull => StatusCode::TOO_MANY_REQUESTS,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            info!(code = err.code(), error=%err, station_id=%ad.station_id, "HTTP advertisement refused");
+            (status, Json(AdvertiseResponse::refused(&err, holder))).into_response()
+        }
+    }
+}
+
+/// Signed profile for an owner key; URL-safe base64 (`-`/`_`) is accepted as well as standard
+pub async fn get_owner_profile(State(state): State<Arc<AppState>>, Path(pubkey): Path<String>) -> impl IntoResponse {
+    let key = pubkey.replace('-', "+").replace('_', "/");
+    match state.get_owner_profile(&key).await {
+        Some(p) => (StatusCode::OK, Json(p)).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(ErrorResponse { error: "owner profile not found".into() })).into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// "1"/"true": start with an `upsert` for every live assignment, then continue with live events
+    snapshot: Option<String>,
+    /// Resume an interrupted snapshot after this event id (also read from `Last-Event-ID`)
+    cursor: Option<String>,
+}
+
+/// Snapshot entries read per registry lock; queued live events are sent between pages, so the
+/// subscription doesn't fall behind while a large registry is backfilled
+const BACKFILL_PAGE: usize = 256;
+
+enum Backfill {
+    /// The cursor is from an older registry generation: tell the client to drop what it has
+    Reset,
+    Paging(Option<BigDecimal>),
+    Live,
+    Done,
+}
+
+/// `<generation>:<frequency key>`, the id of a snapshot event
+fn parse_backfill_cursor(cursor: &str) -> Result<(u64, BigDecimal), String> {
+    let (generation, key) = cursor.split_once(':').ok_or_else(|| format!("invalid cursor '{}'", cursor))?;
+    let generation = generation.parse().map_err(|_| format!("invalid cursor '{}'", cursor))?;
+    let after = BigDecimal::from_str(key).map_err(|_| format!("invalid cursor '{}'", cursor))?;
+    Ok((generation, after))
+}
+
+/// Registry events. With `?snapshot=1` the current entries are sent first, a page at a time, each
+/// carrying an id that can resume the snapshot; `snapshot_complete` marks the switch to live events.
+pub async fn events_sse(State(state): State<Arc<AppState>>, Query(q): Query<EventsQuery>, headers: HeaderMap) -> Response {
+    if !query_flag(q.snapshot.as_deref()) {
+        if q.cursor.is_some() {
+            return bad_request("cursor requires snapshot=1");
         }
+        let stream = BroadcastStream::new(state.events_tx.subscribe()).filter_map(|evt| {
+            match evt {
+                Ok(e) => Some(Ok::<Event, Infallible>(Event::default().data(e.json()))),
+                Err(_) => None,
+            }
+        });
+        return Sse::new(stream).into_response();
+    }
+    let cursor = q.cursor.or_else(|| headers.get("last-event-id").and_then(|v| v.to_str().ok()).map(str::to_string));
+    let resume = match cursor.as_deref().map(parse_backfill_cursor).transpose() {
+        Ok(r) => r,
+        Err(e) => return bad_request(e),
+    };
+    let (rx, generation) = state.subscribe_for_backfill().await;
+    // Resuming is only exact if nothing changed since the snapshot began; otherwise start over
+    let phase = match resume {
+        Some((g, after)) if g == generation => Backfill::Paging(Some(after)),
+        Some(_) => Backfill::Reset,
+        None => Backfill::Paging(None),
+    };
+    let steps = futures_util::stream::unfold((state, rx, phase), move |(state, mut rx, phase)| async move {
+        let (events, next) = match phase {
+            Backfill::Reset => (vec![Event::default().event("snapshot_reset").data("{}")], Backfill::Paging(None)),
+            Backfill::Paging(after) => match state.backfill_page(&mut rx, after.as_ref(), BACKFILL_PAGE).await {
+                Ok((live, page)) => {
+                    let last = (page.len() == BACKFILL_PAGE).then(|| page.last().map(|a| a.frequency.clone())).flatten();
+                    let mut events: Vec<Event> = live.iter().map(|e| Event::default().data(e.json())).collect();
+                    events.extend(page.into_iter().map(|a| {
+                        let id = format!("{}:{}", generation, normalize_frequency_key(&a.frequency));
+                        Event::default().id(id).data(SharedEvent::new(RegistryEvent::new("upsert", a, &state.signing_domain)).json())
+                    }));
+                    if last.is_none() {
+                        events.push(Event::default().event("snapshot_complete").data("{}"));
+                    }
+                    (events, last.map_or(Backfill::Live, |f| Backfill::Paging(Some(f))))
+                }
+                Err(skipped) => (vec![Event::default().event("lagged").data(format!("{{\"skipped\":{}}}", skipped))], Backfill::Done),
+            },
+            Backfill::Live => match rx.recv().await {
+                Ok(e) => (vec![Event::default().data(e.json())], Backfill::Live),
+                // Events were lost, so the client's view can't be kept exact: end and let it resync
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    (vec![Event::default().event("lagged").data(format!("{{\"skipped\":{}}}", skipped))], Backfill::Done)
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+            },
+            Backfill::Done => return None,
+        };
+        Some((events, (state, rx, next)))
     });
- 	Sse::new(stream)
- }
+    let stream = futures_util::StreamExt::flat_map(steps, |events| tokio_stream::iter(events.into_iter().map(Ok::<Event, Infallible>)));
+    Sse::new(stream).into_response()
+}
+
+/// How often the feed samples ingest status; only changes of source, activity or codec are sent
+const FEED_SOURCE_POLL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Registry events, now-playing changes and source status on one SSE connection, for dashboards.
+/// Starts with the current now-playing and source status; `?snapshot=1` also backfills the registry.
+pub async fn feed_sse(State(state): State<Arc<AppState>>, Query(q): Query<EventsQuery>) -> impl IntoResponse {
+    let (backfill, registry_rx) = if query_flag(q.snapshot.as_deref()) {
+        state.subscribe_with_snapshot().await
+    } else {
+        (Vec::new(), state.events_tx.subscribe())
+    };
+    let now_rx = state.now_tx.subscribe();
+    let initial: Vec<FeedEvent> = backfill.into_iter().map(FeedEvent::Registry)
+        .chain(state.get_now_playing().await.map(|np| FeedEvent::NowPlaying(SharedEvent::new(np))))
+        .collect();
+    let registry = BroadcastStream::new(registry_rx).filter_map(|e| e.ok().map(FeedEvent::Registry));
+    let now = BroadcastStream::new(now_rx).filter_map(|e| e.ok().map(FeedEvent::NowPlaying));
+    let source = futures_util::stream::unfold((state, tokio::time::interval(FEED_SOURCE_POLL), None), |(st, mut tick, mut last)| async move {
+        loop {
+            tick.tick().await;
+            let status = st.ingest.status(st.sources.active());
+            let key = Some((status.active, status.active_source.clone(), status.content_type.clone()));
+            if key != last {
+                last = key;
+                return Some((FeedEvent::Source(status), (st, tick, last)));
+            }
+        }
+    });
+    let stream = tokio_stream::iter(initial)
+        .chain(registry.merge(now).merge(Box::pin(source)))
+        .map(|e| {
+            let json = serde_json::to_string(&e).unwrap_or_else(|_| "{}".into());
+            Ok::<Event, Infallible>(Event::default().event(e.name()).data(json))
+        });
+    Sse::new(stream)
+}
 
 pub async fn now_playing(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     match state.get_now_playing().await {
@@ -76,10 +595,7 @@ pub async fn now_events_sse(State(state): State<Arc<AppState>>) -> impl IntoResp
     let rx = state.now_tx.subscribe();
     let broadcast_stream = BroadcastStream::new(rx).filter_map(|evt| {
         match evt {
-            Ok(e) => {
-                let json = serde_json::to_string(&e).unwrap_or_else(|_| "{}".into());
-                Some(Ok::<Event, Infallible>(Event::default().data(json)))
-            }
+            Ok(e) => Some(Ok::<Event, Infallible>(Event::default().data(e.json()))),
             Err(_) => None,
         }
     });
@@ -94,44 +610,348 @@ pub async fn now_events_sse(State(state): State<Arc<AppState>>) -> impl IntoResp
     Sse::new(stream)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct WatchQuery {
+    /// "1"/"true": close the stream after the first `available` event
+    once: Option<String>,
+}
+
+/// SSE announcing when one frequency frees up (expiry, release or eviction) with an `available`
+/// event, sent at once if it's already free. Stays open for later transitions unless `?once=1`.
+/// Missed events end the stream with `lagged`, so the client reconnects and is told the current state.
+pub async fn watch_frequency_sse(State(state): State<Arc<AppState>>, Path(frequency): Path<String>, Query(q): Query<WatchQuery>) -> Response {
+    let key = match parse_frequency(&frequency) {
+        Ok(d) => normalize_frequency_key(&d),
+        Err(e) => return bad_request(e),
+    };
+    let once_only = query_flag(q.once.as_deref());
+    // Subscribed before the registry is checked, so a delete in between isn't missed
+    let rx = state.events_tx.subscribe();
+    let available = |frequency: String, reason: Option<String>, previous: Option<StationAssignment>| {
+        let data = serde_json::to_string(&FrequencyAvailable { frequency, reason, previous }).unwrap_or_else(|_| "{}".into());
+        Event::default().event("available").data(data)
+    };
+    let initial = match state.get_assignment_by_key(&key).await {
+        Some(_) => None,
+        None => Some((available(key.clone(), None, None), once_only)),
+    };
+    // Each event says whether it is the last one
+    let live = BroadcastStream::new(rx).filter_map(move |evt| match evt {
+        Ok(e) if e.event == "delete" && normalize_frequency_key(&e.assignment.frequency) == key => {
+            Some((available(key.clone(), e.reason.clone(), Some(e.assignment.clone())), once_only))
+        }
+        Ok(_) => None,
+        Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+            Some((Event::default().event("lagged").data(format!("{{\"skipped\":{}}}", skipped)), true))
+        }
+    });
+    // The last event is followed by a None that ends the stream without waiting for another event
+    let events = futures_util::StreamExt::flat_map(tokio_stream::iter(initial).chain(live), |(event, last)| {
+        tokio_stream::iter(std::iter::once(Some(event)).chain(last.then_some(None)))
+    });
+    let stream = events.map_while(|event| event.map(Ok::<Event, Infallible>));
+    Sse::new(stream).into_response()
+}
+
 #[derive(Debug, Deserialize)]
 pub struct StreamQuery {
  	content_type: Option<String>,
+    /// Start this many seconds back in the DVR buffer (clamped to its oldest audio)
+    rewind: Option<u32>,
  }
 
-pub async fn stream_audio(State(state): State<Arc<AppState>>, Query(q): Query<StreamQuery>) -> impl IntoResponse {
- 	let mime = q.content_type.unwrap_or_else(|| "audio/mpeg".to_string());
- 	let rx = state.audio_tx.subscribe();
-    let body_stream = BroadcastStream::new(rx)
-        .filter_map(|item| item.ok())
-        .map(|bytes| Ok::<bytes::Bytes, std::io::Error>(bytes));
+/// Live audio. The payload is already-compressed audio, so the response pins `Content-Encoding: identity`
+/// and `no-transform`: a CompressionLayer skips bodies that already declare an encoding, and proxies must
+/// not transfer-compress it (that wastes CPU and makes players buffer instead of streaming).
+pub async fn stream_audio(State(state): State<Arc<AppState>>, Query(q): Query<StreamQuery>) -> Response {
+    // Handed off to a successor: send (re)connecting listeners there
+    if let Some(url) = state.handoff_stream_url() {
+        return (StatusCode::TEMPORARY_REDIRECT, [(header::LOCATION, url)]).into_response();
+    }
+    // Default to what the source is sending; the listener is closed if that changes under it
+ 	let mime = q.content_type
+        .or_else(|| state.ingest.status(None).content_type)
+        .unwrap_or_else(|| "audio/mpeg".to_string());
+    if q.rewind.is_some_and(|r| r > 0) && !state.burst.has_dvr() {
+        return bad_request("rewind is not enabled on this node");
+    }
+ 	let (burst, rx) = state.burst.subscribe(&state.audio_tx, q.rewind.map(|r| std::time::Duration::from_secs(r as u64)));
+    let disconnect_on_lag = state.slow_listener_policy == SlowListenerPolicy::Disconnect;
+    // The codec is updated before the first chunk in it is published, so nothing past a switch leaks
+    let promised_codec = crate::source::codec_for_content_type(&mime);
+    let codec_rx = state.active_codec.subscribe();
+    let live = BroadcastStream::new(rx)
+        .map_while(move |item| match item {
+            Ok(_) if promised_codec.is_some() && codec_rx.borrow().as_ref().is_some_and(|c| Some(c) != promised_codec.as_ref()) => {
+                debug!(promised = promised_codec.as_deref(), "source codec changed; closing listener so it reconnects");
+                None
+            }
+            Ok(chunk) => Some(Some(chunk)),
+            Err(BroadcastStreamRecvError::Lagged(skipped)) if disconnect_on_lag => {
+                debug!(skipped, "listener fell behind live audio; disconnecting");
+                None
+            }
+            Err(BroadcastStreamRecvError::Lagged(_)) => Some(None),
+        })
+        .filter_map(|chunk| chunk);
+    // The guard rides along in the body, so the listener count drops when the client goes away
+    let guard = ListenerGuard::new(state.clone());
+    let mut chunks = Box::pin(tokio_stream::iter(burst).chain(live));
+    // A chunk is written by the time the body is polled for the next one
+    let mut handed_out: Option<std::time::Instant> = None;
+    let metrics_state = state.clone();
+    let timed = futures_util::stream::poll_fn(move |cx| {
+        if let Some(at) = handed_out.take() {
+            metrics_state.metrics.chunk_send_seconds.observe(at.elapsed());
+        }
+        let next = chunks.as_mut().poll_next(cx);
+        if matches!(next, std::task::Poll::Ready(Some(_))) {
+            handed_out = Some(std::time::Instant::now());
+        }
+        next
+    });
+    let body_stream = timed.map(move |chunk| {
+        let _ = &guard;
+        Ok::<bytes::Bytes, std::io::Error>(chunk)
+    });
     let content_type = HeaderValue::from_str(&mime).unwrap_or(HeaderValue::from_static("audio/mpeg"));
     let body = Body::from_stream(body_stream);
-    Response::builder()
+    let mut builder = Response::builder()
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, content_type)
-        .header(header::CACHE_CONTROL, HeaderValue::from_static("no-store"))
-        .header("Cross-Origin-Resource-Policy", HeaderValue::from_static("cross-origin"))
-        .body(body)
-        .unwrap()
+        .header(header::CACHE_CONTROL, HeaderValue::from_static("no-store, no-transform"))
+        .header(header::CONTENT_ENCODING, HeaderValue::from_static("identity"))
+        .header("Cross-Origin-Resource-Policy", HeaderValue::from_static("cross-origin"));
+    for (name, value) in icy_headers(&state) {
+        builder = builder.header(name, value);
+    }
+    builder.body(body).unwrap().into_response()
  }
 
-pub async fn put_source(State(state): State<Arc<AppState>>, headers: HeaderMap, body: Body) -> Response {
+/// `icy-name`/`icy-genre`/`icy-br` for desktop players, from the local station and the measured
+/// ingest bitrate. Each is left out when unknown (or not representable as a header value).
+fn icy_headers(state: &AppState) -> Vec<(&'static str, HeaderValue)> {
+    let Some(ls) = state.local_station.get() else {
+        return Vec::new();
+    };
+    let mut headers = Vec::new();
+    // Players expect raw UTF-8 here, as Icecast sends it
+    if let Ok(v) = HeaderValue::from_bytes(ls.name.as_bytes()) {
+        headers.push(("icy-name", v));
+    }
+    if let Some(v) = ls.genre.as_deref().and_then(|g| HeaderValue::from_bytes(g.as_bytes()).ok()) {
+        headers.push(("icy-genre", v));
+    }
+    if let Some(kbps) = state.ingest.status(None).bitrate_kbps.filter(|b| (1..=MAX_BITRATE_KBPS).contains(b)) {
+        headers.push(("icy-br", HeaderValue::from(kbps)));
+    }
+    headers
+}
+
+/// Live audio for one frequency, only if this node hosts it (its stream_url points here); 404 otherwise
+pub async fn stream_frequency(State(state): State<Arc<AppState>>, Path(frequency): Path<String>, query: Query<StreamQuery>) -> Response {
+    let key = match parse_frequency(&frequency) {
+        Ok(d) => normalize_frequency_key(&d),
+        Err(e) => return bad_request(e),
+    };
+    match state.get_assignment_by_key(&key).await {
+        // Also the station this node handed off, which stream_audio redirects to its successor
+        Some(a) if state.is_local_stream(&a.stream_url) || state.handoff_stream_url().as_deref() == Some(a.stream_url.as_str()) => {
+            stream_audio(State(state), query).await
+        }
+        _ => (StatusCode::NOT_FOUND, Json(ErrorResponse { error: format!("frequency '{}' is not streamed by this node", frequency) })).into_response(),
+    }
+}
+
+/// Bearer-token check shared by every source ingest route; resolves the source name or the 401 message
+fn authenticate_source(state: &AppState, headers: &HeaderMap) -> Result<String, &'static str> {
+    let bearer = match headers.get(header::AUTHORIZATION) {
+        Some(v) => Some(v.to_str().unwrap_or("").strip_prefix("Bearer ").ok_or("invalid Authorization token")?),
+        None => None,
+    };
+    if state.sources.is_prioritized() {
+        // Every HTTP source names itself with its token; one matching no list entry may still
+        // be the --source-token source, which then feeds the broadcast at the lowest priority
+        let Some(bearer) = bearer else {
+            return Err("missing Authorization header");
+        };
+        if let Some(name) = state.sources.match_token(bearer) {
+            return Ok(name.to_string());
+        }
+        return match &state.source_token {
+            Some(expected) if crate::crypto::tokens_match(bearer, expected) => Ok(crate::source::DEFAULT_SOURCE.to_string()),
+            _ => Err("invalid Authorization token"),
+        };
+    }
  	if let Some(expected) = &state.source_token {
- 		let Some(auth) = headers.get(header::AUTHORIZATION) else {
-            return (StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: "missing Authorization header".into() })).into_response();
+ 		let Some(auth) = bearer else {
+            return Err("missing Authorization header");
  		};
- 		let auth = auth.to_str().unwrap_or("");
- 		if auth != format!("Bearer {}", expected) {
-            return (StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: "invalid Authorization token".into() })).into_response();
+ 		if !crate::crypto::tokens_match(auth, expected) {
+            return Err("invalid Authorization token");
  		}
  	}
+    Ok(crate::source::DEFAULT_SOURCE.to_string())
+}
+
+fn unauthorized(msg: &str) -> Response {
+    (StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: msg.into() })).into_response()
+}
+
+/// Bearer check for /api/v1/admin routes; the admin API is off unless --admin-token is set
+fn authenticate_admin(state: &AppState, headers: &HeaderMap) -> Result<(), &'static str> {
+    let Some(expected) = &state.admin_token else {
+        return Err("admin API disabled");
+    };
+    let bearer = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match bearer {
+        None => Err("missing Authorization header"),
+        Some(t) if crate::crypto::tokens_match(t, expected) => Ok(()),
+        Some(_) => Err("invalid Authorization token"),
+    }
+}
+
+/// Cluster-secret check for peer-to-peer endpoints; open when no --cluster-secret is set
+fn authenticate_peer(state: &AppState, headers: &HeaderMap) -> Result<(), &'static str> {
+    let Some(expected) = &state.cluster_secret else {
+        return Ok(());
+    };
+    let bearer = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match bearer {
+        None => Err("missing Authorization header"),
+        Some(t) if crate::crypto::tokens_match(t, expected) => Ok(()),
+        Some(_) => Err("invalid cluster secret"),
+    }
+}
+
+fn set_advertise_paused(state: &AppState, headers: &HeaderMap, paused: bool) -> Response {
+    if let Err(msg) = authenticate_admin(state, headers) {
+        return unauthorized(msg);
+    }
+    if state.owner_public_key.is_none() {
+        return (StatusCode::CONFLICT, Json(ErrorResponse { error: "not in station mode".into() })).into_response();
+    }
+    let was = state.set_advertise_paused(paused);
+    Json(AdvertiseState { paused, changed: was != paused }).into_response()
+}
+
+/// Stop claiming the local frequency (a release is gossiped) while keeping audio running
+pub async fn pause_advertise(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    set_advertise_paused(&state, &headers, true)
+}
+
+/// Resume the heartbeat; the station re-advertises immediately
+pub async fn resume_advertise(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    set_advertise_paused(&state, &headers, false)
+}
+
+async fn peer_bans(state: &AppState, changed: bool) -> Response {
+    let banned = state.banned_peers().await.iter().map(|p| p.to_string()).collect();
+    Json(PeerBans { banned, changed }).into_response()
+}
+
+/// Ban a libp2p peer: its connections are closed and refused, and gossip it originated is dropped
+pub async fn ban_peer(State(state): State<Arc<AppState>>, headers: HeaderMap, Json(req): Json<PeerBanRequest>) -> Response {
+    if let Err(msg) = authenticate_admin(&state, &headers) {
+        return unauthorized(msg);
+    }
+    let peer = match PeerId::from_str(req.peer_id.trim()) {
+        Ok(p) => p,
+        Err(err) => return bad_request(format!("invalid peer_id: {err}")),
+    };
+    if state.gossip.get().is_some_and(|g| g.peer_id() == peer) {
+        return bad_request("refusing to ban this node's own peer id");
+    }
+    let changed = state.ban_peer(peer).await;
+    peer_bans(&state, changed).await
+}
+
+pub async fn unban_peer(State(state): State<Arc<AppState>>, headers: HeaderMap, Json(req): Json<PeerBanRequest>) -> Response {
+    if let Err(msg) = authenticate_admin(&state, &headers) {
+        return unauthorized(msg);
+    }
+    let peer = match PeerId::from_str(req.peer_id.trim()) {
+        Ok(p) => p,
+        Err(err) => return bad_request(format!("invalid peer_id: {err}")),
+    };
+    let changed = state.unban_peer(peer).await;
+    peer_bans(&state, changed).await
+}
+
+pub async fn list_peer_bans(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if let Err(msg) = authenticate_admin(&state, &headers) {
+        return unauthorized(msg);
+    }
+    peer_bans(&state, false).await
+}
+
+// How long a transfer waits to see each re-advertisement land in the registry
+const TRANSFER_CONFIRM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Hand this node's stations to a successor node: each is re-advertised (signed, next sequence) with
+/// its stream URL rendered for the successor's public URL, and /stream redirects there from then on,
+/// so directory clients and reconnecting listeners follow without the frequency ever being released
+pub async fn transfer_stations(State(state): State<Arc<AppState>>, headers: HeaderMap, Json(req): Json<TransferRequest>) -> Response {
+    if let Err(msg) = authenticate_admin(&state, &headers) {
+        return unauthorized(msg);
+    }
+    let Some(ls) = state.local_station.get() else {
+        return (StatusCode::CONFLICT, Json(ErrorResponse { error: "not in station mode".into() })).into_response();
+    };
+    let stream_url = match ls.stream_url_at(&req.successor_url) {
+        Ok(url) => url,
+        Err(err) => return bad_request(err.to_string()),
+    };
+    let mut outcome = StationTransfer {
+        station_id: ls.station_id,
+        frequency: ls.frequency.clone(),
+        stream_url: Some(stream_url.clone()),
+        ok: false,
+        error: None,
+    };
+    if state.is_advertise_paused() {
+        outcome.error = Some("advertising is paused; resume it to transfer".into());
+        return Json(vec![outcome]).into_response();
+    }
+    if state.is_awaiting_source() {
+        outcome.error = Some("the station isn't advertised while it has no source audio".into());
+        return Json(vec![outcome]).into_response();
+    }
+    state.set_handoff_stream_url(stream_url.clone());
+    let key = normalize_frequency_key(&ls.frequency);
+    let deadline = tokio::time::Instant::now() + TRANSFER_CONFIRM_TIMEOUT;
+    loop {
+        let current = state.get_assignment_by_key(&key).await;
+        if current.as_ref().is_some_and(|a| a.station_id == ls.station_id && a.stream_url == stream_url) {
+            outcome.ok = true;
+            break;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            outcome.error = Some("re-advertisement was not accepted in time; see logs".into());
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    info!(successor = %req.successor_url, station_id=%ls.station_id, ok = outcome.ok, "station transfer");
+    Json(vec![outcome]).into_response()
+}
+
+pub async fn put_source(State(state): State<Arc<AppState>>, headers: HeaderMap, body: Body) -> Response {
+    let source = match authenticate_source(&state, &headers) {
+        Ok(name) => name,
+        Err(msg) => return unauthorized(msg),
+    };
+    state.begin_source(&source, headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()));
 
  	let mut stream = body.into_data_stream();
  	while let Some(chunk) = stream.next().await {
  		match chunk {
  			Ok(bytes) => {
- 				let _ = state.audio_tx.send(bytes);
+ 				state.ingest_audio(&source, bytes);
  			}
  			Err(err) => {
  				error!(error=%err, "error reading source stream");
@@ -143,6 +963,130 @@ pub async fn put_source(State(state): State<Arc<AppState>>, headers: HeaderMap,
  }
 
 
+// Largest single WebSocket frame accepted from a source (audio chunk or now-playing JSON)
+const MAX_SOURCE_WS_MESSAGE: usize = 1024 * 1024;
+
+/// Source control channel: binary frames are audio bytes, text frames are now-playing JSON
+pub async fn source_ws(State(state): State<Arc<AppState>>, headers: HeaderMap, ws: WebSocketUpgrade) -> Response {
+    let source = match authenticate_source(&state, &headers) {
+        Ok(name) => name,
+        Err(msg) => return unauthorized(msg),
+    };
+    state.begin_source(&source, None);
+    ws.max_message_size(MAX_SOURCE_WS_MESSAGE)
+        .max_frame_size(MAX_SOURCE_WS_MESSAGE)
+        .on_upgrade(move |socket| handle_source_ws(state, source, socket))
+}
+
+async fn handle_source_ws(state: Arc<AppState>, source: String, mut socket: WebSocket) {
+    // Ping a third of the way into the dead-peer window; any frame (pongs included) proves liveness
+    let timeout = state.dead_peer_timeout;
+    let mut ping = tokio::time::interval(timeout.map(|t| t / 3).unwrap_or(std::time::Duration::from_secs(3600)));
+    ping.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_seen = tokio::time::Instant::now();
+    let mut throttle = state.source_ws_max_bytes_per_sec.map(ByteThrottle::new);
+    loop {
+        let msg = tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = ping.tick(), if timeout.is_some() => {
+                let timeout = timeout.unwrap_or_default();
+                // A send into a dead peer's full socket buffer would otherwise block the loop forever
+                let sent = tokio::time::timeout(timeout, socket.send(Message::Ping(Vec::new()))).await;
+                if last_seen.elapsed() > timeout || !matches!(sent, Ok(Ok(()))) {
+                    warn!(%source, "source websocket peer unresponsive; dropping it");
+                    break;
+                }
+                continue;
+            }
+        };
+        last_seen = tokio::time::Instant::now();
+        let len = match &msg {
+            Ok(Message::Binary(data)) => data.len(),
+            Ok(Message::Text(text)) => text.len(),
+            _ => 0,
+        };
+        if let Some(throttle) = throttle.as_mut().filter(|_| len > 0) {
+            // Not reading the socket meanwhile pushes back on the sender through TCP flow control
+            let wait = throttle.charge(len);
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+        }
+        match msg {
+            Ok(Message::Binary(data)) => {
+                state.ingest_audio(&source, bytes::Bytes::from(data));
+            }
+            Ok(Message::Text(text)) => match serde_json::from_str::<serde_json::Value>(&text) {
+                Ok(v) => {
+                    if let Err(err) = state.apply_now_playing_json(&v).await {
+                        warn!(error=%err, %source, "rejecting source WS now-playing update");
+                        // No HTTP status once upgraded: report it in-band and keep the audio flowing
+                        let body = serde_json::json!({ "error": err.to_string(), "status": StatusCode::PAYLOAD_TOO_LARGE.as_u16() });
+                        let _ = socket.send(Message::Text(body.to_string())).await;
+                    }
+                }
+                Err(err) => warn!(error=%err, "invalid source WS JSON"),
+            },
+            Ok(Message::Close(_)) => break,
+            // Ping/pong are answered by axum
+            Ok(_) => {}
+            Err(err) => {
+                error!(error=%err, "error reading source websocket");
+                break;
+            }
+        }
+    }
+}
+
+/// Gives axum's bare 405s a JSON body like every other API error, and lists OPTIONS (answered
+/// for every route via CORS) in `Allow` on both 405s and OPTIONS responses
+pub async fn allow_header_middleware(req: Request<Body>, next: Next) -> Response {
+    let method = req.method().clone();
+    let mut res = next.run(req).await;
+    let is_405 = res.status() == StatusCode::METHOD_NOT_ALLOWED;
+    if !is_405 && method != axum::http::Method::OPTIONS {
+        return res;
+    }
+    let Some(allow) = res.headers().get(header::ALLOW).and_then(|v| v.to_str().ok()) else {
+        return res;
+    };
+    let allow = if allow.split(',').any(|m| m.trim() == "OPTIONS") { allow.to_string() } else { format!("{allow},OPTIONS") };
+    if is_405 {
+        // Keep the status and headers (CORS, Vary) and only swap in a body
+        let body = serde_json::to_vec(&ErrorResponse { error: format!("method {} not allowed; allowed: {}", method, allow) }).unwrap_or_default();
+        let (mut parts, _) = res.into_parts();
+        parts.headers.remove(header::CONTENT_LENGTH);
+        parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        res = Response::from_parts(parts, Body::from(body));
+    }
+    if let Ok(value) = HeaderValue::from_str(&allow) {
+        res.headers_mut().insert(header::ALLOW, value);
+    }
+    res
+}
+
+/// Per-IP token bucket (--rate-limit-per-sec): 429 with Retry-After once a client's burst is spent.
+/// Keyed on the connection's address (IPv6 by /64), as the blocklist is, so clients behind one proxy share a bucket.
+pub async fn rate_limit_middleware(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if let (Some(limiter), Some(ci)) = (&state.rate_limiter, req.extensions().get::<ConnectInfo<SocketAddr>>()) {
+        if let Err(wait) = limiter.check(ci.0.ip()) {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(ErrorResponse { error: format!("rate limit exceeded; retry in {}s", retry_after) }),
+            ).into_response();
+        }
+    }
+    next.run(req).await
+}
 
 // Global middleware to enforce IP blocklist
 pub async fn blocklist_middleware(
@@ -1,49 +1,134 @@
- use std::{path::Path, sync::Arc};
+ use std::{path::Path, sync::Arc, time::Duration};
 
  use tokio::{
-    io::{AsyncBufReadExt, BufReader, AsyncReadExt},
+    io::{AsyncBufReadExt, BufReader, AsyncReadExt, AsyncWriteExt},
      net::{UnixListener, UnixStream},
+     time::Instant,
  };
+use tokio::sync::Semaphore;
 use tracing::{info, warn};
 
- use crate::{state::AppState, types::NowPlaying};
- use chrono::Utc;
+ use crate::state::AppState;
+use crate::source::AUDIO_IPC_SOURCE;
+
+ // Longest NowPlaying JSON line accepted; longer lines are discarded up to the next newline
+ const MAX_IPC_LINE_BYTES: usize = 64 * 1024;
+
+ async fn handle_ipc_line(state: &AppState, raw: &[u8]) {
+     let Ok(line) = std::str::from_utf8(raw) else {
+         warn!("IPC line is not valid UTF-8");
+         return;
+     };
+     let line = line.trim();
+     if line.is_empty() { return; }
+     match serde_json::from_str::<serde_json::Value>(line) {
+         Ok(v) if v.is_object() => {
+             if let Err(err) = state.apply_now_playing_json(&v).await {
+                 warn!(error=%err, "rejecting IPC now-playing update");
+             }
+         }
+         Ok(_) => warn!("IPC JSON line is not an object"),
+         Err(err) => warn!(error=%err, "invalid IPC JSON"),
+     }
+ }
 
  async fn handle_ipc_stream(state: Arc<AppState>, stream: UnixStream) {
-     let reader = BufReader::new(stream);
-     let mut lines = reader.lines();
-     while let Ok(Some(line)) = lines.next_line().await {
-         let line = line.trim();
-         if line.is_empty() { continue; }
-         match serde_json::from_str::<serde_json::Value>(line) {
-             Ok(v) => {
-                 let np = NowPlaying {
-                     title: v.get("title").and_then(|x| x.as_str()).map(|s| s.to_string()),
-                     artist: v.get("artist").and_then(|x| x.as_str()).map(|s| s.to_string()),
-                     album: v.get("album").and_then(|x| x.as_str()).map(|s| s.to_string()),
-                     cover_url: v.get("cover_url").and_then(|x| x.as_str()).map(|s| s.to_string()),
-                     updated_at: Utc::now(),
-                 };
-                 state.set_now_playing(np).await;
+     // Manual line splitting so a client that never sends '\n' can't make us buffer unbounded memory
+     let mut reader = BufReader::new(stream);
+     let mut line: Vec<u8> = Vec::new();
+     let mut discarding = false;
+     loop {
+         let buf = match reader.fill_buf().await {
+             Ok(b) => b,
+             Err(err) => {
+                 warn!(error=%err, "IPC read error");
+                 break;
              }
-             Err(err) => warn!(error=%err, "invalid IPC JSON"),
+         };
+         if buf.is_empty() {
+             // EOF: a final line without trailing newline is still accepted
+             if !discarding { handle_ipc_line(&state, &line).await; }
+             break;
+         }
+         let (chunk, found_newline) = match buf.iter().position(|&b| b == b'\n') {
+             Some(i) => (&buf[..i], true),
+             None => (buf, false),
+         };
+         let consumed = chunk.len() + found_newline as usize;
+         if !discarding {
+             if line.len() + chunk.len() > MAX_IPC_LINE_BYTES {
+                 warn!(limit = MAX_IPC_LINE_BYTES, "IPC line too long; discarding");
+                 line = Vec::new();
+                 discarding = true;
+             } else {
+                 line.extend_from_slice(chunk);
+             }
+         }
+         reader.consume(consumed);
+         if found_newline {
+             if !discarding { handle_ipc_line(&state, &line).await; }
+             line.clear();
+             discarding = false;
          }
      }
  }
 
- pub async fn run_ipc_listener(state: Arc<AppState>, socket_path: String) -> anyhow::Result<()> {
-     let p = Path::new(&socket_path);
-     if p.exists() {
-         // best effort unlink
-         let _ = tokio::fs::remove_file(p).await;
+ // sun_path is 108 bytes on Linux including the terminating NUL
+ const MAX_SOCKET_PATH_BYTES: usize = 107;
+
+ /// Bind an IPC socket at startup. A stale socket left by a previous run (nothing accepts on it)
+ /// is removed; a live socket, a non-socket file, or a path we can't create is a hard error
+ /// explaining what to fix rather than a silently missing feature.
+ pub fn bind_ipc_socket(socket_path: &str) -> anyhow::Result<UnixListener> {
+     use std::os::unix::fs::FileTypeExt;
+     let p = Path::new(socket_path);
+     if socket_path.len() > MAX_SOCKET_PATH_BYTES {
+         anyhow::bail!("IPC socket path {} is {} bytes; Unix sockets allow at most {}, use a shorter path", socket_path, socket_path.len(), MAX_SOCKET_PATH_BYTES);
+     }
+     if let Ok(meta) = std::fs::symlink_metadata(p) {
+         if !meta.file_type().is_socket() {
+             anyhow::bail!("IPC socket path {} exists and is not a socket; refusing to replace it", socket_path);
+         }
+         match std::os::unix::net::UnixStream::connect(p) {
+             Ok(_) => anyhow::bail!("IPC socket {} is in use by another process; stop it or choose another path", socket_path),
+             Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+                 info!(path=%socket_path, "removing stale IPC socket");
+                 std::fs::remove_file(p)
+                     .map_err(|e| anyhow::anyhow!("cannot remove stale IPC socket {}: {}", socket_path, e))?;
+             }
+             Err(err) => anyhow::bail!("cannot probe existing IPC socket {}: {}", socket_path, err),
+         }
      }
-     let listener = UnixListener::bind(p)?;
-     info!(path=%socket_path, "IPC socket listening");
+     UnixListener::bind(p).map_err(|err| {
+         let hint = match err.kind() {
+             std::io::ErrorKind::PermissionDenied => "check that the directory is writable by this user",
+             std::io::ErrorKind::NotFound => "the parent directory does not exist",
+             std::io::ErrorKind::Unsupported => "the filesystem does not support Unix sockets; use a path on a local filesystem such as /run or /tmp",
+             // e.g. EOPNOTSUPP on network or FUSE mounts, which std doesn't map to Unsupported
+             _ if err.raw_os_error() == Some(95) => "the filesystem does not support Unix sockets; use a path on a local filesystem such as /run or /tmp",
+             _ => "check the path",
+         };
+         anyhow::anyhow!("cannot bind IPC socket {}: {} ({})", socket_path, err, hint)
+     })
+ }
+
+ /// Now-playing JSON lines over a Unix socket; at most `max_connections` clients at once
+ pub async fn run_ipc_listener(state: Arc<AppState>, listener: UnixListener, max_connections: usize) {
+     info!(max_connections, "IPC socket listening");
+     let slots = Arc::new(Semaphore::new(max_connections.max(1)));
      loop {
          match listener.accept().await {
              Ok((stream, _addr)) => {
+                 // The permit lives in the task and frees the slot however the connection ends
+                 let Ok(permit) = slots.clone().try_acquire_owned() else {
+                     warn!(max_connections, "IPC connection limit reached; closing new connection");
+                     continue;
+                 };
                  let st = state.clone();
-                 tokio::spawn(async move { handle_ipc_stream(st, stream).await });
+                 tokio::spawn(async move {
+                     let _permit = permit;
+                     handle_ipc_stream(st, stream).await;
+                 });
              }
              Err(err) => {
                  warn!(error=%err, "IPC accept error");
@@ -52,24 +137,57 @@ use tracing::{info, warn};
      }
  }
 
-pub async fn run_audio_ipc_listener(state: Arc<AppState>, socket_path: String) -> anyhow::Result<()> {
-    let p = Path::new(&socket_path);
-    if p.exists() {
-        let _ = tokio::fs::remove_file(p).await;
-    }
-    let listener = UnixListener::bind(p)?;
-    info!(path=%socket_path, "Audio IPC socket listening");
+// Longest a partial chunk is held back waiting for more bytes; well under a player's jitter buffer
+const AUDIO_COALESCE_DEADLINE: Duration = Duration::from_millis(20);
+
+/// Raw audio over a Unix socket. Small reads are coalesced up to `chunk_bytes` (or for at most
+/// AUDIO_COALESCE_DEADLINE) so a writer doing tiny writes doesn't fan out tiny broadcast chunks.
+/// One writer at a time: a second connection would interleave its bytes into the same stream, so
+/// like the TCP ingest it gets `BUSY\n` and is closed while the first is connected.
+pub async fn run_audio_ipc_listener(state: Arc<AppState>, listener: UnixListener, chunk_bytes: usize) {
+    info!("Audio IPC socket listening");
+    let active = Arc::new(Semaphore::new(1));
     loop {
         match listener.accept().await {
             Ok((mut stream, _addr)) => {
+                let Ok(permit) = active.clone().try_acquire_owned() else {
+                    warn!("audio IPC source already connected; refusing a second client");
+                    tokio::spawn(async move {
+                        let _ = stream.write_all(b"BUSY\n").await;
+                    });
+                    continue;
+                };
                 let st = state.clone();
+                info!("audio IPC source connected");
+                st.begin_source(AUDIO_IPC_SOURCE, None);
                 tokio::spawn(async move {
-                    let mut buf = vec![0u8; 16 * 1024];
+                    let _permit = permit;
+                    let mut buf = vec![0u8; chunk_bytes];
+                    let mut pending = bytes::BytesMut::with_capacity(chunk_bytes);
+                    let mut flush_at: Option<Instant> = None;
                     loop {
-                        match stream.read(&mut buf).await {
+                        let read = match flush_at {
+                            // read() is cancel-safe, so timing out mid-read loses nothing
+                            Some(at) => match tokio::time::timeout_at(at, stream.read(&mut buf)).await {
+                                Ok(r) => r,
+                                Err(_) => {
+                                    st.ingest_audio(AUDIO_IPC_SOURCE, pending.split().freeze());
+                                    flush_at = None;
+                                    continue;
+                                }
+                            },
+                            None => stream.read(&mut buf).await,
+                        };
+                        match read {
                             Ok(0) => break,
                             Ok(n) => {
-                                let _ = st.audio_tx.send(bytes::Bytes::copy_from_slice(&buf[..n]));
+                                pending.extend_from_slice(&buf[..n]);
+                                if pending.len() >= chunk_bytes {
+                                    st.ingest_audio(AUDIO_IPC_SOURCE, pending.split().freeze());
+                                    flush_at = None;
+                                } else if flush_at.is_none() {
+                                    flush_at = Some(Instant::now() + AUDIO_COALESCE_DEADLINE);
+                                }
                             }
                             Err(err) => {
                                 warn!(error=%err, "audio IPC read error");
@@ -77,6 +195,10 @@ pub async fn run_audio_ipc_listener(state: Arc<AppState>, socket_path: String) -
                             }
                         }
                     }
+                    if !pending.is_empty() {
+                        st.ingest_audio(AUDIO_IPC_SOURCE, pending.freeze());
+                    }
+                    info!("audio IPC source disconnected");
                 });
             }
             Err(err) => {
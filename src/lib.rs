@@ -0,0 +1,24 @@
+//! Node internals, shared by the `shortwave` binary and the benches
+
+pub mod config;
+pub mod http;
+pub mod p2p;
+pub mod state;
+pub mod types;
+pub mod crypto;
+pub mod ipc;
+pub mod source;
+pub mod hold;
+pub mod webhook;
+pub mod tcp_ingest;
+pub mod nettime;
+pub mod store;
+pub mod capture;
+pub mod blocklist;
+pub mod healthcheck;
+pub mod peer_bans;
+pub mod resources;
+pub mod signer;
+pub mod metrics;
+pub mod ratelimit;
+pub mod testing;
@@ -1,30 +1,21 @@
- use std::{net::SocketAddr, sync::Arc, time::Duration};
+ use std::{future::IntoFuture, net::SocketAddr, sync::Arc, time::Duration};
 
-use axum::{
-	routing::{get, put},
-	Router,
-};
  use chrono::{DateTime, Utc};
-use tracing::{info, warn};
-
- mod config;
- mod http;
-mod p2p;
- mod state;
- mod types;
-mod crypto;
-mod ipc;
-
- use crate::config::Cli;
- use crate::state::AppState;
-use crate::types::{StationAdvertisement};
-use crate::types::normalize_frequency_key;
-use crate::crypto::{encode_public_key_b64, encode_signature_b64, sign_bytes, canonicalize_ad_bytes};
+use tracing::{debug, error, info, warn};
+
+ use shortwave::{blocklist, capture, hold, http, p2p, resources, store, webhook};
+ use shortwave::config::Cli;
+ use shortwave::state::AppState;
+use shortwave::types::{OwnerProfile, ReleaseRequest, StationAdvertisement};
+use shortwave::types::{next_sequence, normalize_frequency_key, validate_stream_format, AD_CANONICAL_VERSION, MAX_BITRATE_KBPS, MIN_HEARTBEAT_SECS};
+use shortwave::crypto::{encode_public_key_b64, encode_signature_b64, parse_public_key_b64, canonicalize_profile_bytes, canonicalize_release_bytes};
+use shortwave::signer::{Signer, SignerBackend};
 use ed25519_dalek::SigningKey;
 use rand::rngs::OsRng;
 use rand::RngCore;
-use tower_http::cors::CorsLayer;
-use axum::middleware;
+
+/// First retry after a heartbeat couldn't be signed; doubles per failure up to the heartbeat period
+const SIGNING_RETRY_BASE: Duration = Duration::from_secs(5);
 
  #[tokio::main]
  async fn main() -> anyhow::Result<()> {
@@ -35,124 +26,428 @@ use axum::middleware;
  		.init();
 
  	let cli = Cli::parse();
+	let print_config = cli.print_config;
 		let config = cli.into_config()?;
+	// Printed before validation so a config that fails to start can still be inspected
+	if print_config {
+		print!("{}", config.to_redacted_yaml()?);
+		return Ok(());
+	}
+	config.validate()?;
+	if let Some(id) = &config.network_id {
+		info!(network_id = %id, "joining separate shortwave network");
+	}
 
- 	let addr: SocketAddr = config.bind.parse()?;
-
-	let state = Arc::new(AppState::new(
- 		config.node_id,
- 		config.public_url.clone(),
- 		config.source_token.clone(),
-		config.max_frequencies_per_owner,
- 	));
-
- 	// Build router
- 	let app = Router::new()
- 		.route("/api/v1/healthz", get(http::healthz))
- 		.route("/api/v1/stations", get(http::get_stations))
- 		.route("/api/v1/stations/:frequency", get(http::get_station_by_frequency))
- 		.route("/api/v1/events", get(http::events_sse))
-		.route("/api/v1/now", get(http::now_playing))
-		.route("/api/v1/now/events", get(http::now_events_sse))
- 		.route("/stream", get(http::stream_audio))
- 		.route("/api/v1/source", put(http::put_source))
-		// P2P HTTP routes removed (libp2p in use)
-		.with_state(state.clone())
-		.layer(middleware::from_fn_with_state(state.clone(), http::blocklist_middleware))
-		.layer(CorsLayer::permissive());
-
- 	let listener = tokio::net::TcpListener::bind(addr).await?;
- 	info!("listening on http://{}", addr);
+ 	let addrs = config.bind.iter().map(|b| b.parse::<SocketAddr>()).collect::<Result<Vec<_>, _>>()?;
+
+	let backend = match (&config.signer_socket, &config.owner_public_key) {
+		(Some(path), Some(pk)) => SignerBackend::Socket { path: path.clone(), public_key: Box::new(parse_public_key_b64(pk)?) },
+		_ => SignerBackend::Local(Arc::new(match config.owner_signing_key.clone() {
+			Some(sk) => sk,
+			None => {
+				let mut seed = [0u8; 32];
+				OsRng.fill_bytes(&mut seed);
+				SigningKey::from_bytes(&seed)
+			}
+		})),
+	};
+	let signer = Signer::new(backend, config.sign_concurrency);
+	signer.self_test().await
+		.map_err(|e| anyhow::anyhow!("owner key failed its sign/verify self-test: {}", e))?;
+    let signer = Arc::new(signer);
+    let owner_public_key_b64 = encode_public_key_b64(&signer.public_key());
+	if config.local_station.is_some() {
+		if let Some(path) = &config.signer_socket {
+			info!(owner_public_key = %owner_public_key_b64, signer_socket = %path, "station owner identity, signed by an external signer");
+		} else if config.owner_signing_key.is_some() {
+			info!(owner_public_key = %owner_public_key_b64, "station owner identity (record this public key)");
+		} else {
+			warn!(
+				owner_public_key = %owner_public_key_b64,
+				"no owner key configured: using an EPHEMERAL owner key; this station cannot reclaim its frequency after restart"
+			);
+		}
+	}
+
+	let state = Arc::new(AppState::new(&config, config.local_station.as_ref().map(|_| owner_public_key_b64.clone())));
+	if let Some(path) = &config.allowed_owners_file {
+		let keys = shortwave::config::read_allowed_owners_file(path)?;
+		if keys.is_empty() && config.allowed_owners.is_empty() {
+			warn!(path=%path, "allowed owners file is empty; no owner may advertise");
+		}
+		state.set_allowed_owners(config.allowed_owners.iter().cloned().chain(keys).collect()).await;
+	}
+	if let Some(path) = &config.owner_caps_file {
+		let caps = shortwave::config::read_owner_caps_file(path)?;
+		state.set_owner_caps(config.owner_caps.clone().into_iter().chain(caps).collect()).await;
+	}
+	// After the allowlist and caps, which restored entries are checked against
+	store::load_registry(&state).await
+		.map_err(|e| anyhow::anyhow!("failed to load registry from {} store: {}", state.store.name(), e))?;
+	store::spawn_store_sync(state.clone());
+
+	// Our own signed owner profile, republished with every heartbeat
+	let owner_profile = match (&config.local_station, &config.owner_name) {
+		(Some(_), Some(name)) => {
+			let updated_at = Utc::now();
+			let msg = canonicalize_profile_bytes(
+				&state.signing_domain,
+				&owner_public_key_b64,
+				name,
+				config.owner_url.as_deref(),
+				config.owner_avatar_url.as_deref(),
+				&updated_at.to_rfc3339(),
+			);
+			let profile = OwnerProfile {
+				owner_public_key: owner_public_key_b64.clone(),
+				name: name.clone(),
+				url: config.owner_url.clone(),
+				avatar_url: config.owner_avatar_url.clone(),
+				updated_at,
+				signature: encode_signature_b64(&signer.sign(&msg).await
+					.map_err(|e| anyhow::anyhow!("failed to sign owner profile: {}", e))?),
+			};
+			state.accept_owner_profile(&profile).await
+				.map_err(|e| anyhow::anyhow!("invalid owner profile: {}", e))?;
+			Some(profile)
+		}
+		_ => None,
+	};
+
+	let app = http::router(state.clone(), &config);
+
+ 	let mut listeners = Vec::with_capacity(addrs.len());
+ 	for &addr in &addrs {
+		// `[::]` is dual-stack unless an IPv4 address on the same port is bound as well
+		let v6_only = addr.is_ipv6() && addrs.iter().any(|a| a.is_ipv4() && a.port() == addr.port());
+		let listener = http::bind_http_listener(addr, v6_only)
+			.map_err(|e| anyhow::anyhow!("failed to bind {}: {}", addr, e))?;
+		if addr.ip().is_unspecified() && addr.is_ipv6() && !v6_only {
+			info!("listening on http://{} (IPv4 and IPv6)", addr);
+		} else {
+			info!("listening on http://{}", addr);
+		}
+		listeners.push((addr, listener));
+	}
+	// IPC sockets are bound before anything is advertised so a bad path is reported up front
+	// (and is fatal with --ipc-required) rather than failing quietly in a background task
+	let bind_ipc = |flag: &str, path: Option<&String>| -> anyhow::Result<Option<tokio::net::UnixListener>> {
+		let Some(path) = path else { return Ok(None) };
+		match shortwave::ipc::bind_ipc_socket(path) {
+			Ok(listener) => Ok(Some(listener)),
+			Err(err) if config.ipc_required => Err(err.context(format!("{flag} is required (--ipc-required)"))),
+			Err(err) => {
+				error!(error=%err, "{flag} disabled; pass --ipc-required to make this fatal");
+				Ok(None)
+			}
+		}
+	};
+	let ipc_listener = bind_ipc("--ipc-socket", config.ipc_socket.as_ref())?;
+	let audio_ipc_listener = bind_ipc("--audio-ipc-socket", config.audio_ipc_socket.as_ref())?;
+	for (addr, listener) in &listeners {
+		if let Some(dscp) = config.http_dscp {
+			// Accepted sockets inherit the listener's TOS/traffic class, so every connection on this address
+			// is marked, API requests as well as /stream. A dual-stack socket carries IPv4 too, so it gets
+			// the TOS as well as the traffic class
+			let sock = socket2::SockRef::from(listener);
+			let res = if addr.is_ipv4() {
+				sock.set_tos((dscp as u32) << 2)
+			} else {
+				sock.set_tclass_v6((dscp as u32) << 2)
+					.and_then(|()| if sock.only_v6()? { Ok(()) } else { sock.set_tos((dscp as u32) << 2) })
+			};
+			match res {
+				Ok(()) => info!(dscp, %addr, "marking all HTTP traffic on this address with DSCP"),
+				Err(err) => warn!(error=%err, dscp, %addr, "DSCP marking unsupported on this platform; continuing without it"),
+			}
+		}
+		let Some(timeout) = state.dead_peer_timeout else { continue };
+		// Also inherited by accepted sockets. A half-open listener otherwise holds its connection and
+		// audio receiver until a write fails, which on a silent stream may be never: keepalive probes
+		// catch idle dead peers, TCP_USER_TIMEOUT catches ones that stop acknowledging audio.
+		let sock = socket2::SockRef::from(listener);
+		let keepalive = socket2::TcpKeepalive::new()
+			.with_time(timeout / 2)
+			.with_interval((timeout / 6).max(Duration::from_secs(1)));
+		#[cfg(any(target_os = "linux", target_os = "android"))]
+		let keepalive = keepalive.with_retries(3);
+		let res = sock.set_tcp_keepalive(&keepalive);
+		#[cfg(any(target_os = "linux", target_os = "android"))]
+		let res = res.and_then(|()| sock.set_tcp_user_timeout(Some(timeout)));
+		match res {
+			Ok(()) => info!(timeout_secs = timeout.as_secs(), %addr, "dead peer detection enabled for HTTP connections"),
+			Err(err) => warn!(error=%err, %addr, "failed to enable dead peer detection; continuing without it"),
+		}
+	}
+
+	// Subscribed before gossip and the heartbeat start, so the first registry events are forwarded too
+	if let Some(url) = config.event_webhook.clone() {
+		webhook::spawn_event_webhook(state.clone(), url, config.event_webhook_events.clone(), config.event_webhook_queue);
+	}
 
    // Start libp2p gossip
+    state.load_peer_bans().await;
    let p2p_handle = p2p::run_libp2p(
         state.clone(),
         config.p2p_listen.clone(),
         config.p2p_bootstrap.clone(),
        config.p2p_mdns,
+       config.p2p_transport,
        config.p2p_key_path.clone(),
+       config.p2p_key_seed.clone(),
     ).await?;
+    let _ = state.gossip.set(p2p_handle.clone());
 
     // Background: station advertisement (heartbeat)
     let state_for_boot = state.clone();
     let advertise_ttl = config.advertise_ttl_secs;
-    let local_station = config.local_station.clone();
-	let signing_key: SigningKey = match config.owner_signing_key.clone() {
-		Some(sk) => sk,
-		None => {
-			let mut seed = [0u8; 32];
-			OsRng.fill_bytes(&mut seed);
-			SigningKey::from_bytes(&seed)
-		}
-	};
-    let signing_key = std::sync::Arc::new(signing_key);
-    let owner_public_key_b64 = encode_public_key_b64(&signing_key.verifying_key());
+    let listener_heartbeat_secs = config.listener_heartbeat_secs;
+    let heartbeat_jitter = config.heartbeat_jitter;
+    let mut local_station = config.local_station.clone();
+    // Resume from the hold file when it was written by this owner for this frequency
+    let mut sequence: u64 = 0;
+    if let (Some(ls), Some(path)) = (local_station.as_mut(), config.station_hold_path.as_deref()) {
+        if let Some(held) = hold::load(path).await {
+            if held.matches(&owner_public_key_b64, &normalize_frequency_key(&ls.frequency))
+                && (!ls.station_id_explicit || held.station_id == ls.station_id)
+            {
+                if held.station_id != ls.station_id {
+                    *ls = ls.with_station_id(held.station_id, &config.public_url)?;
+                }
+                sequence = held.sequence;
+                info!(station_id=%held.station_id, sequence, advertised_at=%held.advertised_at, "resuming station from hold file");
+            } else {
+                info!(path, "station hold file is for another owner, frequency or station; starting fresh");
+            }
+        }
+    }
+    if let Some(ls) = &local_station {
+        let _ = state.local_station.set(ls.clone());
+    }
+    let station_hold_path = config.station_hold_path.clone();
+    let program_file = config.program_file.clone();
+    // Fail fast on a broken program file; later read errors keep the last good program
+    let mut program = match &program_file {
+        Some(path) => shortwave::config::read_program_file(path)?,
+        None => None,
+    };
     tokio::spawn(async move {
  		// If we're a station, advertise now and periodically
 		if let Some(ls) = local_station {
- 			let mut interval = tokio::time::interval(Duration::from_secs((advertise_ttl / 2).max(10) as u64));
+ 			let idle_period = Duration::from_secs((advertise_ttl / 2).max(MIN_HEARTBEAT_SECS) as u64);
+			// While listeners are tuned in, heartbeat faster (never slower than idle)
+			let listener_period = match listener_heartbeat_secs {
+				0 => idle_period,
+				secs => Duration::from_secs(secs as u64).min(idle_period),
+			};
+			let mut released = false;
+			let mut refused_in_a_row = 0u32;
  			loop {
- 				let now: DateTime<Utc> = Utc::now();
 				let freq_key = normalize_frequency_key(&ls.frequency);
-                // Offload CPU-heavy signing to blocking pool to avoid impacting audio streaming.
-                let sk = signing_key.clone();
-                let station_id_str = ls.station_id.to_string();
-                let stream_url = ls.stream_url.clone();
-                let now_str = now.to_rfc3339();
-                let sig_b64 = tokio::task::spawn_blocking(move || {
-                    let msg = canonicalize_ad_bytes(
-                        "advertise",
-                        &freq_key,
-                        &station_id_str,
-                        &stream_url,
-                        &now_str,
-                        advertise_ttl,
-                    );
-                    encode_signature_b64(&sign_bytes(&sk, &msg))
-                }).await.unwrap_or_else(|_| "".to_string());
-				let ad = StationAdvertisement {
+				let held_back = if state_for_boot.is_advertise_paused() {
+					Some(("paused", "advertising paused"))
+				} else if state_for_boot.is_awaiting_source() {
+					Some(("no_source", "no source audio"))
+				} else {
+					None
+				};
+				if let Some((reason, why)) = held_back {
+					// Give the frequency up once, then sleep until an operator resumes or audio returns
+					if !released {
+						let released_at = Utc::now();
+						sequence = next_sequence(sequence, released_at);
+						let msg = canonicalize_release_bytes(&state_for_boot.signing_domain, "release.v2", &freq_key, &ls.station_id.to_string(), sequence, &released_at.to_rfc3339());
+						match signer.sign(&msg).await {
+							Ok(sig) => {
+								let release = ReleaseRequest {
+									station_id: ls.station_id,
+									frequency: ls.frequency.clone(),
+									reason: Some(reason.to_string()),
+									sequence,
+									released_at,
+									signature: encode_signature_b64(&sig),
+								};
+								state_for_boot.release_assignment(&release).await;
+								p2p_handle.publish_release(release).await;
+								info!(frequency=%ls.frequency, "{why}; released frequency");
+							}
+							Err(err) => error!(error=%err, frequency=%ls.frequency, "{why} but the release could not be signed; the frequency stays held until it expires"),
+						}
+						released = true;
+					}
+					state_for_boot.set_next_heartbeat(None).await;
+					state_for_boot.advertise_wake.notified().await;
+					continue;
+				}
+				released = false;
+				if let Some(path) = &program_file {
+					match shortwave::config::read_program_file(path) {
+						Ok(p) => program = p,
+						Err(err) => warn!(error=%err, path, "failed to read program file; keeping previous program"),
+					}
+				}
+ 				let now: DateTime<Utc> = Utc::now();
+				sequence = next_sequence(sequence, now);
+                // After an admin transfer the station points at its successor's stream alone
+                let handoff = state_for_boot.handoff_stream_url();
+                let stream_urls = if handoff.is_some() { Vec::new() } else { ls.stream_endpoints.clone() };
+                let stream_url = handoff.unwrap_or_else(|| ls.stream_url.clone());
+                // Report what is actually being broadcast; bitrate is only known while the source is live
+                let ingest = state_for_boot.ingest.status(None);
+                let bitrate_kbps = ingest.bitrate_kbps.filter(|b| (1..=MAX_BITRATE_KBPS).contains(b));
+                let codec = ingest.content_type.as_deref().and_then(shortwave::source::codec_for_content_type)
+                    .filter(|c| validate_stream_format(None, Some(c)).is_ok());
+				let mut ad = StationAdvertisement {
  					message_id: uuid::Uuid::new_v4(),
  					station_id: ls.station_id,
 					frequency: ls.frequency.clone(),
  					name: ls.name.clone(),
- 					stream_url: ls.stream_url.clone(),
+ 					stream_url,
  					advertised_at: now,
  					ttl_seconds: advertise_ttl,
+					sequence,
 					owner_public_key: owner_public_key_b64.clone(),
-					signature: sig_b64,
+					signature: String::new(),
+					program: program.clone(),
+					bitrate_kbps,
+					codec,
+					stream_urls,
+					canonical_version: AD_CANONICAL_VERSION,
+					logo_url: ls.logo_url.clone(),
+					location: ls.location.clone(),
  				};
+                // The signer verifies what it produced: a bad signature would just be refused everywhere
+                // and the station would quietly drop off the air.
+                let signed = match ad.signed_bytes(&state_for_boot.signing_domain) {
+                    Some(msg) => signer.sign(&msg).await.map(|sig| encode_signature_b64(&sig)).map_err(|e| e.to_string()),
+                    None => Err(format!("canonical version {} has no signed form", ad.canonical_version)),
+                };
+                match signed {
+                    Ok(signature) => {
+                        ad.signature = signature;
+                        if let Some(failures) = state_for_boot.clear_signing_failure() {
+                            info!(failures, "advertisement signing recovered");
+                        }
+                    }
+                    Err(err) => {
+                        let failures = state_for_boot.record_signing_failure(err.clone());
+                        if failures == 1 {
+                            error!(error=%err, frequency=%ls.frequency, "STATION ADVERTISEMENT COULD NOT BE SIGNED: heartbeats are suspended until signing works again");
+                        } else {
+                            warn!(error=%err, failures, "advertisement signing failed again; will retry");
+                        }
+                        // Retry sooner than a heartbeat at first, backing off to the heartbeat period
+                        let retry = SIGNING_RETRY_BASE.saturating_mul(1 << (failures - 1).min(16)).min(idle_period);
+                        state_for_boot.set_next_heartbeat(Some(now + chrono::Duration::from_std(retry).unwrap_or_default())).await;
+                        tokio::select! {
+                            _ = tokio::time::sleep(retry) => {}
+                            _ = state_for_boot.advertise_wake.notified() => {}
+                        }
+                        continue;
+                    }
+                }
+				if let Some(path) = &station_hold_path {
+					if let Err(err) = hold::save(path, &hold::StationHold::from_advertisement(&ad)).await {
+						warn!(error=%err, path, "failed to persist station hold");
+					}
+				}
+                let sent_at = tokio::time::Instant::now();
                 match state_for_boot.accept_advertisement(&ad).await {
                     Ok(assignment) => {
                         p2p_handle.publish_advertisement(ad.clone()).await;
+                        if let Some(profile) = &owner_profile {
+                            p2p_handle.publish_owner_profile(profile.clone()).await;
+                        }
+                        if refused_in_a_row > 0 {
+                            info!(refused = refused_in_a_row, "local advertisement accepted again");
+                        }
+                        refused_in_a_row = 0;
                         info!(frequency=%assignment.frequency, station_id=%assignment.station_id, "advertised station");
                     }
                     Err(err) => {
-                        warn!(error=%err, "local advertisement conflicted; will retry later");
+                        refused_in_a_row += 1;
+                        let listeners = state_for_boot.stream_listener_count();
+                        if listeners > 0 && refused_in_a_row == 1 {
+                            error!(
+                                code = err.code(), error=%err, listeners, frequency=%ls.frequency,
+                                "STATION ADVERTISEMENT REFUSED WHILE LISTENERS ARE TUNED IN: this node may no longer hold its frequency"
+                            );
+                        } else {
+                            warn!(code = err.code(), error=%err, listeners, refused = refused_in_a_row, "local advertisement refused; will retry later");
+                        }
                     }
                 }
- 				interval.tick().await;
+				// Sleep out the period for the current audience; the first listener arriving shortens it.
+				// Each period is cut short by a fresh random share, so stations started together spread out.
+				let stretch = 1.0 - heartbeat_jitter * rand::random::<f64>();
+				loop {
+					let period = if state_for_boot.stream_listener_count() > 0 { listener_period } else { idle_period };
+					let period = period.mul_f64(stretch);
+					state_for_boot.set_next_heartbeat(Some(now + chrono::Duration::from_std(period).unwrap_or_default())).await;
+					tokio::select! {
+						_ = tokio::time::sleep_until(sent_at + period) => break,
+						_ = state_for_boot.advertise_wake.notified() => break,
+						_ = state_for_boot.listeners_arrived.notified(), if period > listener_period => {}
+					}
+				}
  			}
  		}
  	});
 
-	// Background: IPC listener for NowPlaying
-	if let Some(sock) = config.ipc_socket.clone() {
+	// Background: advertise the station only while a source is sending audio
+	if let (Some(grace), true) = (config.release_without_source_secs, config.local_station.is_some()) {
 		let st = state.clone();
+		let grace = Duration::from_secs(grace);
+		// Startup counts as the last audio, so a source gets the grace period to connect
+		let started = tokio::time::Instant::now();
 		tokio::spawn(async move {
-			if let Err(err) = crate::ipc::run_ipc_listener(st, sock).await {
-				warn!(error=%err, "ipc listener exited");
+			let mut interval = tokio::time::interval(Duration::from_secs(1));
+			loop {
+				interval.tick().await;
+				let idle = st.ingest.idle_for().unwrap_or_else(|| started.elapsed());
+				let silent = idle >= grace;
+				if st.set_awaiting_source(silent) {
+					if silent {
+						info!(idle_secs = idle.as_secs(), "no source audio; releasing the station's frequency until a source returns");
+					} else {
+						info!("source audio is back; claiming the station's frequency again");
+					}
+				}
 			}
 		});
 	}
-	// Background: Audio IPC listener (raw bytes)
-	if let Some(sock) = config.audio_ipc_socket.clone() {
+
+	// Background: IPC listener for NowPlaying
+	if let Some(listener) = ipc_listener {
+		tokio::spawn(shortwave::ipc::run_ipc_listener(state.clone(), listener, config.ipc_max_connections));
+	}
+	if let Some(url) = config.now_webhook.clone() {
+		webhook::spawn_now_webhook(state.clone(), url, config.now_webhook_concurrency);
+	}
+	resources::spawn_resource_guard(
+		state.clone(),
+		config.min_free_memory_mb.saturating_mul(1024 * 1024),
+		config.min_free_disk_mb.saturating_mul(1024 * 1024),
+		config.audio_debug_capture.clone().map(std::path::PathBuf::from),
+	);
+	if let Some(path) = config.audio_debug_capture.clone() {
+		let max_age = (config.audio_debug_capture_max_secs > 0).then(|| std::time::Duration::from_secs(config.audio_debug_capture_max_secs));
+		capture::spawn_audio_capture(state.clone(), path, config.audio_debug_capture_max_bytes, max_age);
+	}
+	// Background: raw TCP audio ingest
+	if let Some(addr) = config.audio_tcp_ingest.clone() {
 		let st = state.clone();
+		let token = config.audio_tcp_token.clone();
 		tokio::spawn(async move {
-			if let Err(err) = crate::ipc::run_audio_ipc_listener(st, sock).await {
-				warn!(error=%err, "audio ipc listener exited");
+			if let Err(err) = shortwave::tcp_ingest::run_audio_tcp_listener(st, addr, token).await {
+				warn!(error=%err, "audio tcp listener exited");
 			}
 		});
 	}
+	// Background: Audio IPC listener (raw bytes)
+	if let Some(listener) = audio_ipc_listener {
+		tokio::spawn(shortwave::ipc::run_audio_ipc_listener(state.clone(), listener, config.audio_ipc_chunk_bytes));
+	}
 
  	// Background: periodic expiry cleanup
  	let expiry_state = state.clone();
@@ -166,49 +461,109 @@ use axum::middleware;
  		}
  	});
 
-	// Background: blocklist fetcher
-	if let Some(url) = config.blocklist_url.clone() {
+	// Background: stream healthcheck for ?status=unreachable
+	if let Some(interval) = state.stream_healthcheck {
+		shortwave::healthcheck::spawn_stream_healthcheck(state.clone(), interval);
+	}
+
+	// Background: allowed-owners file reload
+	if let Some(path) = config.allowed_owners_file.clone() {
 		let st = state.clone();
-		let refresh = config.blocklist_refresh_secs;
+		let static_owners = config.allowed_owners.clone();
 		tokio::spawn(async move {
-			let client = reqwest::Client::builder().no_proxy().build();
-			let mut interval = tokio::time::interval(Duration::from_secs(refresh as u64));
+			let mut interval = tokio::time::interval(Duration::from_secs(30));
+			interval.tick().await;
 			loop {
-				match &client {
-					Ok(c) => {
-						match c.get(&url).send().await {
-							Ok(resp) => {
-								if resp.status().is_success() {
-									if let Ok(body) = resp.text().await {
-										let mut set = std::collections::HashSet::new();
-										for line in body.lines() {
-											let mut s = line.trim();
-											if s.is_empty() || s.starts_with('#') { continue; }
-											if let Some((left, _)) = s.split_once('#') { s = left.trim(); }
-											if let Ok(ip) = s.parse::<std::net::IpAddr>() {
-												set.insert(ip);
-											}
-										}
-										st.set_blocklist(set).await;
-									}
-								}
-							}
-							Err(err) => {
-								warn!(error=%err, "blocklist fetch failed");
-							}
+				interval.tick().await;
+				match shortwave::config::read_allowed_owners_file(&path) {
+					Ok(keys) => {
+						if keys.is_empty() && static_owners.is_empty() {
+							warn!(path=%path, "allowed owners file is empty; no owner may advertise");
 						}
+						st.set_allowed_owners(static_owners.iter().cloned().chain(keys).collect()).await;
 					}
 					Err(err) => {
-						warn!(error=%err, "failed to build http client for blocklist");
+						warn!(error=%err, path=%path, "allowed owners reload failed; keeping previous set");
 					}
 				}
+			}
+		});
+	}
+
+	// Background: reload per-owner caps; file entries override static ones
+	if let Some(path) = config.owner_caps_file.clone() {
+		let st = state.clone();
+		let static_caps = config.owner_caps.clone();
+		tokio::spawn(async move {
+			let mut interval = tokio::time::interval(Duration::from_secs(30));
+			interval.tick().await;
+			loop {
 				interval.tick().await;
+				match shortwave::config::read_owner_caps_file(&path) {
+					Ok(caps) => {
+						st.set_owner_caps(static_caps.clone().into_iter().chain(caps).collect()).await;
+					}
+					Err(err) => {
+						warn!(error=%err, path=%path, "owner caps reload failed; keeping previous caps");
+					}
+				}
 			}
 		});
 	}
 
-	axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
- 	Ok(())
- }
+	// Background: incremental registry sync from configured HTTP peers
+	if !config.peers.is_empty() {
+		let st = state.clone();
+		let peers = config.peers.clone();
+		let (sync_min, sync_max) = (config.sync_min.clone(), config.sync_max.clone());
+		let cluster_secret = config.cluster_secret.clone();
+		tokio::spawn(async move {
+			let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build();
+			let Ok(client) = client else { return };
+			let mut since: std::collections::HashMap<String, DateTime<Utc>> = std::collections::HashMap::new();
+			let mut interval = tokio::time::interval(Duration::from_secs(30));
+			loop {
+				interval.tick().await;
+				for peer in &peers {
+					let base = peer.trim_end_matches('/');
+					let mut query: Vec<(&str, String)> = Vec::new();
+					if let Some(m) = &sync_min { query.push(("min", m.to_string())); }
+					if let Some(m) = &sync_max { query.push(("max", m.to_string())); }
+					if let Some(s) = since.get(base) { query.push(("since", s.to_rfc3339())); }
+					let mut req = client.get(format!("{}/api/v1/sync", base)).query(&query);
+					if let Some(secret) = &cluster_secret {
+						req = req.bearer_auth(secret);
+					}
+					let resp = match req.send().await {
+						Ok(r) if r.status().is_success() => r,
+						Ok(r) if r.status() == reqwest::StatusCode::UNAUTHORIZED => {
+							warn!(peer=%base, "peer sync refused: check that --cluster-secret matches on both nodes");
+							continue;
+						}
+						Ok(r) => { warn!(peer=%base, status=%r.status(), "peer sync failed"); continue; }
+						Err(err) => { warn!(peer=%base, error=%err, "peer sync failed"); continue; }
+					};
+					let Ok(body) = resp.json::<shortwave::types::SyncResponse>().await else { continue };
+					for a in &body.assignments {
+						if let Err(err) = st.import_signed_assignment(a).await {
+							debug!(peer=%base, frequency=%a.frequency, error=%err, "sync import rejected");
+						}
+					}
+					since.insert(base.to_string(), body.server_time);
+				}
+			}
+		});
+	}
 
+	// Background: blocklist fetcher
+	if let Some(url) = config.blocklist_url.clone() {
+		blocklist::spawn_blocklist_fetcher(state.clone(), url, Duration::from_secs(config.blocklist_refresh_secs as u64));
+	}
 
+	// Every listener feeds the same router; the first to fail takes the node down
+	let servers = listeners.into_iter().map(|(_, listener)| {
+		axum::serve(listener, app.clone().into_make_service_with_connect_info::<SocketAddr>()).into_future()
+	});
+	futures_util::future::try_join_all(servers).await?;
+ 	Ok(())
+ }
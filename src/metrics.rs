@@ -0,0 +1,160 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::state::AppState;
+
+/// Content type of the OpenMetrics exposition; anything else gets the Prometheus text format
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+const VERIFY_BUCKETS: &[f64] = &[0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+const CHUNK_SEND_BUCKETS: &[f64] = &[0.0001, 0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// The most recent observation that fell in a bucket, shown as an OpenMetrics exemplar
+struct Exemplar {
+	label_value: String,
+	value: f64,
+	/// Unix seconds
+	at: f64,
+}
+
+/// Fixed-bucket latency histogram. Observing is lock-free unless it carries an exemplar.
+pub struct Histogram {
+	name: &'static str,
+	help: &'static str,
+	bounds: &'static [f64],
+	/// One per bound plus +Inf, not cumulative
+	counts: Vec<AtomicU64>,
+	sum_nanos: AtomicU64,
+	/// Label naming what an exemplar was measured on, with one slot per bucket
+	exemplars: Option<(&'static str, Vec<Mutex<Option<Exemplar>>>)>,
+}
+
+impl Histogram {
+	fn new(name: &'static str, help: &'static str, bounds: &'static [f64]) -> Self {
+		Self {
+			name,
+			help,
+			bounds,
+			counts: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+			sum_nanos: AtomicU64::new(0),
+			exemplars: None,
+		}
+	}
+
+	fn with_exemplars(mut self, label: &'static str) -> Self {
+		self.exemplars = Some((label, (0..=self.bounds.len()).map(|_| Mutex::new(None)).collect()));
+		self
+	}
+
+	fn bucket(&self, seconds: f64) -> usize {
+		self.bounds.iter().position(|b| seconds <= *b).unwrap_or(self.bounds.len())
+	}
+
+	pub fn observe(&self, elapsed: Duration) {
+		self.counts[self.bucket(elapsed.as_secs_f64())].fetch_add(1, Ordering::Relaxed);
+		self.sum_nanos.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+	}
+
+	/// Observe, and keep `label_value` as the exemplar of the bucket it lands in
+	pub fn observe_with_exemplar(&self, elapsed: Duration, label_value: impl std::fmt::Display) {
+		self.observe(elapsed);
+		if let Some((_, slots)) = &self.exemplars {
+			let value = elapsed.as_secs_f64();
+			let at = Utc::now().timestamp_micros() as f64 / 1e6;
+			*slots[self.bucket(value)].lock().unwrap() = Some(Exemplar { label_value: label_value.to_string(), value, at });
+		}
+	}
+
+	fn render(&self, out: &mut String, openmetrics: bool) {
+		let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+		let _ = writeln!(out, "# TYPE {} histogram", self.name);
+		if openmetrics {
+			let _ = writeln!(out, "# UNIT {} seconds", self.name);
+		}
+		let mut cumulative = 0u64;
+		for (i, count) in self.counts.iter().enumerate() {
+			cumulative += count.load(Ordering::Relaxed);
+			let le = self.bounds.get(i).map_or_else(|| "+Inf".to_string(), |b| format!("{b:?}"));
+			let _ = write!(out, "{}_bucket{{le=\"{}\"}} {}", self.name, le, cumulative);
+			if let (true, Some((label, slots))) = (openmetrics, &self.exemplars) {
+				if let Some(ex) = slots[i].lock().unwrap().as_ref() {
+					let _ = write!(out, " # {{{}=\"{}\"}} {} {}", label, escape_label(&ex.label_value), ex.value, ex.at);
+				}
+			}
+			out.push('\n');
+		}
+		let _ = writeln!(out, "{}_sum {}", self.name, self.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9);
+		let _ = writeln!(out, "{}_count {}", self.name, cumulative);
+	}
+}
+
+fn escape_label(v: &str) -> String {
+	v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Latency histograms fed by the registry and the stream handler
+pub struct Metrics {
+	/// Advertisement signature checks, including the wait for a --verify-concurrency permit
+	pub verify_seconds: Histogram,
+	/// How long each audio chunk took to be written to a `/stream` listener
+	pub chunk_send_seconds: Histogram,
+}
+
+impl Default for Metrics {
+	fn default() -> Self {
+		Self {
+			verify_seconds: Histogram::new(
+				"shortwave_ad_verify_seconds",
+				"Advertisement signature verification time, including waiting for a verification slot",
+				VERIFY_BUCKETS,
+			).with_exemplars("station_id"),
+			chunk_send_seconds: Histogram::new(
+				"shortwave_stream_chunk_send_seconds",
+				"Time to write one audio chunk to a /stream listener",
+				CHUNK_SEND_BUCKETS,
+			),
+		}
+	}
+}
+
+/// Whether an Accept header asks for OpenMetrics
+pub fn wants_openmetrics(accept: Option<&str>) -> bool {
+	accept.is_some_and(|a| a.split(',').any(|part| part.trim().starts_with("application/openmetrics-text")))
+}
+
+/// The whole exposition: a few gauges and counters from the node's state, then the histograms.
+/// Exemplars are only part of OpenMetrics, which also names counter samples `_total` and ends in `# EOF`.
+pub async fn render(state: &AppState, openmetrics: bool) -> String {
+	let mut out = String::new();
+	gauge(&mut out, "shortwave_stream_listeners", "Open /stream responses", state.stream_listener_count() as u64);
+	gauge(&mut out, "shortwave_registry_stations", "Assignments in the registry", state.registry.read().await.len() as u64);
+	if let Some(limiter) = &state.rate_limiter {
+		counter(&mut out, "shortwave_http_rate_limited", "API requests refused with 429 by --rate-limit-per-sec", limiter.limited(), openmetrics);
+	}
+	counter(&mut out, "shortwave_gossip_ads_shed", "Gossiped advertisements dropped unverified because verification was saturated", state.gossip_ads_shed(), openmetrics);
+	state.metrics.verify_seconds.render(&mut out, openmetrics);
+	state.metrics.chunk_send_seconds.render(&mut out, openmetrics);
+	if openmetrics {
+		out.push_str("# EOF\n");
+	}
+	out
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: u64) {
+	let _ = writeln!(out, "# HELP {name} {help}");
+	let _ = writeln!(out, "# TYPE {name} gauge");
+	let _ = writeln!(out, "{name} {value}");
+}
+
+/// OpenMetrics names the family without `_total` and the sample with it; Prometheus text uses `_total` for both
+fn counter(out: &mut String, name: &str, help: &str, value: u64, openmetrics: bool) {
+	let family = if openmetrics { name.to_string() } else { format!("{name}_total") };
+	let _ = writeln!(out, "# HELP {family} {help}");
+	let _ = writeln!(out, "# TYPE {family} counter");
+	let _ = writeln!(out, "{name}_total {value}");
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+/// How often each node gossips its clock
+pub const TIME_SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+// Samples older than this no longer describe the peer's clock
+const SAMPLE_MAX_AGE: Duration = Duration::from_secs(600);
+// Below this many peers the median is too easy to steer, so no correction is applied
+const MIN_PEER_SAMPLES: usize = 3;
+// Bound on tracked peers and on the correction itself: even a malicious majority can only
+// move our notion of "now" by MAX_OFFSET
+const MAX_PEERS: usize = 256;
+const MAX_OFFSET_MS: i64 = 60_000;
+
+/// Median clock offset of gossip peers relative to ours. Samples are keyed by the gossipsub
+/// source PeerId (messages are signed), so one peer counts once however often it publishes.
+pub struct NetworkClock {
+	enabled: bool,
+	samples: Mutex<HashMap<String, (i64, Instant)>>,
+}
+
+impl NetworkClock {
+	pub fn new(enabled: bool) -> Self {
+		Self { enabled, samples: Mutex::new(HashMap::new()) }
+	}
+
+	pub fn is_enabled(&self) -> bool {
+		self.enabled
+	}
+
+	/// Record a peer's reported time as received now (propagation delay is ignored; it's far below the bound)
+	pub fn record(&self, peer: &str, sent_at: DateTime<Utc>) {
+		if !self.enabled { return; }
+		let offset_ms = (sent_at - Utc::now()).num_milliseconds();
+		let now = Instant::now();
+		let mut samples = self.samples.lock().unwrap();
+		samples.retain(|_, (_, at)| now.duration_since(*at) < SAMPLE_MAX_AGE);
+		if samples.len() >= MAX_PEERS && !samples.contains_key(peer) {
+			return;
+		}
+		samples.insert(peer.to_string(), (offset_ms, now));
+	}
+
+	/// Median offset in ms over peers plus ourselves (offset 0), clamped to ±MAX_OFFSET_MS
+	pub fn offset_ms(&self) -> i64 {
+		if !self.enabled { return 0; }
+		let now = Instant::now();
+		let samples = self.samples.lock().unwrap();
+		let mut offsets: Vec<i64> = samples.values()
+			.filter(|(_, at)| now.duration_since(*at) < SAMPLE_MAX_AGE)
+			.map(|(o, _)| *o)
+			.collect();
+		if offsets.len() < MIN_PEER_SAMPLES { return 0; }
+		offsets.push(0);
+		offsets.sort_unstable();
+		offsets[offsets.len() / 2].clamp(-MAX_OFFSET_MS, MAX_OFFSET_MS)
+	}
+
+	/// Local time corrected by the network offset
+	pub fn now(&self) -> DateTime<Utc> {
+		Utc::now() + chrono::Duration::milliseconds(self.offset_ms())
+	}
+}
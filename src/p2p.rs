@@ -1,7 +1,10 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
 use libp2p::{
+    core::{transport::MemoryTransport, upgrade, Transport as _},
+    allow_block_list::{self, BlockedPeers},
     gossipsub::{self, IdentTopic as Topic, MessageAuthenticity, ConfigBuilder as GossipsubConfigBuilder, ValidationMode, Event as GossipEvent},
     identity,
     mdns,
@@ -15,37 +18,285 @@ use libp2p::swarm::{behaviour::toggle::Toggle, NetworkBehaviour};
 use tokio::fs;
 use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
-use tracing::{debug, info, warn};
+use tokio::sync::{mpsc, watch, Semaphore};
+use tracing::{debug, info, trace, warn};
 use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
 
+use crate::config::P2PTransport;
 use crate::state::AppState;
-use crate::types::{ReleaseRequest, StationAdvertisement};
+use crate::nettime::TIME_SAMPLE_INTERVAL;
+use crate::state::RegistryError;
+use crate::types::{AdAck, OwnerProfile, ReleaseRequest, StationAdvertisement, StationAssignment, TimeSample};
+
+const ADVERTISE_TOPIC: &str = "shortwave/advertise/v1";
+const RELEASE_TOPIC: &str = "shortwave/release/v1";
+const PROFILE_TOPIC: &str = "shortwave/owner-profile/v1";
+const TIME_TOPIC: &str = "shortwave/time/v1";
+const ACK_TOPIC: &str = "shortwave/ad-ack/v1";
+
+// Gossipsub drops larger frames; decode re-checks so the guard doesn't depend on transport config
+const MAX_GOSSIP_BYTES: usize = 128 * 1024;
+// Our messages nest 3-4 levels; anything much deeper is hostile, and is refused before serde walks it
+const MAX_GOSSIP_DEPTH: usize = 16;
+// Repeats of a gossip warning within this window are counted and summarized instead of logged one by one
+const GOSSIP_WARN_WINDOW: Duration = Duration::from_secs(10);
+// A station's heartbeats get at most one ack from this node per window, unless the verdict changes
+const AD_ACK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const MAX_AD_ACK_STATIONS: usize = 4096;
 
 #[derive(NetworkBehaviour)]
 struct NodeBehaviour {
     pub gossipsub: gossipsub::Behaviour<gossipsub::IdentityTransform, gossipsub::AllowAllSubscriptionFilter>,
     pub mdns: Toggle<mdns::tokio::Behaviour>,
+    /// Operator bans: connections to and from these peers are denied, established ones closed
+    pub blocked: allow_block_list::Behaviour<BlockedPeers>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 enum GossipMessage {
-    Advertise(StationAdvertisement),
+    Advertise(Box<StationAdvertisement>),
     Release(ReleaseRequest),
+    OwnerProfile(OwnerProfile),
+    Time(TimeSample),
+    Ack(AdAck),
+}
+
+/// Topics are namespaced by `--network-id` so separate networks don't exchange (unverifiable) messages
+fn network_topic(network_id: Option<&str>, name: &str) -> Topic {
+    match network_id {
+        Some(id) => Topic::new(name.replacen("shortwave/", &format!("shortwave/{id}/"), 1)),
+        None => Topic::new(name),
+    }
+}
+
+/// Decode an untrusted gossip payload: bounded size and nesting first, then serde
+fn decode_gossip(data: &[u8]) -> Result<GossipMessage, &'static str> {
+    if data.len() > MAX_GOSSIP_BYTES {
+        return Err("oversized");
+    }
+    if json_depth_exceeds(data, MAX_GOSSIP_DEPTH) {
+        return Err("nested too deeply");
+    }
+    serde_json::from_slice(data).map_err(|_| "malformed")
+}
+
+/// Single pass over the bytes tracking `[`/`{` depth outside strings; doesn't validate the JSON
+fn json_depth_exceeds(data: &[u8], max: usize) -> bool {
+    let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+    for &b in data {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max { return true; }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// One kind of recurring gossip warning. The first occurrence in a window is logged as is; the rest
+/// are only counted and reported as one summary line once the window closes, so a peerless node or a
+/// persistently malformed sender can't flood the log during an incident.
+struct WarnSummary {
+    what: &'static str,
+    suppressed: u64,
+    last_detail: String,
+    window_start: Option<std::time::Instant>,
+}
+
+impl WarnSummary {
+    fn new(what: &'static str) -> Self {
+        Self { what, suppressed: 0, last_detail: String::new(), window_start: None }
+    }
+
+    fn record(&mut self, detail: impl std::fmt::Display) {
+        trace!(what = self.what, %detail, "gossip warning");
+        let now = std::time::Instant::now();
+        if self.window_start.is_some_and(|start| now.duration_since(start) < GOSSIP_WARN_WINDOW) {
+            self.suppressed += 1;
+            self.last_detail = detail.to_string();
+            return;
+        }
+        self.report_suppressed();
+        warn!(%detail, "{}", self.what);
+        self.window_start = Some(now);
+    }
+
+    /// Called on a timer so a burst's count is reported even if nothing follows it
+    fn flush(&mut self) {
+        if self.window_start.is_some_and(|start| start.elapsed() >= GOSSIP_WARN_WINDOW) {
+            self.report_suppressed();
+            self.window_start = None;
+        }
+    }
+
+    fn report_suppressed(&mut self) {
+        if self.suppressed > 0 {
+            warn!(count = self.suppressed, last = %self.last_detail, "{}: {} more in the last {}s", self.what, self.suppressed, GOSSIP_WARN_WINDOW.as_secs());
+            self.suppressed = 0;
+        }
+    }
+}
+
+/// The rate-limited warnings of the gossip event loop
+struct GossipWarnings {
+    invalid: WarnSummary,
+    publish: WarnSummary,
+    dial: WarnSummary,
+    shed: WarnSummary,
+}
+
+impl GossipWarnings {
+    fn new() -> Self {
+        Self {
+            invalid: WarnSummary::new("ignored invalid gossip message"),
+            publish: WarnSummary::new("gossip publish failed"),
+            dial: WarnSummary::new("mdns dial failed"),
+            shed: WarnSummary::new("dropped gossiped advertisement: signature verification saturated"),
+        }
+    }
+
+    fn flush(&mut self) {
+        self.invalid.flush();
+        self.publish.flush();
+        self.dial.flush();
+        self.shed.flush();
+    }
+}
+
+/// Decides which gossiped advertisements this node acknowledges. Every node in the mesh sees every
+/// heartbeat, so acks are rationed: per station, one decision per `AD_ACK_INTERVAL` (sooner if the
+/// verdict flips), and only a sampled fraction of acceptances. Conflicts are what an owner needs to
+/// hear about, so they aren't sampled.
+struct AckSampler {
+    sample: f64,
+    /// Station id -> when we last decided, and whether that ad was accepted
+    decided: HashMap<uuid::Uuid, (tokio::time::Instant, bool)>,
+}
+
+impl AckSampler {
+    fn new(sample: f64) -> Self {
+        Self { sample, decided: HashMap::new() }
+    }
+
+    fn ack_for(&mut self, ad: &StationAdvertisement, verdict: &Result<StationAssignment, RegistryError>) -> Option<AdAck> {
+        let (accepted, err) = match verdict {
+            Ok(_) => (true, None),
+            Err(err) if err.is_conflict() => (false, Some(err)),
+            Err(_) => return None,
+        };
+        if self.sample <= 0.0 {
+            return None;
+        }
+        let now = tokio::time::Instant::now();
+        if let Some((at, was_accepted)) = self.decided.get(&ad.station_id) {
+            if now.duration_since(*at) < AD_ACK_INTERVAL && *was_accepted == accepted {
+                return None;
+            }
+        }
+        if self.decided.len() >= MAX_AD_ACK_STATIONS {
+            self.decided.retain(|_, (at, _)| now.duration_since(*at) < AD_ACK_INTERVAL);
+        }
+        self.decided.insert(ad.station_id, (now, accepted));
+        if accepted && rand::random::<f64>() >= self.sample {
+            return None;
+        }
+        Some(AdAck {
+            message_id: ad.message_id,
+            station_id: ad.station_id,
+            owner_public_key: ad.owner_public_key.clone(),
+            accepted,
+            code: err.map(|e| e.code().to_string()),
+            reason: err.map(|e| e.to_string()),
+        })
+    }
 }
 
+/// Operator commands for the swarm, kept apart from what goes on the wire
+enum PeerControl {
+    Ban(PeerId),
+    Unban(PeerId),
+}
+
+#[derive(Clone)]
 pub struct P2PHandle {
     tx: mpsc::Sender<GossipMessage>,
+    control_tx: mpsc::Sender<PeerControl>,
+    peer_id: PeerId,
+    listen_addrs: watch::Receiver<Vec<Multiaddr>>,
 }
 
 impl P2PHandle {
+    pub fn peer_id(&self) -> PeerId {
+        self.peer_id
+    }
+
+    /// Addresses the swarm is listening on right now, with ports resolved (e.g. for `/tcp/0`)
+    pub fn listen_addrs(&self) -> Vec<Multiaddr> {
+        self.listen_addrs.borrow().clone()
+    }
+
+    /// Wait until the swarm listens on at least one address, so another node can bootstrap from it
+    pub async fn wait_listening(&self) -> Vec<Multiaddr> {
+        let mut rx = self.listen_addrs.clone();
+        // Err: the event loop is gone and nothing will ever listen
+        rx.wait_for(|addrs| !addrs.is_empty()).await.map(|addrs| addrs.clone()).unwrap_or_default()
+    }
+
     pub async fn publish_advertisement(&self, ad: StationAdvertisement) {
-        let _ = self.tx.send(GossipMessage::Advertise(ad)).await;
+        let _ = self.tx.send(GossipMessage::Advertise(Box::new(ad))).await;
     }
     pub async fn publish_release(&self, rel: ReleaseRequest) {
         let _ = self.tx.send(GossipMessage::Release(rel)).await;
     }
+    pub async fn publish_owner_profile(&self, profile: OwnerProfile) {
+        let _ = self.tx.send(GossipMessage::OwnerProfile(profile)).await;
+    }
+    pub async fn ban_peer(&self, peer: PeerId) {
+        let _ = self.control_tx.send(PeerControl::Ban(peer)).await;
+    }
+    pub async fn unban_peer(&self, peer: PeerId) {
+        let _ = self.control_tx.send(PeerControl::Unban(peer)).await;
+    }
+}
+
+fn build_behaviour(keys: &identity::Keypair, enable_mdns: bool, network_id: Option<&str>) -> NodeBehaviour {
+    let gossipsub_config = GossipsubConfigBuilder::default()
+        .validation_mode(ValidationMode::Strict)
+        .heartbeat_interval(Duration::from_secs(5))
+        .max_transmit_size(MAX_GOSSIP_BYTES)
+        .build()
+        .expect("gossipsub config");
+    let mut gs = gossipsub::Behaviour::<gossipsub::IdentityTransform, gossipsub::AllowAllSubscriptionFilter>::new(
+        MessageAuthenticity::Signed(keys.clone()),
+        gossipsub_config,
+    )
+    .expect("gossipsub behaviour");
+    let _ = gs.subscribe(&network_topic(network_id, ADVERTISE_TOPIC));
+    let _ = gs.subscribe(&network_topic(network_id, RELEASE_TOPIC));
+    let _ = gs.subscribe(&network_topic(network_id, PROFILE_TOPIC));
+    let _ = gs.subscribe(&network_topic(network_id, TIME_TOPIC));
+    let _ = gs.subscribe(&network_topic(network_id, ACK_TOPIC));
+    let mdns_behaviour = if enable_mdns {
+        Toggle::from(Some(mdns::tokio::Behaviour::new(mdns::Config::default(), PeerId::from(keys.public())).expect("mdns")))
+    } else {
+        Toggle::from(None)
+    };
+    NodeBehaviour { gossipsub: gs, mdns: mdns_behaviour, blocked: allow_block_list::Behaviour::default() }
 }
 
 pub async fn run_libp2p(
@@ -53,79 +304,59 @@ pub async fn run_libp2p(
     listen_addrs: Vec<String>,
     bootstrap: Vec<String>,
     enable_mdns: bool,
+    transport: P2PTransport,
     key_path: Option<String>,
+    key_seed: Option<String>,
 ) -> anyhow::Result<P2PHandle> {
-    // Load or generate a persistent libp2p identity key
-    let local_key = if let Some(path) = key_path {
-        match fs::read(&path).await {
-            Ok(bytes) => {
-                // First try protobuf-encoded Keypair
-                if let Ok(kp) = identity::Keypair::from_protobuf_encoding(&bytes) {
-                    kp
-                } else {
-                    // Fallback to raw/base64 32-byte ed25519 secret
-                    let mut raw = bytes;
-                    if raw.len() != 32 {
-                        if let Ok(s) = std::str::from_utf8(&raw) {
-                            if let Ok(decoded) = B64.decode(s.trim()) {
-                                raw = decoded;
-                            }
-                        }
-                    }
-                    let mut arr: [u8; 32] = raw.as_slice().try_into().map_err(|_| anyhow::anyhow!("invalid p2p key length"))?;
-                    let secret = libp2p::identity::ed25519::SecretKey::try_from_bytes(&mut arr)
-                        .map_err(|_| anyhow::anyhow!("invalid p2p key file (expect 32-byte ed25519 secret)"))?;
-                    let ed = libp2p::identity::ed25519::Keypair::from(secret);
-                    identity::Keypair::from(ed)
-                }
-            }
-            Err(_) => {
-                let kp = identity::Keypair::generate_ed25519();
-                if let Ok(bytes) = kp.to_protobuf_encoding() {
-                    let _ = fs::write(&path, bytes).await;
-                }
-                kp
-            }
-        }
-    } else {
-        identity::Keypair::generate_ed25519()
-    };
+    let local_key = load_identity(key_path, key_seed).await?;
     let local_peer_id = PeerId::from(local_key.public());
     info!(%local_peer_id, "libp2p starting");
+    let network_id = state.network_id.as_deref();
 
-    let mut swarm = SwarmBuilder::with_existing_identity(local_key.clone())
-        .with_tokio()
-        .with_tcp(
-            tcp::Config::default().nodelay(true),
-            noise::Config::new,
-            yamux::Config::default,
-        )?
-        .with_behaviour(move |keys| {
-            let gossipsub_config = GossipsubConfigBuilder::default()
-                .validation_mode(ValidationMode::Strict)
-                .heartbeat_interval(Duration::from_secs(5))
-                .max_transmit_size(1024 * 128)
-                .build()
-                .expect("gossipsub config");
-            let mut gs = gossipsub::Behaviour::<gossipsub::IdentityTransform, gossipsub::AllowAllSubscriptionFilter>::new(
-                MessageAuthenticity::Signed(keys.clone()),
-                gossipsub_config,
-            )
-            .expect("gossipsub behaviour");
-            let _ = gs.subscribe(&Topic::new("shortwave/advertise/v1"));
-            let _ = gs.subscribe(&Topic::new("shortwave/release/v1"));
-            let mdns_behaviour = if enable_mdns {
-                Toggle::from(Some(mdns::tokio::Behaviour::new(mdns::Config::default(), PeerId::from(keys.public())).expect("mdns")))
-            } else {
-                Toggle::from(None)
-            };
-            NodeBehaviour { gossipsub: gs, mdns: mdns_behaviour }
-        })?
-        .build();
+    // The builder's phases are distinct types, so each transport mix builds its own swarm
+    let builder = SwarmBuilder::with_existing_identity(local_key.clone()).with_tokio();
+    let mut swarm = match transport {
+        P2PTransport::Tcp => builder
+            .with_tcp(tcp::Config::default().nodelay(true), noise::Config::new, yamux::Config::default)?
+            .with_behaviour(|keys| build_behaviour(keys, enable_mdns, network_id))?
+            .build(),
+        P2PTransport::Quic => builder
+            .with_quic()
+            .with_behaviour(|keys| build_behaviour(keys, enable_mdns, network_id))?
+            .build(),
+        P2PTransport::Both => builder
+            .with_tcp(tcp::Config::default().nodelay(true), noise::Config::new, yamux::Config::default)?
+            .with_quic()
+            .with_behaviour(|keys| build_behaviour(keys, enable_mdns, network_id))?
+            .build(),
+        P2PTransport::Memory => builder
+            .with_other_transport(|keys| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+                Ok(MemoryTransport::default()
+                    .upgrade(upgrade::Version::V1)
+                    .authenticate(noise::Config::new(keys)?)
+                    .multiplex(yamux::Config::default()))
+            })?
+            .with_behaviour(|keys| build_behaviour(keys, enable_mdns, network_id))?
+            .build(),
+    };
+
+    // Bans restored from disk apply before the first dial
+    let mut banned: HashSet<PeerId> = state.banned_peers().await.into_iter().collect();
+    for peer in &banned {
+        swarm.behaviour_mut().blocked.block_peer(*peer);
+    }
 
     // Listen addresses
     if listen_addrs.is_empty() {
-        swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
+        if transport == P2PTransport::Memory {
+            swarm.listen_on("/memory/0".parse()?)?;
+        }
+        if matches!(transport, P2PTransport::Tcp | P2PTransport::Both) {
+            swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
+        }
+        if matches!(transport, P2PTransport::Quic | P2PTransport::Both) {
+            swarm.listen_on("/ip4/0.0.0.0/udp/0/quic-v1".parse()?)?;
+        }
     } else {
         for la in listen_addrs {
             match la.parse::<Multiaddr>() {
@@ -142,51 +373,173 @@ pub async fn run_libp2p(
     }
 
     let (tx, mut rx) = mpsc::channel::<GossipMessage>(128);
-    let handle = P2PHandle { tx: tx.clone() };
+    let (control_tx, mut control_rx) = mpsc::channel::<PeerControl>(16);
+    let (listen_tx, listen_rx) = watch::channel(Vec::new());
+    let handle = P2PHandle { tx: tx.clone(), control_tx, peer_id: local_peer_id, listen_addrs: listen_rx };
 
     let st = state.clone();
     tokio::spawn(async move {
+        // Latest advertisement per frequency that couldn't be published yet (e.g. no peers);
+        // re-sent when a peer subscribes. Receivers dedupe by message_id, so resending is idempotent.
+        let mut pending: HashMap<String, StationAdvertisement> = HashMap::new();
+        // Our latest owner profile, re-sent to each peer that subscribes to the profile topic
+        let mut own_profile: Option<OwnerProfile> = None;
+        let mut time_interval = tokio::time::interval(TIME_SAMPLE_INTERVAL);
+        let network_time = st.clock.is_enabled();
+        let network_id = st.network_id.as_deref();
+        let mut warnings = GossipWarnings::new();
+        // Finer than the window so a summary follows its window's end closely
+        let mut warn_flush = tokio::time::interval(GOSSIP_WARN_WINDOW / 10);
+        let mut acks = AckSampler::new(st.ad_ack_sample);
+        // Gossiped advertisements being verified; their verdicts come back for acking
+        let verifying = Arc::new(Semaphore::new(st.verify_concurrency));
+        let (verdict_tx, mut verdict_rx) = mpsc::channel::<(Box<StationAdvertisement>, Result<StationAssignment, RegistryError>)>(128);
         loop {
             tokio::select! {
                 Some(cmd) = rx.recv() => {
                     match cmd {
                         GossipMessage::Advertise(ad) => {
-                            if let Ok(bytes) = serde_json::to_vec(&GossipMessage::Advertise(ad)) {
-                                if let Err(err) = swarm.behaviour_mut().gossipsub.publish(Topic::new("shortwave/advertise/v1"), bytes) { warn!(error=%err, "gossip publish advertise failed"); }
+                            let key = crate::types::normalize_frequency_key(&ad.frequency);
+                            if publish_advertisement(&mut swarm, network_id, &ad, &mut warnings.publish) {
+                                pending.remove(&key);
+                            } else {
+                                pending.insert(key, *ad);
                             }
                         }
                         GossipMessage::Release(rel) => {
                             if let Ok(bytes) = serde_json::to_vec(&GossipMessage::Release(rel)) {
-                                if let Err(err) = swarm.behaviour_mut().gossipsub.publish(Topic::new("shortwave/release/v1"), bytes) { warn!(error=%err, "gossip publish release failed"); }
+                                if let Err(err) = swarm.behaviour_mut().gossipsub.publish(network_topic(network_id, RELEASE_TOPIC), bytes) {
+                                    warnings.publish.record(format_args!("release: {err}"));
+                                }
                             }
                         }
+                        GossipMessage::OwnerProfile(profile) => {
+                            publish_owner_profile(&mut swarm, network_id, &profile);
+                            own_profile = Some(profile);
+                        }
+                        // Only originated by the time ticker and received advertisements below
+                        GossipMessage::Time(_) | GossipMessage::Ack(_) => {}
+                    }
+                }
+                Some(control) = control_rx.recv() => {
+                    match control {
+                        // Also closes any open connection to the peer
+                        PeerControl::Ban(peer) => {
+                            banned.insert(peer);
+                            swarm.behaviour_mut().blocked.block_peer(peer);
+                        }
+                        PeerControl::Unban(peer) => {
+                            banned.remove(&peer);
+                            swarm.behaviour_mut().blocked.unblock_peer(peer);
+                        }
+                    }
+                }
+                Some((ad, verdict)) = verdict_rx.recv() => {
+                    if let Err(err) = &verdict {
+                        trace!(code = err.code(), error=%err, station_id=%ad.station_id, "gossiped advertisement not accepted");
+                    }
+                    if let Some(ack) = acks.ack_for(&ad, &verdict) {
+                        if let Ok(bytes) = serde_json::to_vec(&GossipMessage::Ack(ack)) {
+                            if let Err(err) = swarm.behaviour_mut().gossipsub.publish(network_topic(network_id, ACK_TOPIC), bytes) { trace!(error=%err, "gossip publish ad ack failed"); }
+                        }
+                    }
+                }
+                _ = warn_flush.tick() => warnings.flush(),
+                _ = time_interval.tick(), if network_time => {
+                    if let Ok(bytes) = serde_json::to_vec(&GossipMessage::Time(TimeSample { sent_at: chrono::Utc::now() })) {
+                        if let Err(err) = swarm.behaviour_mut().gossipsub.publish(network_topic(network_id, TIME_TOPIC), bytes) { trace!(error=%err, "gossip publish time sample failed"); }
                     }
                 }
                 event = swarm.next() => {
                     let Some(event) = event else { continue };
                     match event {
                         SwarmEvent::Behaviour(NodeBehaviourEvent::Gossipsub(GossipEvent::Message { message, .. })) => {
-                            if let Ok(g) = serde_json::from_slice::<GossipMessage>(&message.data) {
-                                match g {
-                                    GossipMessage::Advertise(ad) => {
-                                        let _ = st.accept_advertisement(&ad).await;
+                            // Our own publications echoed back; `source` is the signed originator, so
+                            // peers relaying someone else's message (multi-hop) still get through
+                            if message.source == Some(local_peer_id) {
+                                trace!("ignoring gossip echo of our own message");
+                                continue;
+                            }
+                            // A banned peer's messages relayed by others are dropped too
+                            if message.source.is_some_and(|p| banned.contains(&p)) {
+                                trace!(source = ?message.source, "ignoring gossip from banned peer");
+                                continue;
+                            }
+                            let g = match decode_gossip(&message.data) {
+                                Ok(g) => g,
+                                Err(reason) => {
+                                    let peer = message.source.map(|p| p.to_string()).unwrap_or_else(|| "unknown peer".into());
+                                    warnings.invalid.record(format_args!("{reason} (from {peer})"));
+                                    continue;
+                                }
+                            };
+                            match g {
+                                GossipMessage::Advertise(ad) => {
+                                    // Checked off the event loop. Past the limit the ad is dropped
+                                    // rather than queued: its next heartbeat supersedes it anyway
+                                    let Ok(permit) = verifying.clone().try_acquire_owned() else {
+                                        st.record_gossip_ad_shed();
+                                        warnings.shed.record(format_args!("station {}", ad.station_id));
+                                        continue;
+                                    };
+                                    let (st, verdict_tx) = (st.clone(), verdict_tx.clone());
+                                    tokio::spawn(async move {
+                                        let verdict = st.accept_advertisement(&ad).await;
+                                        drop(permit);
+                                        let _ = verdict_tx.send((ad, verdict)).await;
+                                    });
+                                }
+                                GossipMessage::Release(rel) => {
+                                    let _ = st.release_assignment(&rel).await;
+                                }
+                                GossipMessage::OwnerProfile(profile) => {
+                                    if let Err(err) = st.accept_owner_profile(&profile).await {
+                                        trace!(error=%err, owner=%profile.owner_public_key, "owner profile not accepted");
+                                    }
+                                }
+                                GossipMessage::Ack(ack) => {
+                                    // Counted per signed originator, like time samples
+                                    if let Some(source) = message.source {
+                                        if st.record_ad_ack(source.to_string(), &ack) {
+                                            debug!(peer = %source, station_id = %ack.station_id, accepted = ack.accepted, code = ?ack.code, "peer acknowledged our advertisement");
+                                        }
                                     }
-                                    GossipMessage::Release(rel) => {
-                                        let key = crate::types::normalize_frequency_key(&rel.frequency);
-                                        let _ = st.release_assignment(&key, rel.station_id, &rel.signature).await;
+                                }
+                                GossipMessage::Time(sample) => {
+                                    // Keyed by the signed originator so relays can't multiply one peer's vote
+                                    if let Some(source) = message.source {
+                                        st.clock.record(&source.to_string(), sample.sent_at);
                                     }
                                 }
                             }
                         }
+                        SwarmEvent::Behaviour(NodeBehaviourEvent::Gossipsub(GossipEvent::Subscribed { peer_id, topic }))
+                            if topic == network_topic(network_id, ADVERTISE_TOPIC).hash() && !pending.is_empty() =>
+                        {
+                            debug!(%peer_id, pending = pending.len(), "peer subscribed; flushing pending advertisements");
+                            flush_pending(&mut swarm, network_id, &mut pending, &mut warnings.publish);
+                        }
+                        SwarmEvent::Behaviour(NodeBehaviourEvent::Gossipsub(GossipEvent::Subscribed { topic, .. }))
+                            if topic == network_topic(network_id, PROFILE_TOPIC).hash() =>
+                        {
+                            if let Some(profile) = &own_profile {
+                                publish_owner_profile(&mut swarm, network_id, profile);
+                            }
+                        }
                         SwarmEvent::Behaviour(NodeBehaviourEvent::Mdns(mdns::Event::Discovered(list))) => {
                             for (_peer, addr) in list {
                                 if let Err(err) = swarm.dial(addr.clone()) {
-                                    warn!(error=%err, addr=%addr, "mdns dial failed");
+                                    warnings.dial.record(format_args!("{addr}: {err}"));
                                 }
                             }
                         }
                         SwarmEvent::NewListenAddr { address, .. } => {
                             info!(%address, "libp2p listening");
+                            listen_tx.send_modify(|addrs| addrs.push(address));
+                        }
+                        SwarmEvent::ExpiredListenAddr { address, .. } => {
+                            debug!(%address, "libp2p listen address expired");
+                            listen_tx.send_modify(|addrs| addrs.retain(|a| *a != address));
                         }
                         SwarmEvent::ConnectionEstablished { peer_id, .. } => { debug!(%peer_id, "connected"); }
                         SwarmEvent::ConnectionClosed { peer_id, .. } => { debug!(%peer_id, "disconnected"); }
@@ -200,4 +553,139 @@ pub async fn run_libp2p(
     Ok(handle)
 }
 
+/// Derive an Ed25519 identity from a seed: a base64 32-byte seed is used directly,
+/// anything else is treated as a passphrase and hashed with SHA-256
+fn keypair_from_seed(seed: &str) -> anyhow::Result<identity::Keypair> {
+    let bytes: [u8; 32] = match B64.decode(seed.trim()) {
+        Ok(raw) if raw.len() == 32 => raw.as_slice().try_into()?,
+        _ => Sha256::digest(seed.as_bytes()).into(),
+    };
+    identity::Keypair::ed25519_from_bytes(bytes).map_err(|e| anyhow::anyhow!("invalid p2p key seed: {}", e))
+}
 
+fn parse_key_file(bytes: Vec<u8>) -> anyhow::Result<identity::Keypair> {
+    // First try protobuf-encoded Keypair
+    if let Ok(kp) = identity::Keypair::from_protobuf_encoding(&bytes) {
+        return Ok(kp);
+    }
+    // Fallback to raw/base64 32-byte ed25519 secret
+    let mut raw = bytes;
+    if raw.len() != 32 {
+        if let Ok(s) = std::str::from_utf8(&raw) {
+            if let Ok(decoded) = B64.decode(s.trim()) {
+                raw = decoded;
+            }
+        }
+    }
+    let mut arr: [u8; 32] = raw.as_slice().try_into().map_err(|_| anyhow::anyhow!("invalid p2p key length"))?;
+    let secret = libp2p::identity::ed25519::SecretKey::try_from_bytes(&mut arr)
+        .map_err(|_| anyhow::anyhow!("invalid p2p key file (expect 32-byte ed25519 secret)"))?;
+    let ed = libp2p::identity::ed25519::Keypair::from(secret);
+    Ok(identity::Keypair::from(ed))
+}
+
+/// Load or generate the libp2p identity. A seed pins the identity; if a key file also exists it must agree.
+async fn load_identity(key_path: Option<String>, key_seed: Option<String>) -> anyhow::Result<identity::Keypair> {
+    let seeded = key_seed.as_deref().map(keypair_from_seed).transpose()?;
+    let Some(path) = key_path else {
+        return Ok(seeded.unwrap_or_else(identity::Keypair::generate_ed25519));
+    };
+    match fs::read(&path).await {
+        Ok(bytes) => {
+            let kp = parse_key_file(bytes)?;
+            if let Some(s) = &seeded {
+                if s.public() != kp.public() {
+                    anyhow::bail!(
+                        "p2p key file '{}' (PeerId {}) disagrees with --p2p-key-seed (PeerId {}); remove one",
+                        path, PeerId::from(kp.public()), PeerId::from(s.public())
+                    );
+                }
+            }
+            Ok(kp)
+        }
+        Err(_) => {
+            let kp = seeded.unwrap_or_else(identity::Keypair::generate_ed25519);
+            if let Ok(bytes) = kp.to_protobuf_encoding() {
+                let _ = fs::write(&path, bytes).await;
+            }
+            Ok(kp)
+        }
+    }
+}
+
+/// Publish an advertisement on the advertise topic; false if gossipsub refused it (no peers yet etc.)
+fn publish_advertisement(swarm: &mut libp2p::Swarm<NodeBehaviour>, network_id: Option<&str>, ad: &StationAdvertisement, warnings: &mut WarnSummary) -> bool {
+    let Ok(bytes) = serde_json::to_vec(&GossipMessage::Advertise(Box::new(ad.clone()))) else { return true };
+    match swarm.behaviour_mut().gossipsub.publish(network_topic(network_id, ADVERTISE_TOPIC), bytes) {
+        Ok(_) => true,
+        Err(gossipsub::PublishError::InsufficientPeers) => {
+            debug!(message_id=%ad.message_id, "no gossip peers yet; buffering advertisement");
+            false
+        }
+        Err(err) => {
+            warnings.record(format_args!("advertise: {err}"));
+            false
+        }
+    }
+}
+
+fn flush_pending(swarm: &mut libp2p::Swarm<NodeBehaviour>, network_id: Option<&str>, pending: &mut HashMap<String, StationAdvertisement>, warnings: &mut WarnSummary) {
+    let now = chrono::Utc::now();
+    // Expired advertisements are no longer worth sending
+    pending.retain(|_, ad| ad.advertised_at + chrono::Duration::seconds(ad.ttl_seconds as i64) > now);
+    pending.retain(|_, ad| !publish_advertisement(swarm, network_id, ad, warnings));
+}
+
+/// Publish our owner profile; it is re-sent on heartbeats and new subscriptions, so failures are only logged at debug
+fn publish_owner_profile(swarm: &mut libp2p::Swarm<NodeBehaviour>, network_id: Option<&str>, profile: &OwnerProfile) {
+    let Ok(bytes) = serde_json::to_vec(&GossipMessage::OwnerProfile(profile.clone())) else { return };
+    if let Err(err) = swarm.behaviour_mut().gossipsub.publish(network_topic(network_id, PROFILE_TOPIC), bytes) {
+        debug!(error=%err, "gossip publish owner profile failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{owner_key, signed_ad};
+
+    #[test]
+    fn oversized_gossip_is_refused_before_parsing() {
+        let mut data = br#"{"type":"Time","data":""#.to_vec();
+        data.resize(MAX_GOSSIP_BYTES + 1, b' ');
+        assert_eq!(decode_gossip(&data).unwrap_err(), "oversized");
+    }
+
+    #[test]
+    fn deeply_nested_gossip_is_refused() {
+        let data = format!(r#"{{"type":"Time","data":{}{}}}"#, "[".repeat(1_000), "]".repeat(1_000));
+        assert_eq!(decode_gossip(data.as_bytes()).unwrap_err(), "nested too deeply");
+        // Brackets inside strings are text, not nesting
+        assert!(!json_depth_exceeds(format!(r#"{{"name":"{}"}}"#, "[{".repeat(100)).as_bytes(), MAX_GOSSIP_DEPTH));
+    }
+
+    #[test]
+    fn malformed_gossip_is_refused() {
+        for data in [
+            &b""[..],
+            b"\xff\xfe",
+            b"{",
+            b"null",
+            br#"{"type":"Nonsense","data":{}}"#,
+            br#"{"type":"Release","data":{"station_id":"not-a-uuid"}}"#,
+            br#"{"type":"Advertise","data":{"frequency":"1e5000000"}}"#,
+        ] {
+            assert_eq!(decode_gossip(data).unwrap_err(), "malformed", "{}", String::from_utf8_lossy(data));
+        }
+    }
+
+    #[test]
+    fn advertisement_gossip_round_trips() {
+        let ad = signed_ad(&owner_key(), "101.1", uuid::Uuid::new_v4(), 1);
+        let data = serde_json::to_vec(&GossipMessage::Advertise(Box::new(ad.clone()))).unwrap();
+        match decode_gossip(&data) {
+            Ok(GossipMessage::Advertise(decoded)) => assert_eq!(decoded.signature, ad.signature),
+            other => panic!("unexpected decode: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use libp2p::PeerId;
+use tokio::fs;
+use tracing::warn;
+
+/// Read a ban file (a JSON array of PeerIds); a missing file is an empty list, an unreadable one
+/// or an unparseable entry is logged and skipped
+pub async fn load(path: &str) -> BTreeSet<PeerId> {
+	let data = match fs::read(path).await {
+		Ok(d) => d,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => return BTreeSet::new(),
+		Err(err) => {
+			warn!(error=%err, path, "failed to read p2p ban file");
+			return BTreeSet::new();
+		}
+	};
+	let entries: Vec<String> = match serde_json::from_slice(&data) {
+		Ok(e) => e,
+		Err(err) => {
+			warn!(error=%err, path, "ignoring invalid p2p ban file");
+			return BTreeSet::new();
+		}
+	};
+	entries
+		.iter()
+		.filter_map(|s| match PeerId::from_str(s) {
+			Ok(peer) => Some(peer),
+			Err(err) => {
+				warn!(error=%err, path, entry = %s, "ignoring invalid PeerId in p2p ban file");
+				None
+			}
+		})
+		.collect()
+}
+
+/// Write the ban list atomically (temp file + rename) so a crash mid-write can't lose it
+pub async fn save(path: &str, bans: &BTreeSet<PeerId>) -> anyhow::Result<()> {
+	let entries: Vec<String> = bans.iter().map(PeerId::to_string).collect();
+	let tmp = format!("{}.tmp", path);
+	fs::write(&tmp, serde_json::to_vec_pretty(&entries)?).await?;
+	fs::rename(&tmp, path).await?;
+	Ok(())
+}
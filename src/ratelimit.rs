@@ -0,0 +1,108 @@
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Clients tracked at once; past this, the client heard from least recently is forgotten
+pub const MAX_TRACKED_CLIENTS: usize = 65_536;
+
+struct Bucket {
+	tokens: f64,
+	refilled_at: Instant,
+}
+
+#[derive(Default)]
+struct Buckets {
+	by_client: HashMap<IpAddr, Bucket>,
+	/// (refilled_at, client) for every bucket, oldest first
+	by_age: BTreeSet<(Instant, IpAddr)>,
+}
+
+/// The key a client is limited under: IPv4 (including IPv4-mapped IPv6) by address, IPv6 by /64,
+/// since a single host is routinely handed a whole /64 to pick addresses from
+pub fn client_key(ip: IpAddr) -> IpAddr {
+	match ip.to_canonical() {
+		IpAddr::V6(v6) => IpAddr::V6(Ipv6Addr::from(u128::from(v6) & !(u64::MAX as u128))),
+		v4 => v4,
+	}
+}
+
+/// Per-client token buckets: each client (see `client_key`) may make `burst` requests at once and
+/// `rate` per second sustained. A client that has been quiet long enough to refill is
+/// indistinguishable from a new one, so evicting the least recently seen loses the least.
+pub struct RateLimiter {
+	rate: f64,
+	burst: f64,
+	buckets: Mutex<Buckets>,
+	limited: AtomicU64,
+}
+
+impl RateLimiter {
+	pub fn new(rate: f64, burst: u32) -> Self {
+		Self { rate, burst: burst.max(1) as f64, buckets: Mutex::new(Buckets::default()), limited: AtomicU64::new(0) }
+	}
+
+	/// Take a token for `ip`; Err holds how long until one is available
+	pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+		let now = Instant::now();
+		let key = client_key(ip);
+		let mut guard = self.buckets.lock().unwrap();
+		let Buckets { by_client, by_age } = &mut *guard;
+		if by_client.len() >= MAX_TRACKED_CLIENTS && !by_client.contains_key(&key) {
+			if let Some((_, oldest)) = by_age.pop_first() {
+				by_client.remove(&oldest);
+			}
+		}
+		let bucket = by_client.entry(key).or_insert(Bucket { tokens: self.burst, refilled_at: now });
+		by_age.remove(&(bucket.refilled_at, key));
+		by_age.insert((now, key));
+		bucket.tokens = (bucket.tokens + now.duration_since(bucket.refilled_at).as_secs_f64() * self.rate).min(self.burst);
+		bucket.refilled_at = now;
+		if bucket.tokens >= 1.0 {
+			bucket.tokens -= 1.0;
+			return Ok(());
+		}
+		self.limited.fetch_add(1, Ordering::Relaxed);
+		Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+	}
+
+	/// Requests refused so far
+	pub fn limited(&self) -> u64 {
+		self.limited.load(Ordering::Relaxed)
+	}
+
+	/// Clients currently holding a bucket
+	pub fn tracked(&self) -> usize {
+		self.buckets.lock().unwrap().by_client.len()
+	}
+}
+
+/// Byte-rate budget for one ingest connection: `rate` bytes per second with up to a second's worth
+/// banked. A frame larger than what's banked still goes through and the connection then waits off
+/// the debt, so a fast sender is slowed down rather than cut off.
+pub struct ByteThrottle {
+	rate: f64,
+	tokens: f64,
+	refilled_at: Instant,
+}
+
+impl ByteThrottle {
+	pub fn new(bytes_per_sec: u64) -> Self {
+		let rate = bytes_per_sec.max(1) as f64;
+		Self { rate, tokens: rate, refilled_at: Instant::now() }
+	}
+
+	/// Charge `bytes` received; returns how long to wait before reading more
+	pub fn charge(&mut self, bytes: usize) -> Duration {
+		let now = Instant::now();
+		self.tokens = (self.tokens + now.duration_since(self.refilled_at).as_secs_f64() * self.rate).min(self.rate);
+		self.refilled_at = now;
+		self.tokens -= bytes as f64;
+		if self.tokens >= 0.0 {
+			Duration::ZERO
+		} else {
+			Duration::from_secs_f64(-self.tokens / self.rate)
+		}
+	}
+}
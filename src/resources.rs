@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::state::AppState;
+use crate::types::ResourceStatus;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// `MemAvailable` from /proc/meminfo; None where that isn't available (non-Linux)
+pub fn memory_available() -> Option<u64> {
+	let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+	let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+	let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+	Some(kib * 1024)
+}
+
+/// Space available to unprivileged writers on the filesystem holding `path`
+pub fn disk_available(path: &Path) -> Option<u64> {
+	use std::os::unix::ffi::OsStrExt;
+	let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+	let mut st = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+	// SAFETY: c_path is NUL-terminated and st is only read after statvfs reports success
+	let st = unsafe {
+		if libc::statvfs(c_path.as_ptr(), st.as_mut_ptr()) != 0 {
+			return None;
+		}
+		st.assume_init()
+	};
+	Some((st.f_bavail as u64).saturating_mul(st.f_frsize as u64))
+}
+
+/// Watch available memory and disk so buffering degrades instead of exhausting a small host: below
+/// `min_memory` the burst/rewind buffer is suspended (and freed), below `min_disk` on the filesystem of
+/// `capture_path` the audio debug capture pauses. Each recovers once 25% above its threshold, so a
+/// reading hovering at the line doesn't flap. A zero threshold disables that check. The first check
+/// runs before returning, so features start out degraded on an already-starved host.
+pub fn spawn_resource_guard(state: Arc<AppState>, min_memory: u64, min_disk: u64, capture_path: Option<PathBuf>) {
+	let capture_dir = capture_path.map(|p| match p.parent() {
+		Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+		_ => PathBuf::from("."),
+	});
+	check(&state, min_memory, min_disk, capture_dir.as_deref());
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(CHECK_INTERVAL);
+		interval.tick().await;
+		loop {
+			interval.tick().await;
+			check(&state, min_memory, min_disk, capture_dir.as_deref());
+		}
+	});
+}
+
+/// Below `min` is low; it stays low until `min` plus a quarter is available again
+fn is_low(available: Option<u64>, min: u64, was_low: bool) -> bool {
+	match available {
+		Some(avail) if min > 0 => avail < if was_low { min.saturating_add(min / 4) } else { min },
+		_ => false,
+	}
+}
+
+fn check(state: &AppState, min_memory: u64, min_disk: u64, capture_dir: Option<&Path>) {
+	let previous = state.resource_status();
+	let memory_available_bytes = memory_available();
+	let disk_available_bytes = capture_dir.and_then(disk_available);
+	let low_memory = is_low(memory_available_bytes, min_memory, previous.as_ref().is_some_and(|s| s.low_memory));
+	let low_disk = is_low(disk_available_bytes, min_disk, previous.as_ref().is_some_and(|s| s.low_disk));
+
+	if state.burst.set_suspended(low_memory) && state.burst.is_configured() {
+		if low_memory {
+			warn!(available_bytes = memory_available_bytes, min_bytes = min_memory, "low memory: burst/rewind buffer suspended");
+		} else {
+			info!(available_bytes = memory_available_bytes, "memory recovered: burst/rewind buffer resumed");
+		}
+	}
+	if state.set_capture_paused(low_disk) && capture_dir.is_some() {
+		if low_disk {
+			warn!(available_bytes = disk_available_bytes, min_bytes = min_disk, "low disk space: audio debug capture paused");
+		} else {
+			info!(available_bytes = disk_available_bytes, "disk space recovered: audio debug capture resumed");
+		}
+	}
+	state.set_resource_status(ResourceStatus {
+		memory_available_bytes,
+		disk_available_bytes,
+		low_memory,
+		low_disk,
+		burst_enabled: state.burst.is_enabled(),
+		capture_paused: low_disk,
+		checked_at: Utc::now(),
+	});
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::sync::Semaphore;
+
+use crate::crypto::{check_signing_key, encode_public_key_b64, parse_sig_b64, self_test_probe, sign_bytes, verify_bytes};
+
+/// Connect, request and reply together; an agent waiting on a hardware token answers well within this
+const SIGNER_TIMEOUT: Duration = Duration::from_secs(10);
+// A base64 signature is 88 characters; anything much longer isn't one
+const MAX_SIGNER_REPLY_BYTES: u64 = 1024;
+
+/// Where owner signatures come from: the in-process key, or an external signer (an HSM or agent
+/// bridge) on a Unix socket, so the private key never lives in this process.
+///
+/// Socket protocol, one connection per signature: the node writes `SIGN <base64 message>\n` and
+/// the signer answers `<base64 signature>\n`, or `ERR <reason>\n` to refuse.
+pub enum SignerBackend {
+	Local(Arc<SigningKey>),
+	Socket { path: String, public_key: Box<VerifyingKey> },
+}
+
+/// A signer backend, with at most --sign-concurrency signatures in flight
+pub struct Signer {
+	backend: SignerBackend,
+	permits: Semaphore,
+}
+
+impl Signer {
+	pub fn new(backend: SignerBackend, concurrency: usize) -> Self {
+		Self { backend, permits: Semaphore::new(concurrency.max(1)) }
+	}
+
+	/// The owner key advertised alongside every signature
+	pub fn public_key(&self) -> VerifyingKey {
+		match &self.backend {
+			SignerBackend::Local(sk) => sk.verifying_key(),
+			SignerBackend::Socket { public_key, .. } => **public_key,
+		}
+	}
+
+	/// Sign `msg`, checking the result against the public key so a broken key or a misbehaving
+	/// signer is reported here instead of producing signatures every peer refuses
+	pub async fn sign(&self, msg: &[u8]) -> anyhow::Result<Signature> {
+		let _permit = self.permits.acquire().await.expect("signing semaphore is never closed");
+		let sig = match &self.backend {
+			// Offload CPU-heavy signing to blocking pool to avoid impacting audio streaming.
+			SignerBackend::Local(sk) => {
+				let (sk, msg) = (sk.clone(), msg.to_vec());
+				tokio::task::spawn_blocking(move || sign_bytes(&sk, &msg)).await
+					.map_err(|e| anyhow::anyhow!("signing task failed: {}", e))?
+			}
+			SignerBackend::Socket { path, .. } => tokio::time::timeout(SIGNER_TIMEOUT, sign_remote(path, msg)).await
+				.map_err(|_| anyhow::anyhow!("external signer {} did not answer within {}s", path, SIGNER_TIMEOUT.as_secs()))??,
+		};
+		verify_bytes(&self.public_key(), msg, &sig)
+			.map_err(|e| anyhow::anyhow!("signature does not match owner key {}: {}", encode_public_key_b64(&self.public_key()), e))?;
+		Ok(sig)
+	}
+
+	/// Startup check that signatures verify the way peers check them, so a wrong key or an
+	/// unreachable signer fails fast
+	pub async fn self_test(&self) -> anyhow::Result<()> {
+		match &self.backend {
+			SignerBackend::Local(sk) => check_signing_key(sk),
+			SignerBackend::Socket { .. } => self.sign(&self_test_probe(&self.public_key())).await.map(|_| ()),
+		}
+	}
+}
+
+async fn sign_remote(path: &str, msg: &[u8]) -> anyhow::Result<Signature> {
+	let stream = UnixStream::connect(path).await
+		.map_err(|e| anyhow::anyhow!("cannot connect to external signer {}: {}", path, e))?;
+	let (read, mut write) = stream.into_split();
+	write.write_all(format!("SIGN {}\n", B64.encode(msg)).as_bytes()).await?;
+	write.shutdown().await?;
+	let mut line = String::new();
+	BufReader::new(read.take(MAX_SIGNER_REPLY_BYTES)).read_line(&mut line).await?;
+	let line = line.trim();
+	if let Some(reason) = line.strip_prefix("ERR") {
+		anyhow::bail!("external signer refused: {}", reason.trim());
+	}
+	if line.is_empty() {
+		anyhow::bail!("external signer closed the connection without a signature");
+	}
+	parse_sig_b64(line).map_err(|e| anyhow::anyhow!("external signer sent an invalid signature: {}", e))
+}
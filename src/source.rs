@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::info;
+
+// A source that hasn't delivered bytes for this long is considered unhealthy
+const SOURCE_HEALTH_WINDOW: Duration = Duration::from_secs(3);
+
+/// Name used for the implicit source when no prioritized list is configured
+pub const DEFAULT_SOURCE: &str = "default";
+
+/// Name of the Unix audio IPC source in the prioritized list
+pub const AUDIO_IPC_SOURCE: &str = "audio-ipc";
+
+/// Name of the raw TCP audio ingest source in the prioritized list
+pub const AUDIO_TCP_SOURCE: &str = "audio-tcp";
+
+/// One configured ingest source; lower priority value wins
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct SourceSpec {
+	pub name: String,
+	pub priority: u32,
+	/// Masked when the config is printed
+	#[serde(serialize_with = "redact_token")]
+	pub token: Option<String>,
+}
+
+fn redact_token<S: serde::Serializer>(token: &Option<String>, s: S) -> Result<S::Ok, S::Error> {
+	serde::Serialize::serialize(&token.as_ref().map(|_| "<redacted>"), s)
+}
+
+impl FromStr for SourceSpec {
+	type Err = anyhow::Error;
+
+	/// Parses `name:priority[:token]`
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.splitn(3, ':');
+		let name = parts.next().unwrap_or("").trim();
+		if name.is_empty() {
+			anyhow::bail!("invalid source '{}': expected name:priority[:token]", s);
+		}
+		let priority = parts.next()
+			.ok_or_else(|| anyhow::anyhow!("invalid source '{}': missing priority", s))?
+			.trim()
+			.parse::<u32>()
+			.map_err(|e| anyhow::anyhow!("invalid source '{}': bad priority: {}", s, e))?;
+		let token = parts.next().map(|t| t.to_string()).filter(|t| !t.is_empty());
+		Ok(SourceSpec { name: name.to_string(), priority, token })
+	}
+}
+
+#[derive(Default)]
+struct SourceHealth {
+	last_bytes: Option<Instant>,
+	last_bytes_at: Option<DateTime<Utc>>,
+	healthy_since: Option<Instant>,
+	bytes_total: u64,
+}
+
+impl SourceHealth {
+	fn is_healthy(&self, now: Instant) -> bool {
+		self.last_bytes.map(|t| now.duration_since(t) < SOURCE_HEALTH_WINDOW).unwrap_or(false)
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceStatus {
+	pub name: String,
+	pub priority: u32,
+	pub healthy: bool,
+	pub active: bool,
+	pub last_bytes_at: Option<DateTime<Utc>>,
+	pub bytes_total: u64,
+}
+
+struct SelectorInner {
+	health: HashMap<String, SourceHealth>,
+	active: Option<String>,
+}
+
+/// Chooses which connected source feeds the broadcast: the healthiest, highest-priority one.
+/// Failback to a higher-priority source only happens once it has been healthy for the hold-down,
+/// and switches happen between chunks (a clean cut; compressed audio can't be crossfaded here).
+pub struct SourceSelector {
+	specs: Vec<SourceSpec>,
+	hold_down: Duration,
+	inner: Mutex<SelectorInner>,
+}
+
+impl SourceSelector {
+	pub fn new(mut specs: Vec<SourceSpec>, hold_down: Duration) -> Self {
+		specs.sort_by_key(|s| s.priority);
+		Self { specs, hold_down, inner: Mutex::new(SelectorInner { health: HashMap::new(), active: None }) }
+	}
+
+	/// True when a prioritized list is configured (otherwise every source is forwarded as before)
+	pub fn is_prioritized(&self) -> bool {
+		!self.specs.is_empty()
+	}
+
+	/// Resolve a bearer token to a configured source name. Tokenless sources (local sockets) are
+	/// never matched: they can't be reached over HTTP at all.
+	pub fn match_token(&self, token: &str) -> Option<&str> {
+		self.specs.iter()
+			.find(|s| s.token.as_deref().is_some_and(|t| crate::crypto::tokens_match(token, t)))
+			.map(|s| s.name.as_str())
+	}
+
+	fn priority_of(&self, name: &str) -> u32 {
+		self.specs.iter().find(|s| s.name == name).map(|s| s.priority).unwrap_or(u32::MAX)
+	}
+
+	/// Record a chunk from `name` and return whether it should be forwarded to listeners
+	pub fn on_chunk(&self, name: &str, len: usize) -> bool {
+		if !self.is_prioritized() {
+			let mut inner = self.inner.lock().unwrap();
+			record(inner.health.entry(name.to_string()).or_default(), len, Instant::now());
+			inner.active = Some(name.to_string());
+			return true;
+		}
+		let now = Instant::now();
+		let mut inner = self.inner.lock().unwrap();
+		record(inner.health.entry(name.to_string()).or_default(), len, now);
+
+		let current_ok = inner.active.as_ref()
+			.and_then(|a| inner.health.get(a))
+			.map(|h| h.is_healthy(now))
+			.unwrap_or(false);
+		let current_prio = inner.active.as_deref().map(|a| self.priority_of(a)).unwrap_or(u32::MAX);
+		let candidate = self.specs.iter().find(|s| {
+			let Some(h) = inner.health.get(&s.name) else { return false };
+			if !h.is_healthy(now) { return false; }
+			if !current_ok || inner.active.as_deref() == Some(s.name.as_str()) { return true; }
+			// Preempt a healthy active source only after the hold-down
+			s.priority < current_prio
+				&& h.healthy_since.map(|t| now.duration_since(t) >= self.hold_down).unwrap_or(false)
+		});
+		if let Some(c) = candidate {
+			if inner.active.as_deref() != Some(c.name.as_str()) {
+				info!(from=?inner.active, to=%c.name, "switching active source");
+				inner.active = Some(c.name.clone());
+			}
+		}
+		inner.active.as_deref() == Some(name)
+	}
+
+	pub fn active(&self) -> Option<String> {
+		self.inner.lock().unwrap().active.clone()
+	}
+
+	pub fn status(&self) -> Vec<SourceStatus> {
+		let now = Instant::now();
+		let inner = self.inner.lock().unwrap();
+		let mut names: Vec<(String, u32)> = self.specs.iter().map(|s| (s.name.clone(), s.priority)).collect();
+		for n in inner.health.keys() {
+			if !names.iter().any(|(x, _)| x == n) { names.push((n.clone(), self.priority_of(n))); }
+		}
+		names.into_iter().map(|(name, priority)| {
+			let h = inner.health.get(&name);
+			SourceStatus {
+				healthy: h.map(|h| h.is_healthy(now)).unwrap_or(false),
+				active: inner.active.as_deref() == Some(name.as_str()),
+				last_bytes_at: h.and_then(|h| h.last_bytes_at),
+				bytes_total: h.map(|h| h.bytes_total).unwrap_or(0),
+				name,
+				priority,
+			}
+		}).collect()
+	}
+}
+
+fn record(h: &mut SourceHealth, len: usize, now: Instant) {
+	if !h.is_healthy(now) {
+		h.healthy_since = Some(now);
+	}
+	h.last_bytes = Some(now);
+	h.last_bytes_at = Some(Utc::now());
+	h.bytes_total += len as u64;
+}
+
+// Window over which the ingest bitrate is estimated
+const BITRATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Guess an audio MIME type from the first bytes of a source connection
+pub fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+	if data.starts_with(b"OggS") {
+		Some("audio/ogg")
+	} else if data.starts_with(b"fLaC") {
+		Some("audio/flac")
+	} else if data.starts_with(b"ID3") {
+		Some("audio/mpeg")
+	} else if data.len() >= 2 && data[0] == 0xFF && (data[1] & 0xF6) == 0xF0 {
+		// ADTS sync word with layer bits 00
+		Some("audio/aac")
+	} else if data.len() >= 2 && data[0] == 0xFF && (data[1] & 0xE0) == 0xE0 {
+		Some("audio/mpeg")
+	} else {
+		None
+	}
+}
+
+/// Short codec name advertised for a MIME type, e.g. "audio/mpeg" -> "mp3"
+pub fn codec_for_content_type(content_type: &str) -> Option<String> {
+	let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+	let codec = match mime.as_str() {
+		"audio/mpeg" | "audio/mp3" => "mp3",
+		"audio/aac" | "audio/aacp" => "aac",
+		"audio/ogg" | "application/ogg" => "ogg",
+		"audio/opus" => "opus",
+		"audio/flac" => "flac",
+		other => other.strip_prefix("audio/")?,
+	};
+	Some(codec.to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestStatus {
+	/// Bytes were forwarded within the health window
+	pub active: bool,
+	pub active_source: Option<String>,
+	pub last_bytes_at: Option<DateTime<Utc>>,
+	pub content_type: Option<String>,
+	pub bitrate_kbps: Option<u32>,
+}
+
+#[derive(Default)]
+struct MonitorInner {
+	window: std::collections::VecDeque<(Instant, usize)>,
+	last_bytes: Option<Instant>,
+	last_bytes_at: Option<DateTime<Utc>>,
+	content_type: Option<String>,
+	sniff_next: bool,
+	/// Source whose chunks were recorded last; a switch to another one re-detects the codec
+	source: Option<String>,
+}
+
+/// Tracks what is actually being broadcast: bitrate over a sliding window and the detected codec
+#[derive(Default)]
+pub struct IngestMonitor {
+	inner: Mutex<MonitorInner>,
+}
+
+impl IngestMonitor {
+	/// A source connection started; use its declared type or sniff its first chunk. Returns the
+	/// declared type when it was taken.
+	pub fn begin(&self, source: &str, declared_content_type: Option<&str>) -> Option<String> {
+		let mut inner = self.inner.lock().unwrap();
+		inner.source = Some(source.to_string());
+		match declared_content_type.filter(|t| t.starts_with("audio/") || *t == "application/ogg") {
+			Some(t) => {
+				inner.content_type = Some(t.to_string());
+				inner.sniff_next = false;
+				inner.content_type.clone()
+			}
+			None => {
+				inner.sniff_next = true;
+				None
+			}
+		}
+	}
+
+	/// Account a broadcast chunk from `source`; returns the content type sniffed from it, if this
+	/// chunk is the one that determined it
+	pub fn record(&self, source: &str, chunk: &[u8]) -> Option<String> {
+		let now = Instant::now();
+		let mut inner = self.inner.lock().unwrap();
+		if inner.source.as_deref() != Some(source) {
+			inner.source = Some(source.to_string());
+			inner.sniff_next = true;
+		}
+		let mut sniffed = None;
+		if inner.sniff_next {
+			inner.sniff_next = false;
+			if let Some(t) = sniff_content_type(chunk) {
+				inner.content_type = Some(t.to_string());
+				sniffed = inner.content_type.clone();
+			}
+		}
+		inner.window.push_back((now, chunk.len()));
+		while inner.window.front().map(|(t, _)| now.duration_since(*t) > BITRATE_WINDOW).unwrap_or(false) {
+			inner.window.pop_front();
+		}
+		inner.last_bytes = Some(now);
+		inner.last_bytes_at = Some(Utc::now());
+		sniffed
+	}
+
+	pub fn bitrate_kbps(&self) -> Option<u32> {
+		let now = Instant::now();
+		let inner = self.inner.lock().unwrap();
+		let oldest = inner.window.iter().find(|(t, _)| now.duration_since(*t) <= BITRATE_WINDOW)?.0;
+		let bytes: usize = inner.window.iter().filter(|(t, _)| now.duration_since(*t) <= BITRATE_WINDOW).map(|(_, n)| n).sum();
+		// Never divide by less than a second so a single early chunk doesn't read as a huge rate
+		let secs = now.duration_since(oldest).as_secs_f64().max(1.0);
+		Some(((bytes as f64 * 8.0) / secs / 1000.0).round() as u32)
+	}
+
+	/// Time since the last broadcast chunk; None if there never was one
+	pub fn idle_for(&self) -> Option<Duration> {
+		self.inner.lock().unwrap().last_bytes.map(|t| t.elapsed())
+	}
+
+	pub fn status(&self, active_source: Option<String>) -> IngestStatus {
+		let bitrate_kbps = self.bitrate_kbps();
+		let inner = self.inner.lock().unwrap();
+		let active = inner.last_bytes.map(|t| t.elapsed() < SOURCE_HEALTH_WINDOW).unwrap_or(false);
+		IngestStatus {
+			active,
+			active_source: if active { active_source } else { None },
+			last_bytes_at: inner.last_bytes_at,
+			content_type: inner.content_type.clone(),
+			bitrate_kbps: if active { bitrate_kbps } else { None },
+		}
+	}
+}
+
+/// The most recent `max_bytes` of broadcast audio, sent to a listener immediately on connect so
+/// its jitter buffer fills faster than real time (Icecast's `burst-size`). After the burst the
+/// listener only gets live chunks, which arrive at the source's rate, so it can't run further ahead.
+///
+/// With a DVR window it also keeps the last `dvr_window` of audio (at most `dvr_max_bytes`), so a
+/// listener can start further back (`/stream?rewind=N`). The whole backlog is sent at once and the
+/// player works through it, so a rewound listener stays that far behind live.
+pub struct BurstBuffer {
+	max_bytes: usize,
+	dvr_window: Duration,
+	dvr_max_bytes: usize,
+	inner: Mutex<BufferedAudio>,
+	/// Set by the resource guard on low memory; listeners then start from live audio
+	suspended: AtomicBool,
+}
+
+#[derive(Default)]
+struct BufferedAudio {
+	chunks: std::collections::VecDeque<(Instant, bytes::Bytes)>,
+	bytes: usize,
+}
+
+impl BurstBuffer {
+	pub fn new(max_bytes: usize, dvr_window: Duration, dvr_max_bytes: usize) -> Self {
+		Self { max_bytes, dvr_window, dvr_max_bytes, inner: Mutex::new(BufferedAudio::default()), suspended: AtomicBool::new(false) }
+	}
+
+	/// Drop the buffered audio, e.g. when the source switched to another codec it can't be mixed with
+	pub fn clear(&self) {
+		*self.inner.lock().unwrap() = BufferedAudio::default();
+	}
+
+	/// Stop (freeing the buffered audio) or resume buffering; false if it was already in that state
+	pub fn set_suspended(&self, suspended: bool) -> bool {
+		let mut inner = self.inner.lock().unwrap();
+		if self.suspended.swap(suspended, Ordering::Relaxed) == suspended {
+			return false;
+		}
+		if suspended {
+			*inner = BufferedAudio::default();
+		}
+		true
+	}
+
+	pub fn is_configured(&self) -> bool {
+		self.max_bytes > 0 || self.has_dvr()
+	}
+
+	/// Configured and not suspended
+	pub fn is_enabled(&self) -> bool {
+		self.is_configured() && !self.suspended.load(Ordering::Relaxed)
+	}
+
+	pub fn has_dvr(&self) -> bool {
+		!self.dvr_window.is_zero() && self.dvr_max_bytes > 0
+	}
+
+	/// Remember a chunk and broadcast it; done under one lock so `subscribe` sees no gap or overlap
+	pub fn publish(&self, chunk: bytes::Bytes, tx: &tokio::sync::broadcast::Sender<bytes::Bytes>) {
+		let mut inner = self.inner.lock().unwrap();
+		if self.is_enabled() {
+			let now = Instant::now();
+			inner.bytes += chunk.len();
+			inner.chunks.push_back((now, chunk.clone()));
+			let cap = if self.has_dvr() { self.max_bytes.max(self.dvr_max_bytes) } else { self.max_bytes };
+			// Drop whole chunks so the burst starts where the source split its writes
+			while let Some((at, front)) = inner.chunks.front() {
+				let for_burst = inner.bytes <= self.max_bytes;
+				let for_dvr = self.has_dvr() && now.duration_since(*at) <= self.dvr_window;
+				if inner.bytes <= cap && (for_burst || for_dvr) {
+					break;
+				}
+				let len = front.len();
+				inner.chunks.pop_front();
+				inner.bytes -= len;
+			}
+		}
+		let _ = tx.send(chunk);
+	}
+
+	/// Subscribe to live audio, returning what precedes the first live chunk: the burst, or with
+	/// `rewind` the audio since that long ago (clamped to the oldest buffered chunk)
+	pub fn subscribe(&self, tx: &tokio::sync::broadcast::Sender<bytes::Bytes>, rewind: Option<Duration>) -> (Vec<bytes::Bytes>, tokio::sync::broadcast::Receiver<bytes::Bytes>) {
+		let inner = self.inner.lock().unwrap();
+		let backlog = match rewind.filter(|r| !r.is_zero() && self.has_dvr()) {
+			Some(rewind) => {
+				let now = Instant::now();
+				let rewind = rewind.min(self.dvr_window);
+				inner.chunks.iter().skip_while(|(at, _)| now.duration_since(*at) > rewind).map(|(_, c)| c.clone()).collect()
+			}
+			None => {
+				let mut total = 0;
+				let mut tail: Vec<bytes::Bytes> = inner.chunks.iter().rev()
+					.map(|(_, c)| c)
+					.take_while(|c| {
+						total += c.len();
+						total <= self.max_bytes
+					})
+					.cloned()
+					.collect();
+				tail.reverse();
+				tail
+			}
+		};
+		(backlog, tx.subscribe())
+	}
+}
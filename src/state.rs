@@ -1,13 +1,38 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
-use chrono::{Duration, Utc};
- use tokio::sync::{broadcast, RwLock};
+use libp2p::PeerId;
+
+use chrono::{DateTime, Duration, Utc};
+ use tokio::sync::{broadcast, watch, Notify, RwLock};
  use uuid::Uuid;
 
-use crate::types::{normalize_frequency_key, PeerInfo, RegistryEvent, StationAdvertisement, StationAssignment, NowPlaying};
-use crate::crypto::{parse_public_key_b64, parse_sig_b64, verify_bytes, canonicalize_ad_bytes, canonicalize_release_bytes};
+use bigdecimal::BigDecimal;
+use ed25519_dalek::{Signature, VerifyingKey};
+use crate::types::{normalize_frequency_key, AdAck, AdAckSummary, AdRejection, BlocklistStatus, ResourceStatus, SigningFailure, validate_logo_url, validate_stream_endpoints, validate_stream_format, NowPlaying, OwnerProfile, PeerInfo, RegistryEvent, ReleaseRequest, SharedEvent, StationAdvertisement, StationAssignment, StationStatus};
+use crate::config::{Config, LocalStationConfig, RegistryFullPolicy, SlowListenerPolicy};
+use crate::metrics::Metrics;
+use crate::ratelimit::RateLimiter;
+use crate::nettime::NetworkClock;
+use crate::p2p::P2PHandle;
+use crate::source::{codec_for_content_type, BurstBuffer, IngestMonitor, SourceSelector};
+use crate::store::RegistryStore;
+use crate::crypto::{parse_public_key_b64, parse_sig_b64, verify_bytes, canonicalize_profile_bytes, canonicalize_release_bytes};
 
 use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use sha2::{Digest, Sha256};
+
+/// What a message id stands for: owner, signature and signed bytes. Ed25519 signatures are
+/// deterministic, so a re-gossiped copy of the same advertisement hashes the same.
+fn ad_fingerprint(ad: &StationAdvertisement, signed_bytes: &[u8]) -> [u8; 32] {
+    let mut h = Sha256::new();
+    for part in [ad.owner_public_key.as_bytes(), ad.signature.as_bytes(), signed_bytes] {
+        h.update((part.len() as u64).to_be_bytes());
+        h.update(part);
+    }
+    h.finalize().into()
+}
 
  #[derive(thiserror::Error, Debug)]
  pub enum RegistryError {
@@ -19,75 +44,359 @@ use std::net::IpAddr;
     OwnerMismatch,
     #[error("owner cap exceeded")]
     OwnerCapExceeded,
+    #[error("owner not in allowlist")]
+    OwnerNotAllowed,
+    #[error("stale advertisement sequence {0} (have {1})")]
+    StaleSequence(u64, u64),
+    #[error("registry full")]
+    RegistryFull,
+    #[error("owner limit reached")]
+    OwnerLimitReached,
+    #[error("station {0} is held by a different owner key")]
+    StationOwnerMismatch(Uuid),
+    #[error("advertised_at is too far in the future")]
+    ClockSkew,
+    #[error("advertisement is {0}s old; at most {1}s accepted")]
+    TooOld(i64, i64),
+    #[error("invalid program: {0}")]
+    InvalidProgram(&'static str),
+    #[error("invalid stream format: {0}")]
+    InvalidFormat(&'static str),
+    #[error("invalid stream endpoints: {0}")]
+    InvalidEndpoints(&'static str),
+    #[error("invalid logo: {0}")]
+    InvalidLogo(&'static str),
+    #[error("invalid location: {0}")]
+    InvalidLocation(&'static str),
+    #[error("unsupported canonical version {0}")]
+    UnsupportedVersion(u32),
+    #[error("canonical version {0} is below this node's minimum of {1}")]
+    VersionTooOld(u32, u32),
+    #[error("message id {0} was already used for a different advertisement")]
+    MessageIdReused(Uuid),
+ }
+
+impl RegistryError {
+    /// Stable machine-readable cause; reasons (Display) may be reworded, codes may not
+    pub fn code(&self) -> &'static str {
+        match self {
+            RegistryError::FrequencyConflict(..) => "frequency_conflict",
+            RegistryError::InvalidSignature => "invalid_signature",
+            RegistryError::OwnerMismatch => "owner_mismatch",
+            RegistryError::OwnerCapExceeded => "owner_cap_exceeded",
+            RegistryError::OwnerNotAllowed => "owner_not_allowed",
+            RegistryError::StaleSequence(..) => "stale_sequence",
+            RegistryError::RegistryFull => "registry_full",
+            RegistryError::OwnerLimitReached => "owner_limit_reached",
+            RegistryError::StationOwnerMismatch(_) => "station_owner_mismatch",
+            RegistryError::ClockSkew => "clock_skew",
+            RegistryError::TooOld(..) => "too_old",
+            RegistryError::InvalidProgram(_) => "invalid_program",
+            RegistryError::InvalidFormat(_) => "invalid_format",
+            RegistryError::InvalidEndpoints(_) => "invalid_endpoints",
+            RegistryError::InvalidLogo(_) => "invalid_logo",
+            RegistryError::InvalidLocation(_) => "invalid_location",
+            RegistryError::UnsupportedVersion(_) => "unsupported_version",
+            RegistryError::VersionTooOld(..) => "version_too_old",
+            RegistryError::MessageIdReused(_) => "message_id_reused",
+        }
+    }
+
+    /// Someone else holds what the advertisement claims, as opposed to the ad itself being refused
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, RegistryError::FrequencyConflict(..) | RegistryError::OwnerMismatch | RegistryError::StationOwnerMismatch(_) | RegistryError::MessageIdReused(_))
+    }
+}
+
+ #[derive(thiserror::Error, Debug)]
+ #[error("now-playing update is {size} bytes (max {max})")]
+ pub struct NowPlayingTooLarge {
+ 	pub size: usize,
+ 	pub max: usize,
+ }
+
+ #[derive(thiserror::Error, Debug)]
+ pub enum ProfileError {
+ 	#[error("invalid signature")]
+ 	InvalidSignature,
+ 	#[error("invalid profile: {0}")]
+ 	Invalid(&'static str),
+ 	#[error("owner not in allowlist")]
+ 	OwnerNotAllowed,
+ 	#[error("not newer than the cached profile")]
+ 	NotNewer,
+ 	#[error("owner profile cache full")]
+ 	CacheFull,
+ }
+
+ // Bounds on cached owner profiles and their fields
+ const MAX_OWNER_PROFILES: usize = 4096;
+ const MAX_PROFILE_NAME_CHARS: usize = 64;
+ const MAX_PROFILE_URL_BYTES: usize = 512;
+ // Profiles dated further ahead than this are refused so they can't pin a key's profile
+ const MAX_PROFILE_CLOCK_SKEW_SECS: i64 = 300;
+ // Advertisements dated further ahead than this (by network time when enabled) are refused
+ const MAX_AD_CLOCK_SKEW_SECS: i64 = 300;
+ // Peer verdicts on our advertisements: how long each counts, how many peers per station, reason length
+ const AD_ACK_TTL_SECS: i64 = 30 * 60;
+ const MAX_AD_ACK_PEERS: usize = 1024;
+ const MAX_AD_ACK_REASON_CHARS: usize = 256;
+
+/// Whether `rel` applies to the held assignment `a`: same station, and no older than its last advertisement
+fn releases(a: &StationAssignment, rel: &ReleaseRequest) -> bool {
+    a.station_id == rel.station_id && rel.sequence >= a.sequence && rel.released_at >= a.last_seen
+}
+
+ /// One peer's latest verdict on a station of ours
+ struct PeerAck {
+ 	message_id: Uuid,
+ 	/// (code, reason) of a rejection
+ 	rejection: Option<(String, String)>,
+ 	at: DateTime<Utc>,
+ }
+
+ /// What a verified message id carried (`ad_fingerprint`) and when it was advertised
+ #[derive(Debug, Clone, Copy)]
+ pub struct SeenMessage {
+ 	pub fingerprint: [u8; 32],
+ 	pub advertised_at: DateTime<Utc>,
  }
 
  pub struct AppState {
  	pub node_id: Uuid,
  	pub public_url: String,
+	/// `--network-id`, when this node is on a separate network
+	pub network_id: Option<String>,
+	/// Prefix of everything signed or verified on this node's network (`crypto::signing_domain`)
+	pub signing_domain: String,
  	pub source_token: Option<String>,
+	pub admin_token: Option<String>,
+	pub cluster_secret: Option<String>,
 	pub max_frequencies_per_owner: u32,
+	pub max_ttl_secs: u32,
+	pub max_ad_age_fraction: f64,
+	/// Oldest canonical layout accepted (`--min-canonical-version`)
+	pub min_canonical_version: u32,
+	/// Fraction of accepted gossiped advertisements acknowledged to their owner
+	pub ad_ack_sample: f64,
+	/// Signature verifications allowed in flight on the blocking pool
+	pub verify_concurrency: usize,
+	verify_permits: tokio::sync::Semaphore,
+	gossip_ads_shed: AtomicU64,
+	pub metrics: Metrics,
+	pub max_registry_size: usize,
+	pub max_owners: usize,
+	pub registry_full_policy: RegistryFullPolicy,
+	pub max_now_playing_bytes: usize,
+	/// Base64 public key this node signs its own station with (None when not in station mode)
+	pub owner_public_key: Option<String>,
+	pub next_heartbeat_at: RwLock<Option<DateTime<Utc>>>,
+	/// Local advertising paused by an operator; the heartbeat loop waits on `advertise_wake`
+	pub advertise_paused: AtomicBool,
+	pub advertise_wake: Notify,
+	/// Set while --release-without-source-secs holds the frequency back for lack of source audio
+	awaiting_source: AtomicBool,
+	/// Open `/stream` responses; the heartbeat tightens while there are any
+	stream_listeners: AtomicUsize,
+	/// Signalled when the first listener tunes in, so a sleeping heartbeat switches interval
+	pub listeners_arrived: Notify,
+	/// Gossip handle, set once libp2p is up, so HTTP-submitted advertisements reach peers
+	pub gossip: OnceLock<P2PHandle>,
+	/// This node's station as advertised (after any hold-file restore)
+	pub local_station: OnceLock<LocalStationConfig>,
+	/// Successor stream URL after an admin transfer: the heartbeat advertises it and /stream redirects to it
+	pub handoff_stream_url: std::sync::RwLock<Option<String>>,
+	/// Sorted feature flags advertised in NodeInfo
+	pub capabilities: Vec<String>,
 
  	pub peers: RwLock<HashMap<String, PeerInfo>>, // key: api_base_url
     pub registry: RwLock<HashMap<String, StationAssignment>>, // key: normalized frequency string
- 	pub seen_messages: RwLock<HashSet<Uuid>>, // message dedupe
+ 	/// Message dedupe, pruned with expiry once an advertisement is older than the longest TTL we honour
+ 	pub seen_messages: RwLock<HashMap<Uuid, SeenMessage>>,
 
-    pub events_tx: broadcast::Sender<RegistryEvent>,
+    pub events_tx: broadcast::Sender<Arc<SharedEvent<RegistryEvent>>>,
+    /// Bumped on every registry mutation so pollers can cheaply detect change
+    pub generation: AtomicU64,
     pub audio_tx: broadcast::Sender<bytes::Bytes>,
-    pub now_tx: broadcast::Sender<NowPlaying>,
+    pub sources: SourceSelector,
+    pub ingest: IngestMonitor,
+    /// Codec of the audio being broadcast (e.g. "mp3"), once known; `/stream` listeners promised
+    /// another one are closed when it changes
+    pub active_codec: watch::Sender<Option<String>>,
+    pub burst: BurstBuffer,
+    pub slow_listener_policy: SlowListenerPolicy,
+    /// Silence after which a WebSocket peer is presumed dead; None disables the check
+    pub dead_peer_timeout: Option<std::time::Duration>,
+    /// Ingest cap of a WebSocket source in bytes per second; None disables it
+    pub source_ws_max_bytes_per_sec: Option<u64>,
+    pub now_tx: broadcast::Sender<Arc<SharedEvent<NowPlaying>>>,
     pub now_playing: RwLock<Option<NowPlaying>>,
 	pub blocklist: RwLock<std::collections::HashSet<IpAddr>>,
+	/// Fetcher health; None until the first fetch (or without --blocklist-url)
+	blocklist_status: std::sync::RwLock<Option<BlocklistStatus>>,
+	/// Interval of the stream healthcheck (`--stream-healthcheck-secs`); None when it doesn't run
+	pub stream_healthcheck: Option<std::time::Duration>,
+	/// Stream URL each station failed its last healthcheck on; replaced after every round
+	unreachable_streams: std::sync::RwLock<HashMap<Uuid, String>>,
+	/// Per-IP limit on the JSON API; None without --rate-limit-per-sec
+	pub rate_limiter: Option<RateLimiter>,
+	/// Last resource guard reading; None until its first check
+	resource_status: std::sync::RwLock<Option<ResourceStatus>>,
+	/// Set while the advertise loop can't sign heartbeats
+	signing_failure: std::sync::RwLock<Option<SigningFailure>>,
+	/// Verdicts peers sent on our stations' advertisements, by station then peer id
+	ad_acks: std::sync::RwLock<HashMap<Uuid, HashMap<String, PeerAck>>>,
+	/// Set by the resource guard when the capture's filesystem runs low
+	capture_paused: AtomicBool,
+	/// libp2p peers an operator banned; the swarm refuses connections from them
+	banned_peers: RwLock<BTreeSet<PeerId>>,
+	peer_ban_path: Option<String>,
+	/// Owner public keys permitted to advertise; None when no allowlist is configured (any owner).
+	/// A configured list that ends up empty admits no one rather than everyone.
+	pub allowed_owners: RwLock<Option<HashSet<String>>>,
+	/// Per-owner overrides of max_frequencies_per_owner
+	pub owner_caps: RwLock<HashMap<String, u32>>,
+	/// Verified owner profiles keyed by owner public key
+	pub owner_profiles: RwLock<HashMap<String, OwnerProfile>>,
+	pub clock: NetworkClock,
+	pub store: Arc<dyn RegistryStore>,
+	/// Signalled on every registry mutation; the store sync task saves a fresh snapshot
+	pub store_dirty: Notify,
  }
 
  impl AppState {
- 	pub fn new(
- 		node_id: Uuid,
- 		public_url: String,
- 		source_token: Option<String>,
-		max_frequencies_per_owner: u32,
- 	) -> Self {
+ 	pub fn new(config: &Config, owner_public_key: Option<String>) -> Self {
         let (events_tx, _events_rx) = broadcast::channel(1024);
         let (audio_tx, _audio_rx) = broadcast::channel(256);
         let (now_tx, _now_rx) = broadcast::channel(128);
 
  		Self {
- 			node_id,
- 			public_url,
- 			source_token,
-			max_frequencies_per_owner,
+ 			node_id: config.node_id,
+ 			public_url: config.public_url.clone(),
+			network_id: config.network_id.clone(),
+			signing_domain: crate::crypto::signing_domain(config.network_id.as_deref()),
+ 			source_token: config.source_token.clone(),
+			admin_token: config.admin_token.clone(),
+			cluster_secret: config.cluster_secret.clone(),
+			max_frequencies_per_owner: config.max_frequencies_per_owner,
+			max_ttl_secs: config.max_ttl_secs,
+			max_ad_age_fraction: config.max_ad_age_fraction,
+			min_canonical_version: config.min_canonical_version,
+			ad_ack_sample: config.ad_ack_sample,
+			verify_concurrency: config.verify_concurrency,
+			verify_permits: tokio::sync::Semaphore::new(config.verify_concurrency),
+			gossip_ads_shed: AtomicU64::new(0),
+			metrics: Metrics::default(),
+			max_registry_size: config.max_registry_size,
+			max_owners: config.max_owners,
+			registry_full_policy: config.registry_full_policy,
+			max_now_playing_bytes: config.max_now_playing_bytes,
+			owner_public_key,
+			next_heartbeat_at: RwLock::new(None),
+			advertise_paused: AtomicBool::new(false),
+			advertise_wake: Notify::new(),
+			awaiting_source: AtomicBool::new(false),
+			stream_listeners: AtomicUsize::new(0),
+			listeners_arrived: Notify::new(),
+			gossip: OnceLock::new(),
+			local_station: OnceLock::new(),
+			handoff_stream_url: std::sync::RwLock::new(None),
+			capabilities: config.capabilities(),
  			peers: RwLock::new(HashMap::new()),
  			registry: RwLock::new(HashMap::new()),
- 			seen_messages: RwLock::new(HashSet::new()),
+ 			seen_messages: RwLock::new(HashMap::new()),
             events_tx,
+            generation: AtomicU64::new(0),
             audio_tx,
+            ingest: IngestMonitor::default(),
+            active_codec: watch::Sender::new(None),
+            burst: BurstBuffer::new(config.burst_bytes, std::time::Duration::from_secs(config.dvr_secs as u64), config.dvr_max_bytes),
+            slow_listener_policy: config.audio_slow_listener_policy,
+            dead_peer_timeout: (config.dead_peer_timeout_secs > 0).then(|| std::time::Duration::from_secs(config.dead_peer_timeout_secs)),
+            source_ws_max_bytes_per_sec: (config.source_ws_max_kbps > 0).then(|| config.source_ws_max_kbps as u64 * 1000 / 8),
+            sources: SourceSelector::new(config.sources.clone(), std::time::Duration::from_secs(config.source_hold_down_secs as u64)),
             now_tx,
             now_playing: RwLock::new(None),
 			blocklist: RwLock::new(std::collections::HashSet::new()),
+			blocklist_status: std::sync::RwLock::new(None),
+			stream_healthcheck: (config.stream_healthcheck_secs > 0).then(|| std::time::Duration::from_secs(config.stream_healthcheck_secs)),
+			unreachable_streams: std::sync::RwLock::new(HashMap::new()),
+			rate_limiter: config.rate_limit_per_sec.map(|rate| RateLimiter::new(rate, config.rate_limit_burst)),
+			resource_status: std::sync::RwLock::new(None),
+			signing_failure: std::sync::RwLock::new(None),
+			ad_acks: std::sync::RwLock::new(HashMap::new()),
+			capture_paused: AtomicBool::new(false),
+			banned_peers: RwLock::new(BTreeSet::new()),
+			peer_ban_path: config.p2p_ban_path.clone(),
+			allowed_owners: RwLock::new(
+				(!config.allowed_owners.is_empty() || config.allowed_owners_file.is_some())
+					.then(|| config.allowed_owners.iter().cloned().collect()),
+			),
+			owner_caps: RwLock::new(config.owner_caps.clone()),
+			owner_profiles: RwLock::new(HashMap::new()),
+			clock: NetworkClock::new(config.network_time),
+			store: crate::store::open(&config.registry_store),
+			store_dirty: Notify::new(),
  		}
  	}
 
    pub async fn accept_advertisement(&self, ad: &StationAdvertisement) -> Result<StationAssignment, RegistryError> {
         let key = normalize_frequency_key(&ad.frequency);
-        {
-            let mut seen = self.seen_messages.write().await;
-            if !seen.insert(ad.message_id) {
-                // already processed
+        if ad.canonical_version < self.min_canonical_version {
+            return Err(RegistryError::VersionTooOld(ad.canonical_version, self.min_canonical_version));
+        }
+        let msg = ad.signed_bytes(&self.signing_domain).ok_or(RegistryError::UnsupportedVersion(ad.canonical_version))?;
+        let fingerprint = ad_fingerprint(ad, &msg);
+        match self.seen_messages.read().await.get(&ad.message_id) {
+            // Already processed: a true duplicate is benign, the same id carrying anything else is not
+            Some(seen) if seen.fingerprint == fingerprint => {
                 if let Some(existing) = self.registry.read().await.get(&key).cloned() {
                     return Ok(existing);
                 }
             }
+            Some(_) => return Err(RegistryError::MessageIdReused(ad.message_id)),
+            None => {}
+        }
+        if !self.is_owner_allowed(&ad.owner_public_key).await {
+            return Err(RegistryError::OwnerNotAllowed);
+        }
+        if ad.advertised_at > self.clock.now() + Duration::seconds(MAX_AD_CLOCK_SKEW_SECS) {
+            return Err(RegistryError::ClockSkew);
+        }
+        // A heartbeat delayed for most of its TTL would expire almost as soon as it's imported
+        if self.max_ad_age_fraction > 0.0 {
+            let max_age = (ad.ttl_seconds.min(self.max_ttl_secs) as f64 * self.max_ad_age_fraction) as i64;
+            let age = (self.clock.now() - ad.advertised_at).num_seconds();
+            if age > max_age {
+                return Err(RegistryError::TooOld(age, max_age));
+            }
+        }
+        if let Some(program) = &ad.program {
+            program.validate().map_err(RegistryError::InvalidProgram)?;
+        }
+        validate_stream_format(ad.bitrate_kbps, ad.codec.as_deref()).map_err(RegistryError::InvalidFormat)?;
+        validate_stream_endpoints(&ad.stream_urls).map_err(RegistryError::InvalidEndpoints)?;
+        if let Some(logo) = &ad.logo_url {
+            validate_logo_url(logo).map_err(RegistryError::InvalidLogo)?;
+        }
+        if let Some(location) = &ad.location {
+            location.validate().map_err(RegistryError::InvalidLocation)?;
         }
        // Verify signature for advertisement
        let vk = parse_public_key_b64(&ad.owner_public_key).map_err(|_| RegistryError::InvalidSignature)?;
-        let msg = canonicalize_ad_bytes(
-            "advertise",
-            &key,
-            &ad.station_id.to_string(),
-            &ad.stream_url,
-            &ad.advertised_at.to_rfc3339(),
-            ad.ttl_seconds,
-        );
        let sig = parse_sig_b64(&ad.signature).map_err(|_| RegistryError::InvalidSignature)?;
-        verify_bytes(&vk, &msg, &sig).map_err(|_| RegistryError::InvalidSignature)?;
+        if !self.verify_offloaded(vk, msg, sig, ad.station_id).await {
+            return Err(RegistryError::InvalidSignature);
+        }
+        // Ids are only recorded once verified, so unsigned junk can't claim an id ahead of the real ad
+        match self.seen_messages.write().await.entry(ad.message_id) {
+            std::collections::hash_map::Entry::Occupied(seen) if seen.get().fingerprint != fingerprint => {
+                return Err(RegistryError::MessageIdReused(ad.message_id));
+            }
+            std::collections::hash_map::Entry::Occupied(_) => {}
+            std::collections::hash_map::Entry::Vacant(slot) => { slot.insert(SeenMessage { fingerprint, advertised_at: ad.advertised_at }); }
+        }
+        let owner_cap = self.owner_cap(&ad.owner_public_key).await;
         let mut reg = self.registry.write().await;
         if let Some(existing) = reg.get(&key) {
  			if existing.station_id != ad.station_id {
@@ -95,18 +404,44 @@ use std::net::IpAddr;
  			}
             if existing.owner_public_key != ad.owner_public_key {
                 return Err(RegistryError::OwnerMismatch);
+            }
+            // Same owner and station: only move forward so duplicate deployments don't flap
+            if ad.sequence < existing.sequence {
+                return Err(RegistryError::StaleSequence(ad.sequence, existing.sequence));
             }
  		}
+        // A station_id belongs to one owner; another key reusing it (on any frequency) is refused
+        if reg.values().any(|a| a.station_id == ad.station_id && a.owner_public_key != ad.owner_public_key) {
+            return Err(RegistryError::StationOwnerMismatch(ad.station_id));
+        }
         if !reg.contains_key(&key) {
             let owner = &ad.owner_public_key;
             let count = reg.values().filter(|a| &a.owner_public_key == owner).count() as u32;
-            if count >= self.max_frequencies_per_owner {
+            if count >= owner_cap {
                 return Err(RegistryError::OwnerCapExceeded);
             }
+            // Owners are counted from live entries, so an owner's slot frees with its last frequency
+            if count == 0 && self.max_owners > 0 {
+                let owners: HashSet<&str> = reg.values().map(|a| a.owner_public_key.as_str()).collect();
+                if owners.len() >= self.max_owners {
+                    return Err(RegistryError::OwnerLimitReached);
+                }
+            }
+            if reg.len() >= self.max_registry_size {
+                match self.registry_full_policy {
+                    RegistryFullPolicy::Reject => return Err(RegistryError::RegistryFull),
+                    RegistryFullPolicy::EvictSoonest => {
+                        let victim = reg.iter().min_by_key(|(_, a)| a.expires_at).map(|(k, _)| k.clone());
+                        if let Some(removed) = victim.and_then(|k| reg.remove(&k)) {
+                            self.emit_registry_event("delete", removed, Some("evicted"));
+                        }
+                    }
+                }
+            }
         }
 
  		let created_at = Utc::now();
- 		let expires_at = ad.advertised_at + Duration::seconds(ad.ttl_seconds as i64);
+ 		let expires_at = ad.advertised_at + Duration::seconds(ad.ttl_seconds.min(self.max_ttl_secs) as i64);
         let assignment = StationAssignment {
  			station_id: ad.station_id,
             frequency: ad.frequency.clone(),
@@ -116,37 +451,63 @@ use std::net::IpAddr;
  			last_seen: ad.advertised_at,
  			expires_at,
             owner_public_key: ad.owner_public_key.clone(),
+            sequence: ad.sequence,
+            ttl_seconds: ad.ttl_seconds,
+            signature: ad.signature.clone(),
+            owner_name: None,
+            program: ad.program.clone(),
+            bitrate_kbps: ad.bitrate_kbps,
+            codec: ad.codec.clone(),
+            stream_urls: ad.stream_urls.clone(),
+            message_id: Some(ad.message_id),
+            canonical_version: ad.canonical_version,
+            logo_url: ad.logo_url.clone(),
+            location: ad.location.clone(),
+            local: false,
  		};
         reg.insert(key, assignment.clone());
- 		drop(reg);
- 		let _ = self.events_tx.send(RegistryEvent { event: "upsert".into(), assignment: assignment.clone() });
+ 		self.emit_registry_event("upsert", assignment.clone(), None);
  		Ok(assignment)
  	}
 
-  pub async fn release_assignment(&self, frequency_key: &str, station_id: Uuid, signature_b64: &str) -> bool {
+  /// Drop an assignment its owner released. A release older than the held advertisement, by
+  /// sequence or by time, is refused, as is one outside the clock window, so a captured release
+  /// can't take the station down again after it resumes.
+  pub async fn release_assignment(&self, rel: &ReleaseRequest) -> bool {
+       let frequency_key = normalize_frequency_key(&rel.frequency);
+       let now = self.clock.now();
+       if rel.released_at > now + Duration::seconds(MAX_AD_CLOCK_SKEW_SECS)
+           || rel.released_at < now - Duration::seconds(self.max_ttl_secs as i64)
+       {
+           return false;
+       }
        // First, read to verify
        let maybe_owner_pk = {
            let reg = self.registry.read().await;
-           match reg.get(frequency_key) {
-               Some(a) if a.station_id == station_id => Some(a.owner_public_key.clone()),
+           match reg.get(&frequency_key) {
+               Some(a) if releases(a, rel) => Some(a.owner_public_key.clone()),
                _ => None,
            }
        };
        let Some(owner_pk) = maybe_owner_pk else { return false };
        let vk = match parse_public_key_b64(&owner_pk) { Ok(v) => v, Err(_) => return false };
-       let msg = canonicalize_release_bytes("release", frequency_key, &station_id.to_string());
-       let sig = match parse_sig_b64(signature_b64) { Ok(s) => s, Err(_) => return false };
+       let msg = canonicalize_release_bytes(
+           &self.signing_domain,
+           "release.v2",
+           &frequency_key,
+           &rel.station_id.to_string(),
+           rel.sequence,
+           &rel.released_at.to_rfc3339(),
+       );
+       let sig = match parse_sig_b64(&rel.signature) { Ok(s) => s, Err(_) => return false };
        if verify_bytes(&vk, &msg, &sig).is_err() { return false; }
-       // Verified; proceed to remove
+       // Verified; proceed to remove unless a newer advertisement landed meanwhile
        let mut reg = self.registry.write().await;
-       if let Some(a) = reg.get(frequency_key) {
-           if a.station_id != station_id { return false; }
-       } else {
+       if !reg.get(&frequency_key).is_some_and(|a| releases(a, rel)) {
            return false;
        }
-       let removed = reg.remove(frequency_key).unwrap();
-       drop(reg);
-       let _ = self.events_tx.send(RegistryEvent { event: "delete".into(), assignment: removed });
+       let removed = reg.remove(&frequency_key).unwrap();
+       self.emit_registry_event("delete", removed, Some("released"));
        true
    }
 
@@ -165,19 +526,168 @@ use std::net::IpAddr;
  			let mut reg = self.registry.write().await;
  			for freq in to_remove {
  				if let Some(removed) = reg.remove(&freq) {
- 					let _ = self.events_tx.send(RegistryEvent { event: "delete".into(), assignment: removed });
+ 					self.emit_registry_event("delete", removed, Some("expired"));
  				}
  			}
  		}
+ 		// Anything advertised before this has expired everywhere, so its id no longer needs remembering
+ 		let horizon = now - Duration::seconds(self.max_ttl_secs as i64);
+ 		self.seen_messages.write().await.retain(|_, seen| seen.advertised_at > horizon);
  		Ok(())
  	}
 
+ 	/// Record a registry mutation: bump the generation and notify event subscribers.
+ 	/// Called with the registry write lock held, which is what makes `subscribe_with_snapshot` gap-free.
+ 	fn emit_registry_event(&self, event: &str, assignment: StationAssignment, reason: Option<&str>) {
+ 		self.generation.fetch_add(1, Ordering::SeqCst);
+ 		self.store_dirty.notify_one();
+ 		// Building the event (owner proof included) is skipped while nobody is subscribed. Mutations
+ 		// emit under the registry write lock, so a snapshot subscriber can't slip in between.
+ 		if self.events_tx.receiver_count() > 0 {
+ 			let _ = self.events_tx.send(SharedEvent::new(RegistryEvent::new(event, assignment, &self.signing_domain).with_reason(reason)));
+ 		}
+ 	}
+
+ 	/// Subscribe to registry events together with synthetic upserts for the current live entries.
+ 	/// Both are taken under the registry read lock, so every later mutation arrives on the receiver
+ 	/// and none of them is already reflected in the snapshot.
+ 	pub async fn subscribe_with_snapshot(&self) -> (Vec<Arc<SharedEvent<RegistryEvent>>>, broadcast::Receiver<Arc<SharedEvent<RegistryEvent>>>) {
+ 		let now = Utc::now();
+ 		let reg = self.registry.read().await;
+ 		let rx = self.events_tx.subscribe();
+ 		let mut live: Vec<StationAssignment> = reg.values().filter(|a| a.expires_at > now).cloned().collect();
+ 		drop(reg);
+ 		live.sort_by(|a, b| a.frequency.cmp(&b.frequency).then_with(|| a.station_id.cmp(&b.station_id)));
+ 		(live.into_iter().map(|a| SharedEvent::new(RegistryEvent::new("upsert", a, &self.signing_domain))).collect(), rx)
+ 	}
+
+ 	/// Subscribe to registry events for a paged snapshot (see `backfill_page`), together with the
+ 	/// registry generation the subscription starts at
+ 	pub async fn subscribe_for_backfill(&self) -> (broadcast::Receiver<Arc<SharedEvent<RegistryEvent>>>, u64) {
+ 		let _reg = self.registry.read().await;
+ 		(self.events_tx.subscribe(), self.registry_generation())
+ 	}
+
+ 	/// One step of a paged snapshot: the events `rx` has received so far, then up to `limit` live
+ 	/// assignments above frequency `after`, in frequency order. Both are taken under the registry
+ 	/// read lock, so each returned event predates the page and each later event postdates it.
+ 	/// Err(skipped) when the receiver lagged and events were lost.
+ 	pub async fn backfill_page(
+ 		&self,
+ 		rx: &mut broadcast::Receiver<Arc<SharedEvent<RegistryEvent>>>,
+ 		after: Option<&BigDecimal>,
+ 		limit: usize,
+ 	) -> Result<(Vec<Arc<SharedEvent<RegistryEvent>>>, Vec<StationAssignment>), u64> {
+ 		let now = Utc::now();
+ 		let reg = self.registry.read().await;
+ 		let mut events = Vec::new();
+ 		loop {
+ 			match rx.try_recv() {
+ 				Ok(e) => events.push(e),
+ 				Err(broadcast::error::TryRecvError::Lagged(n)) => return Err(n),
+ 				Err(_) => break,
+ 			}
+ 		}
+ 		let mut page: Vec<&StationAssignment> = reg.values()
+ 			.filter(|a| a.expires_at > now && after.is_none_or(|f| a.frequency > *f))
+ 			.collect();
+ 		if page.len() > limit {
+ 			page.select_nth_unstable_by(limit, |a, b| a.frequency.cmp(&b.frequency));
+ 			page.truncate(limit);
+ 		}
+ 		page.sort_by(|a, b| a.frequency.cmp(&b.frequency));
+ 		Ok((events, page.into_iter().cloned().collect()))
+ 	}
+
+ 	pub fn registry_generation(&self) -> u64 {
+ 		self.generation.load(Ordering::SeqCst)
+ 	}
+
+ 	pub async fn count_live(&self) -> usize {
+ 		let now = Utc::now();
+ 		self.registry.read().await.values().filter(|a| a.expires_at > now).count()
+ 	}
+
+ 	/// Live assignments ordered by frequency ascending (ties broken by station_id) so output is stable
  	pub async fn snapshot_registry(&self) -> Vec<StationAssignment> {
  		let now = Utc::now();
  		let reg = self.registry.read().await;
- 		reg.values().filter(|a| a.expires_at > now).cloned().collect()
+ 		let mut out: Vec<StationAssignment> = reg.values().filter(|a| a.expires_at > now).cloned().collect();
+ 		drop(reg);
+ 		out.sort_by(|a, b| a.frequency.cmp(&b.frequency).then_with(|| a.station_id.cmp(&b.station_id)));
+ 		out
  	}
 
+    /// Live assignments held by this node's own owner key
+    pub async fn my_assignments(&self) -> Vec<StationAssignment> {
+        let Some(me) = &self.owner_public_key else { return Vec::new() };
+        self.snapshot_registry().await.into_iter().filter(|a| &a.owner_public_key == me).collect()
+    }
+
+    pub async fn set_next_heartbeat(&self, at: Option<DateTime<Utc>>) {
+        *self.next_heartbeat_at.write().await = at;
+    }
+
+    /// Pause or resume the local heartbeat; returns the previous state. On a change the loop is woken
+    /// so a pause releases the frequency and a resume re-advertises without waiting for the interval.
+    pub fn set_advertise_paused(&self, paused: bool) -> bool {
+        let was = self.advertise_paused.swap(paused, Ordering::SeqCst);
+        if was != paused {
+            self.advertise_wake.notify_one();
+        }
+        was
+    }
+
+    /// Point the local station at a successor's stream and re-advertise right away
+    pub fn set_handoff_stream_url(&self, url: String) {
+        *self.handoff_stream_url.write().unwrap() = Some(url);
+        self.advertise_wake.notify_one();
+    }
+
+    pub fn stream_listener_count(&self) -> usize {
+        self.stream_listeners.load(Ordering::Relaxed)
+    }
+
+    /// ed25519 verification on the blocking pool, `verify_concurrency` at a time, so a flood of
+    /// signatures to check can't occupy the runtime threads audio is served from
+    async fn verify_offloaded(&self, vk: VerifyingKey, msg: Vec<u8>, sig: Signature, station_id: Uuid) -> bool {
+        let started = std::time::Instant::now();
+        let Ok(_permit) = self.verify_permits.acquire().await else { return false };
+        let ok = tokio::task::spawn_blocking(move || verify_bytes(&vk, &msg, &sig).is_ok()).await.unwrap_or(false);
+        self.metrics.verify_seconds.observe_with_exemplar(started.elapsed(), station_id);
+        ok
+    }
+
+    pub fn record_gossip_ad_shed(&self) {
+        self.gossip_ads_shed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn gossip_ads_shed(&self) -> u64 {
+        self.gossip_ads_shed.load(Ordering::Relaxed)
+    }
+
+    pub fn handoff_stream_url(&self) -> Option<String> {
+        self.handoff_stream_url.read().unwrap().clone()
+    }
+
+    pub fn is_advertise_paused(&self) -> bool {
+        self.advertise_paused.load(Ordering::SeqCst)
+    }
+
+    /// Hold advertising back until source audio returns (or let it go again); true on a change,
+    /// which wakes the heartbeat so the frequency is released or re-claimed right away
+    pub fn set_awaiting_source(&self, waiting: bool) -> bool {
+        let changed = self.awaiting_source.swap(waiting, Ordering::SeqCst) != waiting;
+        if changed {
+            self.advertise_wake.notify_one();
+        }
+        changed
+    }
+
+    pub fn is_awaiting_source(&self) -> bool {
+        self.awaiting_source.load(Ordering::SeqCst)
+    }
+
     pub async fn get_assignment_by_key(&self, frequency_key: &str) -> Option<StationAssignment> {
         self.registry.read().await.get(frequency_key).cloned()
  	}
@@ -191,12 +701,261 @@ use std::net::IpAddr;
 		*bl = ips;
 	}
 
+	/// Load persisted peer bans; call before libp2p starts so they apply to the first connections
+	pub async fn load_peer_bans(&self) {
+		if let Some(path) = &self.peer_ban_path {
+			*self.banned_peers.write().await = crate::peer_bans::load(path).await;
+		}
+	}
+
+	pub async fn banned_peers(&self) -> Vec<PeerId> {
+		self.banned_peers.read().await.iter().copied().collect()
+	}
+
+	/// Ban a libp2p peer: its connections are closed and refused from now on. False if already banned.
+	pub async fn ban_peer(&self, peer: PeerId) -> bool {
+		if !self.banned_peers.write().await.insert(peer) {
+			return false;
+		}
+		tracing::info!(%peer, "banned p2p peer");
+		if let Some(gossip) = self.gossip.get() {
+			gossip.ban_peer(peer).await;
+		}
+		self.save_peer_bans().await;
+		true
+	}
+
+	/// Lift a ban; the peer may connect again (nothing redials it until mDNS or it does). False if not banned.
+	pub async fn unban_peer(&self, peer: PeerId) -> bool {
+		if !self.banned_peers.write().await.remove(&peer) {
+			return false;
+		}
+		tracing::info!(%peer, "unbanned p2p peer");
+		if let Some(gossip) = self.gossip.get() {
+			gossip.unban_peer(peer).await;
+		}
+		self.save_peer_bans().await;
+		true
+	}
+
+	async fn save_peer_bans(&self) {
+		let Some(path) = &self.peer_ban_path else { return };
+		let bans = self.banned_peers.read().await.clone();
+		if let Err(err) = crate::peer_bans::save(path, &bans).await {
+			tracing::warn!(error=%err, path, "failed to persist p2p ban list; the change holds until restart");
+		}
+	}
+
+	pub fn set_resource_status(&self, status: ResourceStatus) {
+		*self.resource_status.write().unwrap() = Some(status);
+	}
+
+	pub fn resource_status(&self) -> Option<ResourceStatus> {
+		self.resource_status.read().unwrap().clone()
+	}
+
+	/// Count a failed heartbeat signature; returns the failures in a row so far
+	pub fn record_signing_failure(&self, error: String) -> u32 {
+		let mut guard = self.signing_failure.write().unwrap();
+		let failure = guard.get_or_insert_with(|| SigningFailure { error: String::new(), consecutive_failures: 0, since: Utc::now() });
+		failure.error = error;
+		failure.consecutive_failures += 1;
+		failure.consecutive_failures
+	}
+
+	/// Signing works again; returns the failures in a row it ends, if any
+	pub fn clear_signing_failure(&self) -> Option<u32> {
+		self.signing_failure.write().unwrap().take().map(|f| f.consecutive_failures)
+	}
+
+	pub fn signing_failure(&self) -> Option<SigningFailure> {
+		self.signing_failure.read().unwrap().clone()
+	}
+
+	/// Keep `peer`'s verdict on one of our advertisements; false if the ack is for someone else's
+	pub fn record_ad_ack(&self, peer: String, ack: &AdAck) -> bool {
+		if self.owner_public_key.as_deref() != Some(ack.owner_public_key.as_str()) {
+			return false;
+		}
+		let now = Utc::now();
+		let rejection = (!ack.accepted).then(|| (
+			ack.code.clone().unwrap_or_else(|| "rejected".into()).chars().take(MAX_AD_ACK_REASON_CHARS).collect(),
+			ack.reason.clone().unwrap_or_default().chars().take(MAX_AD_ACK_REASON_CHARS).collect(),
+		));
+		let mut acks = self.ad_acks.write().unwrap();
+		let peers = acks.entry(ack.station_id).or_default();
+		peers.retain(|_, a| now - a.at < Duration::seconds(AD_ACK_TTL_SECS));
+		if peers.len() >= MAX_AD_ACK_PEERS && !peers.contains_key(&peer) {
+			if let Some(oldest) = peers.iter().min_by_key(|(_, a)| a.at).map(|(p, _)| p.clone()) {
+				peers.remove(&oldest);
+			}
+		}
+		peers.insert(peer, PeerAck { message_id: ack.message_id, rejection, at: now });
+		true
+	}
+
+	/// Peers' recent verdicts on a station of ours; None if none has reported back
+	pub fn ad_ack_summary(&self, station_id: Uuid) -> Option<AdAckSummary> {
+		let now = Utc::now();
+		let acks = self.ad_acks.read().unwrap();
+		let live = acks.get(&station_id)?.iter().filter(|(_, a)| now - a.at < Duration::seconds(AD_ACK_TTL_SECS));
+		let mut summary = AdAckSummary { accepted: 0, rejected: 0, rejections: Vec::new() };
+		for (peer, ack) in live {
+			match &ack.rejection {
+				None => summary.accepted += 1,
+				Some((code, reason)) => {
+					summary.rejected += 1;
+					summary.rejections.push(AdRejection {
+						peer_id: peer.clone(),
+						message_id: ack.message_id,
+						code: code.clone(),
+						reason: reason.clone(),
+						at: ack.at,
+					});
+				}
+			}
+		}
+		if summary.accepted + summary.rejected == 0 {
+			return None;
+		}
+		summary.rejections.sort_by_key(|r| std::cmp::Reverse(r.at));
+		Some(summary)
+	}
+
+	/// False if the capture was already in that state
+	pub fn set_capture_paused(&self, paused: bool) -> bool {
+		self.capture_paused.swap(paused, Ordering::Relaxed) != paused
+	}
+
+	pub fn is_capture_paused(&self) -> bool {
+		self.capture_paused.load(Ordering::Relaxed)
+	}
+
+	pub fn set_blocklist_status(&self, status: BlocklistStatus) {
+		*self.blocklist_status.write().unwrap() = Some(status);
+	}
+
+	pub fn blocklist_status(&self) -> Option<BlocklistStatus> {
+		self.blocklist_status.read().unwrap().clone()
+	}
+
+	pub fn set_unreachable_streams(&self, unreachable: HashMap<Uuid, String>) {
+		*self.unreachable_streams.write().unwrap() = unreachable;
+	}
+
+	/// `assignment.status`, except that a station whose current stream URL failed the last
+	/// healthcheck is `Unreachable`. A new URL counts as reachable until it is checked.
+	pub fn station_status(&self, assignment: &StationAssignment, now: DateTime<Utc>) -> StationStatus {
+		let unreachable = self.unreachable_streams.read().unwrap();
+		if unreachable.get(&assignment.station_id).is_some_and(|url| *url == assignment.stream_url) {
+			return StationStatus::Unreachable;
+		}
+		assignment.status(now)
+	}
+
 	pub async fn is_ip_blocked(&self, ip: &IpAddr) -> bool {
-		self.blocklist.read().await.contains(ip)
+		// A dual-stack listener reports IPv4 clients as ::ffff:a.b.c.d
+		self.blocklist.read().await.contains(&ip.to_canonical())
+	}
+
+	pub async fn set_allowed_owners(&self, owners: HashSet<String>) {
+		let mut al = self.allowed_owners.write().await;
+		*al = Some(owners);
+	}
+
+	pub async fn set_owner_caps(&self, caps: HashMap<String, u32>) {
+		*self.owner_caps.write().await = caps;
+	}
+
+	/// Frequency cap for an owner: its override if configured, else the global default
+	pub async fn owner_cap(&self, owner_public_key: &str) -> u32 {
+		self.owner_caps.read().await.get(owner_public_key).copied().unwrap_or(self.max_frequencies_per_owner)
 	}
 
+	pub async fn is_owner_allowed(&self, owner_public_key: &str) -> bool {
+		match &*self.allowed_owners.read().await {
+			Some(al) => al.contains(owner_public_key),
+			None => true,
+		}
+	}
+
+	/// Verify and cache an owner profile. When the cache is full, profiles of owners that hold no
+	/// frequency are evicted first, so a flood of throwaway keys can't push out real stations' names.
+	pub async fn accept_owner_profile(&self, profile: &OwnerProfile) -> Result<(), ProfileError> {
+		if profile.name.trim().is_empty() || profile.name.chars().count() > MAX_PROFILE_NAME_CHARS {
+			return Err(ProfileError::Invalid("name must be 1-64 characters"));
+		}
+		for u in [&profile.url, &profile.avatar_url].into_iter().flatten() {
+			if u.len() > MAX_PROFILE_URL_BYTES || !(u.starts_with("https://") || u.starts_with("http://")) {
+				return Err(ProfileError::Invalid("urls must be http(s) and at most 512 bytes"));
+			}
+		}
+		if profile.updated_at > self.clock.now() + Duration::seconds(MAX_PROFILE_CLOCK_SKEW_SECS) {
+			return Err(ProfileError::Invalid("updated_at is in the future"));
+		}
+		if !self.is_owner_allowed(&profile.owner_public_key).await {
+			return Err(ProfileError::OwnerNotAllowed);
+		}
+		let vk = parse_public_key_b64(&profile.owner_public_key).map_err(|_| ProfileError::InvalidSignature)?;
+		let msg = canonicalize_profile_bytes(
+			&self.signing_domain,
+			&profile.owner_public_key,
+			&profile.name,
+			profile.url.as_deref(),
+			profile.avatar_url.as_deref(),
+			&profile.updated_at.to_rfc3339(),
+		);
+		let sig = parse_sig_b64(&profile.signature).map_err(|_| ProfileError::InvalidSignature)?;
+		verify_bytes(&vk, &msg, &sig).map_err(|_| ProfileError::InvalidSignature)?;
+
+		let mut profiles = self.owner_profiles.write().await;
+		match profiles.get(&profile.owner_public_key) {
+			Some(existing) if existing.updated_at >= profile.updated_at => return Err(ProfileError::NotNewer),
+			Some(_) => {}
+			None if profiles.len() >= MAX_OWNER_PROFILES => {
+				let holders: HashSet<String> = self.registry.read().await.values().map(|a| a.owner_public_key.clone()).collect();
+				let victim = profiles.values()
+					.filter(|p| !holders.contains(&p.owner_public_key))
+					.min_by_key(|p| p.updated_at)
+					.map(|p| p.owner_public_key.clone());
+				match victim {
+					Some(k) => { profiles.remove(&k); }
+					None => return Err(ProfileError::CacheFull),
+				}
+			}
+			None => {}
+		}
+		profiles.insert(profile.owner_public_key.clone(), profile.clone());
+		Ok(())
+	}
+
+	pub async fn get_owner_profile(&self, owner_public_key: &str) -> Option<OwnerProfile> {
+		self.owner_profiles.read().await.get(owner_public_key).cloned()
+	}
+
+	/// Fill in the unsigned display fields: `owner_name` from cached profiles and `local`
+	pub async fn annotate_assignments(&self, assignments: &mut [StationAssignment]) {
+		let profiles = self.owner_profiles.read().await;
+		for a in assignments.iter_mut() {
+			a.owner_name = profiles.get(&a.owner_public_key).map(|p| p.name.clone());
+			a.local = self.is_local_stream(&a.stream_url);
+		}
+	}
+
+	/// Whether `stream_url` points at this node: same scheme, host (case-insensitive) and effective
+	/// port as public_url. Paths, trailing slashes and queries don't matter; unparseable URLs never match.
+	pub fn is_local_stream(&self, stream_url: &str) -> bool {
+		match (reqwest::Url::parse(stream_url), reqwest::Url::parse(&self.public_url)) {
+			(Ok(stream), Ok(public)) => stream.origin().is_tuple() && stream.origin() == public.origin(),
+			_ => false,
+		}
+	}
+
+ 	/// The peer table, most recently seen first
  	pub async fn list_peers(&self) -> Vec<PeerInfo> {
- 		self.peers.read().await.values().cloned().collect()
+ 		let mut peers: Vec<PeerInfo> = self.peers.read().await.values().cloned().collect();
+ 		peers.sort_by_key(|p| std::cmp::Reverse(p.last_seen));
+ 		peers
  	}
 
  	pub async fn merge_peer_register_response(&self, peer_base: &str, resp: crate::types::RegisterPeerResponse) {
@@ -205,35 +964,111 @@ use std::net::IpAddr;
  			self.add_or_update_peer(p.api_base_url.clone(), p).await;
  		}
 		for a in resp.registry {
-			self.import_assignment(a).await;
+			let _ = self.import_signed_assignment(&a).await;
 		}
  	}
 
+	/// Import an assignment learned from a peer by re-verifying its owner signature
+	pub async fn import_signed_assignment(&self, assignment: &StationAssignment) -> Result<StationAssignment, RegistryError> {
+		self.accept_advertisement(&assignment.to_advertisement()).await
+	}
+
 	pub async fn import_assignment(&self, assignment: StationAssignment) {
 		let key = normalize_frequency_key(&assignment.frequency);
 		let mut reg = self.registry.write().await;
 		match reg.get(&key) {
 			Some(existing) => {
-				// If owner matches, update; if owner differs, adopt incoming to converge
-				if existing.owner_public_key == assignment.owner_public_key {
-					reg.insert(key, assignment.clone());
-				} else {
-					reg.insert(key, assignment.clone());
+				// If owner matches, update unless it's an older sequence; if owner differs, adopt incoming to converge
+				if existing.owner_public_key == assignment.owner_public_key && assignment.sequence < existing.sequence {
+					return;
 				}
+				reg.insert(key, assignment.clone());
 			}
 			None => {
 				reg.insert(key, assignment.clone());
 			}
 		}
-		let _ = self.events_tx.send(RegistryEvent { event: "upsert".into(), assignment });
+		self.emit_registry_event("upsert", assignment, None);
 	}
 
-    pub async fn set_now_playing(&self, np: NowPlaying) {
-        {
-            let mut guard = self.now_playing.write().await;
-            *guard = Some(np.clone());
+    /// A source connection opened; `content_type` is what it declared, if anything
+    pub fn begin_source(&self, source: &str, content_type: Option<&str>) {
+        tracing::debug!(source, content_type, "source connected");
+        // A standby source connecting must not clobber what the active one is broadcasting
+        let active = self.sources.active();
+        if !self.sources.is_prioritized() || active.is_none() || active.as_deref() == Some(source) {
+            if let Some(t) = self.ingest.begin(source, content_type) {
+                self.set_active_codec(&t);
+            }
+        }
+    }
+
+    /// Forward a chunk from an ingest source to listeners if that source is currently active
+    pub fn ingest_audio(&self, source: &str, chunk: bytes::Bytes) {
+        if self.sources.on_chunk(source, chunk.len()) {
+            if let Some(t) = self.ingest.record(source, &chunk) {
+                self.set_active_codec(&t);
+            }
+            self.burst.publish(chunk, &self.audio_tx);
+        }
+    }
+
+    /// Note the broadcast's content type. On a codec change the buffered audio (old codec) is
+    /// dropped, and listeners see the new codec before its first chunk is published.
+    fn set_active_codec(&self, content_type: &str) {
+        let Some(codec) = codec_for_content_type(content_type) else { return };
+        let mut previous = None;
+        let changed = self.active_codec.send_if_modified(|current| {
+            if current.as_deref() == Some(codec.as_str()) {
+                return false;
+            }
+            previous = current.replace(codec.clone());
+            true
+        });
+        if changed && previous.is_some() {
+            self.burst.clear();
+            tracing::info!(from = previous.as_deref(), to = %codec, "source codec changed; closing listeners tuned to the old codec");
+        }
+    }
+
+    /// Apply a now-playing JSON object from a source: a full replace, or a merge onto the current
+    /// track when it is a patch (`"patch": true`)
+    pub async fn apply_now_playing_json(&self, v: &serde_json::Value) -> Result<(), NowPlayingTooLarge> {
+        if NowPlaying::is_patch(v) {
+            self.update_now_playing(|current| NowPlaying::merge_json(current, v)).await
+        } else {
+            self.set_now_playing(NowPlaying::from_json(v)).await
+        }
+    }
+
+    /// Publish a now-playing update; updates whose JSON exceeds the configured cap are refused
+    /// so a single client can't flood the broadcast and every SSE subscriber with huge payloads
+    pub async fn set_now_playing(&self, np: NowPlaying) -> Result<(), NowPlayingTooLarge> {
+        self.update_now_playing(|_| np).await
+    }
+
+    /// Build the new now-playing from the current one under the write lock, so concurrent patches
+    /// from different sources can't lose each other's fields
+    async fn update_now_playing(&self, build: impl FnOnce(Option<&NowPlaying>) -> NowPlaying) -> Result<(), NowPlayingTooLarge> {
+        let mut guard = self.now_playing.write().await;
+        let np = build(guard.as_ref());
+        // The size check's serialization is kept as the SSE payload
+        let json = serde_json::value::to_raw_value(&np).ok();
+        let size = json.as_ref().map(|j| j.get().len()).unwrap_or(usize::MAX);
+        if size > self.max_now_playing_bytes {
+            return Err(NowPlayingTooLarge { size, max: self.max_now_playing_bytes });
         }
-        let _ = self.now_tx.send(np);
+        // A repeat of the current track only refreshes the timestamp; subscribers aren't notified
+        if let Some(current) = guard.as_mut().filter(|current| current.same_track(&np)) {
+            current.updated_at = np.updated_at;
+            return Ok(());
+        }
+        *guard = Some(np.clone());
+        drop(guard);
+        if let (Some(json), true) = (json, self.now_tx.receiver_count() > 0) {
+            let _ = self.now_tx.send(SharedEvent::with_json(np, json));
+        }
+        Ok(())
     }
 
     pub async fn get_now_playing(&self) -> Option<NowPlaying> {
@@ -241,4 +1076,20 @@ use std::net::IpAddr;
     }
  }
 
+/// Counts a `/stream` listener for as long as its response body is alive
+pub struct ListenerGuard(Arc<AppState>);
+
+impl ListenerGuard {
+    pub fn new(state: Arc<AppState>) -> Self {
+        if state.stream_listeners.fetch_add(1, Ordering::Relaxed) == 0 {
+            state.listeners_arrived.notify_one();
+        }
+        Self(state)
+    }
+}
 
+impl Drop for ListenerGuard {
+    fn drop(&mut self) {
+        self.0.stream_listeners.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::state::AppState;
+use crate::types::{normalize_frequency_key, StationAssignment};
+
+/// How long after the first unsaved mutation the registry is written; at most this much is lost on a crash
+const STORE_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// Durable home for the registry. The registry itself stays in memory in `AppState`; a store
+/// is loaded once at startup and handed the live snapshot after mutations. Methods are blocking
+/// and are called on the blocking pool.
+pub trait RegistryStore: Send + Sync {
+	/// Short backend name for logs
+	fn name(&self) -> &'static str;
+
+	/// Assignments saved by a previous run; a store that was never written is empty, not an error
+	fn load(&self) -> anyhow::Result<Vec<StationAssignment>>;
+
+	/// Replace the stored registry with `assignments`
+	fn save(&self, assignments: &[StationAssignment]) -> anyhow::Result<()>;
+
+	/// Assignments written by others, for backends shared between nodes (e.g. Redis);
+	/// node-local stores have nothing to report
+	fn subscribe(&self) -> Option<mpsc::Receiver<StationAssignment>> {
+		None
+	}
+}
+
+/// Keeps the last snapshot in process memory only; the registry is rebuilt from gossip after a restart
+#[derive(Default)]
+pub struct MemoryStore {
+	saved: Mutex<Vec<StationAssignment>>,
+}
+
+impl RegistryStore for MemoryStore {
+	fn name(&self) -> &'static str {
+		"memory"
+	}
+
+	fn load(&self) -> anyhow::Result<Vec<StationAssignment>> {
+		Ok(self.saved.lock().unwrap().clone())
+	}
+
+	fn save(&self, assignments: &[StationAssignment]) -> anyhow::Result<()> {
+		*self.saved.lock().unwrap() = assignments.to_vec();
+		Ok(())
+	}
+}
+
+/// A JSON object mapping normalized frequency keys to assignments, rewritten atomically
+pub struct JsonFileStore {
+	path: PathBuf,
+}
+
+impl JsonFileStore {
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self { path: path.into() }
+	}
+}
+
+impl RegistryStore for JsonFileStore {
+	fn name(&self) -> &'static str {
+		"json"
+	}
+
+	fn load(&self) -> anyhow::Result<Vec<StationAssignment>> {
+		let data = match std::fs::read(&self.path) {
+			Ok(d) => d,
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(err) => return Err(err.into()),
+		};
+		let map: HashMap<String, StationAssignment> = serde_json::from_slice(&data)?;
+		Ok(map.into_values().collect())
+	}
+
+	/// Temp file + rename so a crash mid-write leaves the previous registry intact
+	fn save(&self, assignments: &[StationAssignment]) -> anyhow::Result<()> {
+		let map: HashMap<String, &StationAssignment> = assignments.iter()
+			.map(|a| (normalize_frequency_key(&a.frequency), a))
+			.collect();
+		let mut tmp = self.path.clone().into_os_string();
+		tmp.push(".tmp");
+		std::fs::write(&tmp, serde_json::to_vec_pretty(&map)?)?;
+		std::fs::rename(&tmp, &self.path)?;
+		Ok(())
+	}
+}
+
+/// Placeholder for a SQLite database at `path`. The backend is selectable so the option and its
+/// wiring are in place, but no driver is linked yet: every call fails, which stops startup in
+/// `load_registry` rather than silently running without persistence.
+pub struct SqliteStore {
+	path: PathBuf,
+}
+
+impl SqliteStore {
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self { path: path.into() }
+	}
+
+	fn unavailable(&self) -> anyhow::Error {
+		anyhow::anyhow!("the sqlite registry store ({}) is not implemented yet; use json:<path>", self.path.display())
+	}
+}
+
+impl RegistryStore for SqliteStore {
+	fn name(&self) -> &'static str {
+		"sqlite"
+	}
+
+	fn load(&self) -> anyhow::Result<Vec<StationAssignment>> {
+		Err(self.unavailable())
+	}
+
+	fn save(&self, _assignments: &[StationAssignment]) -> anyhow::Result<()> {
+		Err(self.unavailable())
+	}
+}
+
+/// `--registry-store` value: `memory`, `json:<path>` or `sqlite:<path>`. Other backends plug in
+/// here and in `open`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryStoreSpec {
+	Memory,
+	Json(String),
+	Sqlite(String),
+}
+
+impl FromStr for RegistryStoreSpec {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (kind, path) = s.split_once(':').unwrap_or((s, ""));
+		match (kind, path) {
+			("memory", "") => Ok(RegistryStoreSpec::Memory),
+			("json", path) if !path.is_empty() => Ok(RegistryStoreSpec::Json(path.to_string())),
+			("sqlite", path) if !path.is_empty() => Ok(RegistryStoreSpec::Sqlite(path.to_string())),
+			_ => anyhow::bail!("invalid registry store '{}': expected memory, json:<path> or sqlite:<path>", s),
+		}
+	}
+}
+
+pub fn open(spec: &RegistryStoreSpec) -> Arc<dyn RegistryStore> {
+	match spec {
+		RegistryStoreSpec::Memory => Arc::new(MemoryStore::default()),
+		RegistryStoreSpec::Json(path) => Arc::new(JsonFileStore::new(path)),
+		RegistryStoreSpec::Sqlite(path) => Arc::new(SqliteStore::new(path)),
+	}
+}
+
+/// Restore the registry from the store. Entries go through the same signature and conflict checks
+/// as gossip, so a tampered or stale file can't inject anything; expired ones are dropped.
+pub async fn load_registry(state: &AppState) -> anyhow::Result<()> {
+	let store = state.store.clone();
+	let saved = tokio::task::spawn_blocking(move || store.load()).await??;
+	let now = Utc::now();
+	let (mut restored, mut dropped) = (0usize, 0usize);
+	for assignment in saved {
+		if assignment.expires_at <= now {
+			dropped += 1;
+			continue;
+		}
+		match state.import_signed_assignment(&assignment).await {
+			Ok(_) => restored += 1,
+			Err(err) => {
+				warn!(code = err.code(), error=%err, frequency=%assignment.frequency, "dropping stored assignment");
+				dropped += 1;
+			}
+		}
+	}
+	info!(store = state.store.name(), restored, dropped, "registry loaded");
+	Ok(())
+}
+
+/// Keep the store in step with the registry: every mutation marks it dirty and this task saves the
+/// live snapshot STORE_DEBOUNCE later, so a burst of mutations is one write (mutations arriving
+/// during a save coalesce into the next one). Changes pushed by a shared backend are imported like gossip.
+pub fn spawn_store_sync(state: Arc<AppState>) {
+	if let Some(mut rx) = state.store.subscribe() {
+		let st = state.clone();
+		tokio::spawn(async move {
+			while let Some(assignment) = rx.recv().await {
+				if let Err(err) = st.import_signed_assignment(&assignment).await {
+					tracing::debug!(code = err.code(), error=%err, "ignoring assignment from registry store");
+				}
+			}
+		});
+	}
+	tokio::spawn(async move {
+		loop {
+			state.store_dirty.notified().await;
+			tokio::time::sleep(STORE_DEBOUNCE).await;
+			let snapshot = state.snapshot_registry().await;
+			let store = state.store.clone();
+			match tokio::task::spawn_blocking(move || store.save(&snapshot)).await {
+				Ok(Ok(())) => {}
+				Ok(Err(err)) => warn!(store = state.store.name(), error=%err, "failed to save registry"),
+				Err(err) => warn!(error=%err, "registry save task failed"),
+			}
+		}
+	});
+}
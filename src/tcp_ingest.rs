@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+use crate::source::AUDIO_TCP_SOURCE;
+use crate::state::AppState;
+
+// Longest accepted token line, and how long a client has to send it
+const MAX_TOKEN_LINE_BYTES: usize = 256;
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(5);
+// Connections still sending their token; more are closed at once, so idle sockets can't pile up
+const MAX_PENDING_HANDSHAKES: usize = 8;
+// A silent encoder connection is dropped after this, so a half-open socket can't block reconnects
+const IDLE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Raw TCP audio ingest for encoders that can't speak HTTP. One source at a time: further
+/// connections are refused while one is streaming. With a token, the first line must be that token,
+/// and the slot is only claimed once it has been checked, so an unauthenticated client can neither
+/// hold it nor learn whether a source is connected.
+pub async fn run_audio_tcp_listener(state: Arc<AppState>, addr: String, token: Option<String>) -> anyhow::Result<()> {
+	let listener = TcpListener::bind(&addr).await?;
+	serve_audio_tcp(state, listener, token).await
+}
+
+/// `run_audio_tcp_listener` on an already-bound listener
+pub async fn serve_audio_tcp(state: Arc<AppState>, listener: TcpListener, token: Option<String>) -> anyhow::Result<()> {
+	info!(addr = %listener.local_addr()?, "Audio TCP ingest listening");
+	let busy = Arc::new(AtomicBool::new(false));
+	let handshakes = Arc::new(Semaphore::new(MAX_PENDING_HANDSHAKES));
+	loop {
+		let (mut stream, peer) = match listener.accept().await {
+			Ok(c) => c,
+			Err(err) => {
+				warn!(error=%err, "audio TCP accept error");
+				continue;
+			}
+		};
+		let Ok(handshake) = handshakes.clone().try_acquire_owned() else {
+			warn!(%peer, "too many audio TCP connections awaiting a token; closing");
+			continue;
+		};
+		let st = state.clone();
+		let (busy, token) = (busy.clone(), token.clone());
+		tokio::spawn(async move {
+			let authenticated = authenticate(&mut stream, token.as_deref()).await;
+			drop(handshake);
+			let leftover = match authenticated {
+				Ok(leftover) => leftover,
+				Err(msg) => {
+					warn!(%peer, reason = msg, "audio TCP source rejected");
+					let _ = stream.write_all(b"UNAUTHORIZED\n").await;
+					return;
+				}
+			};
+			if busy.swap(true, Ordering::AcqRel) {
+				warn!(%peer, "audio TCP source already connected; refusing");
+				let _ = stream.write_all(b"BUSY\n").await;
+				return;
+			}
+			info!(%peer, "audio TCP source connected");
+			st.begin_source(AUDIO_TCP_SOURCE, None);
+			if !leftover.is_empty() {
+				st.ingest_audio(AUDIO_TCP_SOURCE, bytes::Bytes::from(leftover));
+			}
+			pump(&st, &mut stream).await;
+			info!(%peer, "audio TCP source disconnected");
+			busy.store(false, Ordering::Release);
+		});
+	}
+}
+
+/// Check the token line; returns any audio bytes that arrived in the same reads after it
+async fn authenticate(stream: &mut TcpStream, token: Option<&str>) -> Result<Vec<u8>, &'static str> {
+	let Some(token) = token else { return Ok(Vec::new()) };
+	let mut buf = Vec::with_capacity(MAX_TOKEN_LINE_BYTES);
+	let read_line = async {
+		let mut chunk = [0u8; MAX_TOKEN_LINE_BYTES];
+		loop {
+			if let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+				return Ok(pos);
+			}
+			if buf.len() > MAX_TOKEN_LINE_BYTES {
+				return Err("token line too long");
+			}
+			match stream.read(&mut chunk).await {
+				Ok(0) | Err(_) => return Err("connection closed before token"),
+				Ok(n) => buf.extend_from_slice(&chunk[..n]),
+			}
+		}
+	};
+	let pos = tokio::time::timeout(TOKEN_TIMEOUT, read_line).await.map_err(|_| "timed out waiting for token")??;
+	let line = std::str::from_utf8(&buf[..pos]).map_err(|_| "token is not UTF-8")?;
+	if !crate::crypto::tokens_match(line.trim_end_matches('\r'), token) {
+		return Err("invalid token");
+	}
+	Ok(buf.split_off(pos + 1))
+}
+
+async fn pump(state: &AppState, stream: &mut TcpStream) {
+	let mut buf = vec![0u8; 16 * 1024];
+	loop {
+		match tokio::time::timeout(IDLE_TIMEOUT, stream.read(&mut buf)).await {
+			Ok(Ok(0)) => break,
+			Ok(Ok(n)) => state.ingest_audio(AUDIO_TCP_SOURCE, bytes::Bytes::copy_from_slice(&buf[..n])),
+			Ok(Err(err)) => {
+				warn!(error=%err, "audio TCP read error");
+				break;
+			}
+			Err(_) => {
+				warn!("audio TCP source idle; dropping connection");
+				break;
+			}
+		}
+	}
+}
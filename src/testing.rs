@@ -0,0 +1,184 @@
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::config::{Cli, P2PTransport};
+use crate::crypto::{canonicalize_release_bytes, encode_public_key_b64, encode_signature_b64, sign_bytes, signing_domain};
+use crate::http;
+use crate::p2p::{self, P2PHandle};
+use crate::state::AppState;
+use crate::types::{normalize_frequency_key, ReleaseRequest, StationAdvertisement, AD_CANONICAL_VERSION};
+
+/// Registry state for a node started with extra command-line `args`, without gossip or HTTP
+pub fn app_state(args: &[&str]) -> Arc<AppState> {
+	let cli = Cli::try_parse_from(
+		["shortwave", "--public-url", "http://127.0.0.1:8080"].into_iter().chain(args.iter().copied()),
+	).expect("test config");
+	let config = cli.into_config().expect("test config");
+	config.validate().expect("test config");
+	Arc::new(AppState::new(&config, None))
+}
+
+pub fn owner_key() -> SigningKey {
+	let mut seed = [0u8; 32];
+	OsRng.fill_bytes(&mut seed);
+	SigningKey::from_bytes(&seed)
+}
+
+/// A current advertisement of `station_id` on `frequency` (e.g. "101.1"), signed by `sk`
+pub fn signed_ad(sk: &SigningKey, frequency: &str, station_id: Uuid, sequence: u64) -> StationAdvertisement {
+	let mut ad = StationAdvertisement {
+		message_id: Uuid::new_v4(),
+		station_id,
+		frequency: BigDecimal::from_str(frequency).expect("test frequency"),
+		name: format!("Test {frequency}"),
+		stream_url: format!("http://127.0.0.1:8080/stream/{frequency}"),
+		advertised_at: Utc::now(),
+		ttl_seconds: 60,
+		sequence,
+		owner_public_key: encode_public_key_b64(&sk.verifying_key()),
+		signature: String::new(),
+		program: None,
+		bitrate_kbps: None,
+		codec: None,
+		stream_urls: Vec::new(),
+		canonical_version: AD_CANONICAL_VERSION,
+		logo_url: None,
+		location: None,
+	};
+	resign(sk, &mut ad);
+	ad
+}
+
+/// A release of `station_id` from `frequency`, signed by `sk`
+pub fn signed_release(sk: &SigningKey, frequency: &str, station_id: Uuid, sequence: u64, released_at: DateTime<Utc>) -> ReleaseRequest {
+	let frequency = BigDecimal::from_str(frequency).expect("test frequency");
+	let msg = canonicalize_release_bytes(
+		&signing_domain(None),
+		"release.v2",
+		&normalize_frequency_key(&frequency),
+		&station_id.to_string(),
+		sequence,
+		&released_at.to_rfc3339(),
+	);
+	ReleaseRequest {
+		station_id,
+		frequency,
+		reason: None,
+		sequence,
+		released_at,
+		signature: encode_signature_b64(&sign_bytes(sk, &msg)),
+	}
+}
+
+/// Re-sign `ad` for the default network after a test has changed its fields
+pub fn resign(sk: &SigningKey, ad: &mut StationAdvertisement) {
+	resign_for(sk, ad, None);
+}
+
+/// Re-sign `ad` for the network named `network_id` (`--network-id`)
+pub fn resign_for(sk: &SigningKey, ad: &mut StationAdvertisement, network_id: Option<&str>) {
+	let msg = ad.signed_bytes(&signing_domain(network_id)).expect("known canonical version");
+	ad.signature = encode_signature_b64(&sign_bytes(sk, &msg));
+}
+
+/// One in-process relay node: the real router on an ephemeral loopback port and the real gossip
+/// loop on libp2p's in-memory transport. Its HTTP server stops when it's dropped.
+pub struct TestNode {
+	pub state: Arc<AppState>,
+	pub p2p: P2PHandle,
+	/// `http://127.0.0.1:<port>`, also its --public-url
+	pub base_url: String,
+	server: JoinHandle<()>,
+}
+
+impl Drop for TestNode {
+	fn drop(&mut self) {
+		self.server.abort();
+	}
+}
+
+/// Start a node with extra command-line `args`, dialing `bootstrap` (`/memory/<port>/p2p/<id>`) once up.
+/// No local station: the heartbeat belongs to the binary, so stations reach these nodes through
+/// `POST /api/v1/advertise` or gossip.
+pub async fn spawn_node(args: &[&str], bootstrap: Vec<String>) -> anyhow::Result<TestNode> {
+	let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+	let addr = listener.local_addr()?;
+	let base_url = format!("http://{}", addr);
+	let bind = addr.to_string();
+	let cli = Cli::try_parse_from(
+		["shortwave", "--bind", bind.as_str(), "--public-url", base_url.as_str(), "--p2p-transport", "memory"]
+			.into_iter()
+			.chain(args.iter().copied()),
+	)?;
+	let config = cli.into_config()?;
+	config.validate()?;
+
+	let state = Arc::new(AppState::new(&config, None));
+	let handle = p2p::run_libp2p(
+		state.clone(),
+		config.p2p_listen.clone(),
+		bootstrap,
+		false,
+		P2PTransport::Memory,
+		None,
+		None,
+	).await?;
+	let _ = state.gossip.set(handle.clone());
+	handle.wait_listening().await;
+
+	let app = http::router(state.clone(), &config);
+	let server = tokio::spawn(async move {
+		let _ = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).into_future().await;
+	});
+	Ok(TestNode { state, p2p: handle, base_url, server })
+}
+
+/// Start `n` interconnected nodes: every node after the first bootstraps from the first, and
+/// gossipsub carries messages on from there
+pub async fn spawn_mesh(n: usize, args: &[&str]) -> anyhow::Result<Vec<TestNode>> {
+	let mut nodes: Vec<TestNode> = Vec::with_capacity(n);
+	for _ in 0..n {
+		let bootstrap = match nodes.first() {
+			Some(first) => first.p2p.listen_addrs().iter()
+				.map(|a| format!("{}/p2p/{}", a, first.p2p.peer_id()))
+				.collect(),
+			None => Vec::new(),
+		};
+		nodes.push(spawn_node(args, bootstrap).await?);
+	}
+	Ok(nodes)
+}
+
+/// Wait until every node's registry holds `station_id` on `frequency`; false if `timeout` runs out first
+pub async fn wait_for_station(nodes: &[TestNode], frequency: &BigDecimal, station_id: Uuid, timeout: Duration) -> bool {
+	let key = normalize_frequency_key(frequency);
+	let deadline = tokio::time::Instant::now() + timeout;
+	loop {
+		let mut converged = true;
+		for node in nodes {
+			if node.state.get_assignment_by_key(&key).await.map(|a| a.station_id) != Some(station_id) {
+				converged = false;
+				break;
+			}
+		}
+		if converged {
+			return true;
+		}
+		if tokio::time::Instant::now() >= deadline {
+			return false;
+		}
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+}
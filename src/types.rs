@@ -3,6 +3,10 @@ use chrono::{DateTime, Utc};
  use uuid::Uuid;
 use bigdecimal::BigDecimal;
 use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use serde_json::value::RawValue;
+use crate::source::{IngestStatus, SourceStatus};
+use crate::state::RegistryError;
 
 // Serde helpers to accept numbers or strings for BigDecimal and serialize as string to preserve precision
 mod serde_decimal {
@@ -20,45 +24,120 @@ mod serde_decimal {
     where
         D: Deserializer<'de>,
     {
-        struct V;
-        impl<'de> de::Visitor<'de> for V {
-            type Value = BigDecimal;
-            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-                write!(f, "a decimal number or string")
-            }
-            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                BigDecimal::from_str(v).map_err(|e| E::custom(format!("invalid decimal: {}", e)))
-            }
-            fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                BigDecimal::from_str(v).map_err(|e| E::custom(format!("invalid decimal: {}", e)))
-            }
-            fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(BigDecimal::from_str(&format!("{v}"))
-                    .map_err(|e| E::custom(format!("invalid decimal: {}", e)))?)
-            }
-            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(BigDecimal::from(v))
+        d.deserialize_any(V { units: false })
+    }
+
+    /// Like `deserialize`, but strings may carry a unit (see `parse_frequency`)
+    pub fn deserialize_frequency<'de, D>(d: D) -> Result<BigDecimal, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_any(V { units: true })
+    }
+
+    struct V {
+        units: bool,
+    }
+
+    impl<'de> de::Visitor<'de> for V {
+        type Value = BigDecimal;
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a decimal number or string")
+        }
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            if self.units {
+                return parse_frequency(v).map_err(E::custom);
             }
-            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(BigDecimal::from(v))
+            if v.len() > MAX_FREQUENCY_CHARS {
+                return Err(E::custom("decimal is too long"));
             }
+            let d = BigDecimal::from_str(v).map_err(|e| E::custom(format!("invalid decimal: {}", e)))?;
+            check_frequency_bounds(d).map_err(E::custom)
         }
-        d.deserialize_any(V)
+        fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            let d = BigDecimal::from_str(&format!("{v}")).map_err(|e| E::custom(format!("invalid decimal: {}", e)))?;
+            check_frequency_bounds(d).map_err(E::custom)
+        }
+        fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            check_frequency_bounds(BigDecimal::from(v)).map_err(E::custom)
+        }
+        fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            check_frequency_bounds(BigDecimal::from(v)).map_err(E::custom)
+        }
+    }
+}
+
+/// Parse a frequency with an optional unit: "101.1", "88.5 MHz", "6175 kHz", "1.2GHz". Registry
+/// frequencies are in MHz, so bare numbers are taken as MHz and units are converted to it exactly.
+/// Units are Hz, kHz, MHz and GHz (any case, except "mHz", which is millihertz and surely a typo);
+/// anything else, including a lone "k" or "M", is refused.
+pub fn parse_frequency(s: &str) -> Result<BigDecimal, String> {
+    let s = s.trim();
+    if s.len() > MAX_FREQUENCY_CHARS {
+        return Err(format!("frequency is longer than {} characters", MAX_FREQUENCY_CHARS));
+    }
+    let number = s.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = &s[number.len()..];
+    if unit == "mHz" {
+        return Err("ambiguous frequency unit 'mHz' (millihertz); use MHz".to_string());
+    }
+    // Powers of ten from the unit to MHz
+    let shift: i64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "mhz" => 0,
+        "hz" => -6,
+        "khz" => -3,
+        "ghz" => 3,
+        _ => return Err(format!("unknown frequency unit '{}' (expected Hz, kHz, MHz or GHz)", unit)),
+    };
+    let value = BigDecimal::from_str(number.trim()).map_err(|_| format!("invalid frequency '{}'", s))?;
+    let (digits, scale) = value.into_bigint_and_exponent();
+    check_frequency_bounds(BigDecimal::new(digits, scale - shift))
+}
+
+const MAX_FREQUENCY_CHARS: usize = 64;
+/// Finest frequency step kept, in decimal places of MHz (a micro-hertz)
+const MAX_FREQUENCY_SCALE: i64 = 12;
+/// Digits before the decimal point, in MHz
+const MAX_FREQUENCY_INT_DIGITS: i64 = 12;
+
+/// Refuse frequencies with absurd exponents. Checked on the digit count and exponent alone:
+/// `1e5000000` is short to write, but comparing or subtracting it would expand millions of digits.
+fn check_frequency_bounds(value: BigDecimal) -> Result<BigDecimal, String> {
+    let normalized = value.normalized();
+    let (_, scale) = normalized.as_bigint_and_exponent();
+    if scale > MAX_FREQUENCY_SCALE || normalized.digits() as i64 - scale > MAX_FREQUENCY_INT_DIGITS {
+        return Err("frequency is out of range".to_string());
+    }
+    Ok(value)
+}
+
+/// A frequency in config files or flags, where a unit is allowed (see `parse_frequency`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frequency(pub BigDecimal);
+
+impl FromStr for Frequency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_frequency(s).map(Frequency)
+    }
+}
+
+impl<'de> Deserialize<'de> for Frequency {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        serde_decimal::deserialize_frequency(d).map(Frequency)
     }
 }
 
@@ -78,6 +157,9 @@ pub fn normalize_frequency_key(f: &BigDecimal) -> String {
  	pub node_id: Uuid,
  	pub api_base_url: String,
  	pub version: String,
+	/// Sorted feature flags enabled on this node, for client feature detection
+	#[serde(default)]
+	pub capabilities: Vec<String>,
  }
 
  #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,12 +179,327 @@ pub fn normalize_frequency_key(f: &BigDecimal) -> String {
  	pub stream_url: String,
  	pub advertised_at: DateTime<Utc>,
  	pub ttl_seconds: u32,
+    /// Monotonic per-station counter, signed (see `next_sequence`); 0 for legacy advertisements without one
+    #[serde(default)]
+    pub sequence: u64,
     /// Base64 Ed25519 public key of owner (the broadcaster)
     pub owner_public_key: String,
     /// Signature over canonical advertisement bytes
     pub signature: String,
+    /// Current and next show; signed when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program: Option<ProgramInfo>,
+    /// Measured stream bitrate; signed with `codec` when either is present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitrate_kbps: Option<u32>,
+    /// Short codec name such as "mp3", "aac", "ogg" or "flac"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+    /// Weighted edges serving the same stream, for clients that pick the nearest; `stream_url`
+    /// stays the primary for clients that don't. Signed when non-empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stream_urls: Vec<StreamEndpoint>,
+    /// Layout of the signed bytes (see `crypto::ad_namespace`); 0 for legacy ads that don't sign `message_id`,
+    /// 3 for canonical JSON
+    #[serde(default)]
+    pub canonical_version: u32,
+    /// Station artwork (its identity, unlike the per-track now-playing `cover_url`); signed when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo_url: Option<String>,
+    /// Where the station is, for map-based directories; signed when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<StationLocation>,
  }
 
+/// Canonical version new advertisements are signed with
+pub const AD_CANONICAL_VERSION: u32 = 3;
+
+/// Sequence for a station's next advertisement: the signing time in milliseconds, or one past
+/// `previous` if the clock hasn't moved on from it. Being clock-derived, it keeps increasing
+/// across restarts even without a hold file to carry the last value over.
+pub fn next_sequence(previous: u64, now: DateTime<Utc>) -> u64 {
+    (previous + 1).max(now.timestamp_millis().max(0) as u64)
+}
+
+impl StationAdvertisement {
+    /// The bytes `signature` covers on the network with signing `domain` (`crypto::signing_domain`);
+    /// None when `canonical_version` is unknown to this build
+    pub fn signed_bytes(&self, domain: &str) -> Option<Vec<u8>> {
+        let namespace = crate::crypto::ad_namespace(self.canonical_version)?;
+        if self.canonical_version >= 3 {
+            return Some(crate::crypto::canonicalize_ad_json(domain, namespace, &self.signed_fields()));
+        }
+        let mut msg = crate::crypto::canonicalize_ad_bytes(
+            domain,
+            namespace,
+            &normalize_frequency_key(&self.frequency),
+            &self.station_id.to_string(),
+            &self.stream_url,
+            &self.advertised_at.to_rfc3339(),
+            self.ttl_seconds,
+            self.sequence,
+        );
+        if self.canonical_version >= 2 {
+            crate::crypto::append_ad_message_id(&mut msg, &self.message_id.to_string());
+        }
+        crate::crypto::append_ad_extensions(
+            &mut msg,
+            self.program.as_ref().map(ProgramInfo::canonical_json).as_deref(),
+            stream_format_json(self.bitrate_kbps, self.codec.as_deref()).as_deref(),
+            stream_endpoints_json(&self.stream_urls).as_deref(),
+            self.logo_url.as_deref(),
+            self.location.as_ref().map(StationLocation::canonical_json).as_deref(),
+        );
+        Some(msg)
+    }
+
+    /// Canonical version 3 content: what v2 signs plus the display name, absent values as `null`.
+    /// `location` came later and is left out when absent, so older v3 signatures still verify.
+    fn signed_fields(&self) -> serde_json::Value {
+        let format = (self.bitrate_kbps.is_some() || self.codec.is_some())
+            .then(|| serde_json::json!({ "bitrate_kbps": self.bitrate_kbps, "codec": self.codec }));
+        let endpoints = (!self.stream_urls.is_empty()).then_some(&self.stream_urls);
+        let mut fields = serde_json::json!({
+            "freq": normalize_frequency_key(&self.frequency),
+            "station": self.station_id,
+            "name": self.name,
+            "url": self.stream_url,
+            "at": self.advertised_at.to_rfc3339(),
+            "ttl": self.ttl_seconds,
+            "seq": self.sequence,
+            "mid": self.message_id,
+            "program": self.program,
+            "format": format,
+            "endpoints": endpoints,
+            "logo": self.logo_url,
+        });
+        if let Some(location) = &self.location {
+            fields["location"] = serde_json::to_value(location).unwrap_or_default();
+        }
+        fields
+    }
+}
+
+/// One place a station's stream can be fetched from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamEndpoint {
+    pub url: String,
+    /// Free-form region hint such as "eu-west" or "us"
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Relative share of listeners; absent means 1
+    #[serde(default)]
+    pub weight: Option<u32>,
+}
+
+impl FromStr for StreamEndpoint {
+    type Err = anyhow::Error;
+
+    /// Parses `url[ region=<region>][ weight=<n>]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let url = parts.next().ok_or_else(|| anyhow::anyhow!("invalid stream endpoint '{}': missing url", s))?;
+        let mut endpoint = StreamEndpoint { url: url.to_string(), region: None, weight: None };
+        for part in parts {
+            match part.split_once('=') {
+                Some(("region", region)) => endpoint.region = Some(region.to_string()),
+                Some(("weight", weight)) => endpoint.weight = Some(weight.parse()
+                    .map_err(|e| anyhow::anyhow!("invalid stream endpoint '{}': bad weight: {}", s, e))?),
+                _ => anyhow::bail!("invalid stream endpoint '{}': expected url[ region=<region>][ weight=<n>]", s),
+            }
+        }
+        validate_stream_endpoints(std::slice::from_ref(&endpoint))
+            .map_err(|e| anyhow::anyhow!("invalid stream endpoint '{}': {}", s, e))?;
+        Ok(endpoint)
+    }
+}
+
+/// Longest accepted show name, in characters
+pub const MAX_SHOW_NAME_CHARS: usize = 128;
+/// Longest accepted codec name, in characters
+pub const MAX_CODEC_CHARS: usize = 32;
+/// Highest plausible advertised bitrate; anything above is treated as garbage
+pub const MAX_BITRATE_KBPS: u32 = 10_000;
+/// Most alternative stream endpoints one advertisement may carry
+pub const MAX_STREAM_ENDPOINTS: usize = 8;
+/// Longest accepted endpoint URL, in bytes
+pub const MAX_ENDPOINT_URL_BYTES: usize = 512;
+/// Longest accepted endpoint region, in characters
+pub const MAX_REGION_CHARS: usize = 32;
+/// Highest accepted endpoint weight
+pub const MAX_ENDPOINT_WEIGHT: u32 = 1000;
+/// Longest accepted logo URL, in bytes
+pub const MAX_LOGO_URL_BYTES: usize = 512;
+
+/// Signed form of an advertisement's stream format: JSON with absent values as `null`, or None
+/// when neither is reported so ads without a format keep their original bytes
+pub fn stream_format_json(bitrate_kbps: Option<u32>, codec: Option<&str>) -> Option<String> {
+    if bitrate_kbps.is_none() && codec.is_none() {
+        return None;
+    }
+    Some(serde_json::json!({ "bitrate_kbps": bitrate_kbps, "codec": codec }).to_string())
+}
+
+pub fn validate_stream_format(bitrate_kbps: Option<u32>, codec: Option<&str>) -> Result<(), &'static str> {
+    if bitrate_kbps.is_some_and(|b| b == 0 || b > MAX_BITRATE_KBPS) {
+        return Err("bitrate out of range");
+    }
+    if let Some(codec) = codec {
+        if codec.is_empty() || codec.chars().count() > MAX_CODEC_CHARS || !codec.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '+')) {
+            return Err("codec must be 1-32 characters of [A-Za-z0-9.+-]");
+        }
+    }
+    Ok(())
+}
+
+/// Signed form of an advertisement's stream endpoints, or None when there are none so single-URL
+/// ads keep their original bytes. A change to its layout needs a new `canonical_version`.
+pub fn stream_endpoints_json(endpoints: &[StreamEndpoint]) -> Option<String> {
+    if endpoints.is_empty() {
+        return None;
+    }
+    serde_json::to_string(endpoints).ok()
+}
+
+pub fn validate_stream_endpoints(endpoints: &[StreamEndpoint]) -> Result<(), &'static str> {
+    if endpoints.len() > MAX_STREAM_ENDPOINTS {
+        return Err("at most 8 stream endpoints");
+    }
+    for endpoint in endpoints {
+        if endpoint.url.len() > MAX_ENDPOINT_URL_BYTES || !(endpoint.url.starts_with("https://") || endpoint.url.starts_with("http://")) {
+            return Err("endpoint urls must be http(s) and at most 512 bytes");
+        }
+        if endpoint.region.as_ref().is_some_and(|r| r.is_empty() || r.chars().count() > MAX_REGION_CHARS) {
+            return Err("endpoint region must be 1-32 characters");
+        }
+        if endpoint.weight.is_some_and(|w| w == 0 || w > MAX_ENDPOINT_WEIGHT) {
+            return Err("endpoint weight must be 1-1000");
+        }
+    }
+    Ok(())
+}
+
+/// Longest accepted station genre, in characters
+pub const MAX_GENRE_CHARS: usize = 64;
+
+/// The genre goes out verbatim as a response header, so it must be short and free of control characters
+pub fn validate_genre(genre: &str) -> Result<(), &'static str> {
+    if genre.trim().is_empty() || genre.chars().count() > MAX_GENRE_CHARS {
+        return Err("genre must be 1-64 characters");
+    }
+    if genre.chars().any(char::is_control) {
+        return Err("genre must not contain control characters");
+    }
+    Ok(())
+}
+
+/// Logos are fetched by directory UIs, not by nodes, so the guard is about clients: an advertised
+/// logo must not point browsers at their own machine or LAN (localhost, private or link-local literals)
+pub fn validate_logo_url(url: &str) -> Result<(), &'static str> {
+    if url.len() > MAX_LOGO_URL_BYTES {
+        return Err("logo url must be at most 512 bytes");
+    }
+    let parsed = reqwest::Url::parse(url).map_err(|_| "logo url is not a valid URL")?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("logo url must be http(s)");
+    }
+    let host = parsed.host_str().unwrap_or("").trim_start_matches('[').trim_end_matches(']');
+    let internal_v4 = |ip: std::net::Ipv4Addr| ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast();
+    let internal = match host.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => internal_v4(ip),
+        Ok(std::net::IpAddr::V6(ip)) => {
+            let first = ip.segments()[0];
+            ip.is_loopback() || ip.is_unspecified() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+                || ip.to_ipv4_mapped().is_some_and(internal_v4)
+        }
+        Err(_) => {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            host.is_empty() || host == "localhost" || host.ends_with(".localhost")
+        }
+    };
+    if internal {
+        return Err("logo url must not point at a local or private address");
+    }
+    Ok(())
+}
+
+/// Where a station is. Coordinates come as a pair; either they or the country (or both) must be set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StationLocation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// ISO 3166-1 alpha-2 code, uppercase (e.g. "US")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+}
+
+impl StationLocation {
+    /// JSON in field order with absent fields left out; this exact text is what gets signed
+    pub fn canonical_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        match (self.latitude, self.longitude) {
+            (Some(lat), Some(lon)) => {
+                if !(-90.0..=90.0).contains(&lat) {
+                    return Err("latitude must be within -90..90");
+                }
+                if !(-180.0..=180.0).contains(&lon) {
+                    return Err("longitude must be within -180..180");
+                }
+            }
+            (None, None) if self.country.is_none() => return Err("location needs coordinates or a country"),
+            (None, None) => {}
+            _ => return Err("latitude and longitude must be given together"),
+        }
+        if self.country.as_ref().is_some_and(|c| c.len() != 2 || !c.bytes().all(|b| b.is_ascii_uppercase())) {
+            return Err("country must be an uppercase ISO 3166-1 alpha-2 code");
+        }
+        Ok(())
+    }
+}
+
+/// What's on air now and next. Unlike now-playing (per track, local to the node carrying the audio)
+/// this travels with the signed advertisement and lives in the registry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgramInfo {
+    /// Current show name
+    pub show: String,
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub ends_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub next_show: Option<String>,
+    #[serde(default)]
+    pub next_starts_at: Option<DateTime<Utc>>,
+}
+
+impl ProgramInfo {
+    /// JSON in field order with absent values as `null`; this exact text is what gets signed
+    pub fn canonical_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.show.trim().is_empty() {
+            return Err("program show must not be empty");
+        }
+        if self.show.chars().count() > MAX_SHOW_NAME_CHARS || self.next_show.as_ref().is_some_and(|s| s.chars().count() > MAX_SHOW_NAME_CHARS) {
+            return Err("program show name too long");
+        }
+        if let (Some(start), Some(end)) = (self.starts_at, self.ends_at) {
+            if end < start {
+                return Err("program ends before it starts");
+            }
+        }
+        Ok(())
+    }
+}
+
  #[derive(Debug, Clone, Serialize, Deserialize)]
  pub struct StationAssignment {
  	pub station_id: Uuid,
@@ -114,20 +511,338 @@ pub fn normalize_frequency_key(f: &BigDecimal) -> String {
  	pub last_seen: DateTime<Utc>,
  	pub expires_at: DateTime<Utc>,
     pub owner_public_key: String,
+    /// Sequence of the advertisement this assignment was built from
+    #[serde(default)]
+    pub sequence: u64,
+    /// TTL as advertised (expires_at may be clamped locally); part of the signed bytes
+    #[serde(default)]
+    pub ttl_seconds: u32,
+    /// Owner signature of the originating advertisement, so the assignment can be re-verified
+    #[serde(default)]
+    pub signature: String,
+    /// Display name from the owner's profile, filled in when served over HTTP (not signed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_name: Option<String>,
+    /// Program info from the originating advertisement (signed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program: Option<ProgramInfo>,
+    /// Stream format from the originating advertisement (signed); null when not reported
+    #[serde(default)]
+    pub bitrate_kbps: Option<u32>,
+    #[serde(default)]
+    pub codec: Option<String>,
+    /// Weighted alternatives to `stream_url` from the originating advertisement (signed)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stream_urls: Vec<StreamEndpoint>,
+    /// Id and signed layout of the originating advertisement, so it can be re-gossiped verbatim
+    #[serde(default)]
+    pub message_id: Option<Uuid>,
+    #[serde(default)]
+    pub canonical_version: u32,
+    /// Station artwork from the originating advertisement (signed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo_url: Option<String>,
+    /// Station location from the originating advertisement (signed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<StationLocation>,
+    /// The stream is served by the node answering the request, i.e. `stream_url` has its
+    /// public_url's origin; filled in when served over HTTP (not signed, omitted when false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub local: bool,
+ }
+
+/// Shortest station heartbeat; stations refresh every max(TTL/2, this)
+pub const MIN_HEARTBEAT_SECS: u32 = 10;
+
+/// Liveness of a listed assignment, judged from its refresh history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StationStatus {
+    /// Refreshed on schedule: within its last heartbeat period plus a quarter TTL of slack
+    Live,
+    /// Still within its TTL but a heartbeat is overdue; it expires unless one arrives
+    Stale,
+    /// The last stream healthcheck failed, whatever the heartbeats say (see `healthcheck`)
+    Unreachable,
+}
+
+impl FromStr for StationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "live" => Ok(Self::Live),
+            "stale" => Ok(Self::Stale),
+            "unreachable" => Ok(Self::Unreachable),
+            other => Err(format!("invalid status '{}'", other)),
+        }
+    }
+}
+
+impl StationAssignment {
+    /// Stale once the last refresh is older than one heartbeat period (max(TTL/2, MIN_HEARTBEAT_SECS),
+    /// as stations send them) plus a quarter of the TTL for gossip delay and clock skew. Never
+    /// `Unreachable`: that takes a healthcheck (`AppState::station_status`).
+    pub fn status(&self, now: DateTime<Utc>) -> StationStatus {
+        let period = (self.ttl_seconds / 2).max(MIN_HEARTBEAT_SECS) + self.ttl_seconds / 4;
+        if now - self.last_seen > chrono::Duration::seconds(period as i64) {
+            StationStatus::Stale
+        } else {
+            StationStatus::Live
+        }
+    }
+
+    /// Owner proof for this assignment on the network with signing `domain`; None for entries
+    /// without a stored signature
+    pub fn proof(&self, domain: &str) -> Option<EventProof> {
+        if self.signature.is_empty() {
+            return None;
+        }
+        let payload = self.to_advertisement().signed_bytes(domain)?;
+        Some(EventProof {
+            owner_public_key: self.owner_public_key.clone(),
+            signature: self.signature.clone(),
+            signed_payload: String::from_utf8(payload).ok()?,
+        })
+    }
+
+    /// Rebuild the signed advertisement this assignment came from (with a fresh message_id for
+    /// legacy entries that didn't record one)
+    pub fn to_advertisement(&self) -> StationAdvertisement {
+        StationAdvertisement {
+            message_id: self.message_id.unwrap_or_else(Uuid::new_v4),
+            station_id: self.station_id,
+            frequency: self.frequency.clone(),
+            name: self.name.clone(),
+            stream_url: self.stream_url.clone(),
+            advertised_at: self.last_seen,
+            ttl_seconds: self.ttl_seconds,
+            sequence: self.sequence,
+            owner_public_key: self.owner_public_key.clone(),
+            signature: self.signature.clone(),
+            program: self.program.clone(),
+            bitrate_kbps: self.bitrate_kbps,
+            codec: self.codec.clone(),
+            stream_urls: self.stream_urls.clone(),
+            canonical_version: self.canonical_version,
+            logo_url: self.logo_url.clone(),
+            location: self.location.clone(),
+        }
+    }
+}
+
+ /// Self-signed display details for an owner key, gossiped so UIs can show a name instead of base64
+ #[derive(Debug, Clone, Serialize, Deserialize)]
+ pub struct OwnerProfile {
+ 	/// Base64 Ed25519 public key the profile describes (and is signed by)
+ 	pub owner_public_key: String,
+ 	pub name: String,
+ 	pub url: Option<String>,
+ 	pub avatar_url: Option<String>,
+ 	/// Newer profiles replace older ones for the same key
+ 	pub updated_at: DateTime<Utc>,
+ 	pub signature: String,
+ }
+
+ /// An assignment held by this node's owner key, with heartbeat timing
+ #[derive(Debug, Clone, Serialize)]
+ pub struct MyStation {
+ 	#[serde(flatten)]
+ 	pub assignment: StationAssignment,
+ 	pub remaining_ttl_secs: i64,
+ 	pub next_heartbeat_at: Option<DateTime<Utc>>,
+ 	/// Why heartbeats aren't going out; the assignment lapses at expiry unless signing recovers
+ 	#[serde(skip_serializing_if = "Option::is_none")]
+ 	pub signing_error: Option<String>,
+ 	/// How peers received recent advertisements; None until one has reported back
+ 	#[serde(skip_serializing_if = "Option::is_none")]
+ 	pub peer_acks: Option<AdAckSummary>,
+ }
+
+ /// Incremental registry slice served at /api/v1/sync
+ #[derive(Debug, Clone, Serialize, Deserialize)]
+ pub struct SyncResponse {
+ 	pub assignments: Vec<StationAssignment>,
+ 	/// Pass back as `since` on the next poll
+ 	pub server_time: DateTime<Utc>,
+ }
+
+ /// One page of `GET /api/v1/stations?limit=&offset=`, in the same order as the unpaged list
+ #[derive(Debug, Clone, Serialize, Deserialize)]
+ pub struct StationsPage {
+ 	pub stations: Vec<StationAssignment>,
+ 	/// Stations matching the filters, across all pages
+ 	pub total: usize,
+ 	pub offset: usize,
+ 	/// Page size actually applied, after clamping
+ 	pub limit: usize,
+ }
+
+ #[derive(Debug, Clone, Serialize, Deserialize)]
+ pub struct StationsCount {
+ 	pub count: usize,
+ 	/// Monotonic registry mutation counter; refetch the list when it changes
+ 	pub generation: u64,
+ }
+
+ #[derive(Debug, Clone, Serialize)]
+ pub struct SpectrumOccupant {
+ 	#[serde(with = "serde_decimal")]
+ 	pub frequency: BigDecimal,
+ 	pub name: String,
+ 	pub owner_public_key: String,
+ }
+
+ /// One quantized slot [frequency, frequency + step) of the spectrum view
+ #[derive(Debug, Clone, Serialize)]
+ pub struct SpectrumSlot {
+ 	#[serde(with = "serde_decimal")]
+ 	pub frequency: BigDecimal,
+ 	pub occupied: bool,
+ 	#[serde(skip_serializing_if = "Vec::is_empty")]
+ 	pub stations: Vec<SpectrumOccupant>,
+ }
+
+ /// Operational snapshot served at /api/v1/stats
+ #[derive(Debug, Clone, Serialize)]
+ pub struct Stats {
+ 	pub active_source: Option<String>,
+ 	pub sources: Vec<SourceStatus>,
+ 	/// Median peer clock offset applied to timestamp checks (None unless --network-time)
+ 	pub network_time_offset_ms: Option<i64>,
+ 	/// Open `/stream` responses on this node
+ 	pub stream_listeners: usize,
+ 	/// Gossiped advertisements dropped unverified because --verify-concurrency was saturated
+ 	pub gossip_ads_shed: u64,
+ 	/// Blocklist fetcher health; omitted without --blocklist-url
+ 	#[serde(skip_serializing_if = "Option::is_none")]
+ 	pub blocklist: Option<BlocklistStatus>,
+ 	/// Host memory/disk as last seen by the resource guard, and what it degraded
+ 	pub resources: Option<ResourceStatus>,
+ 	/// The station's advertisements can't currently be signed; omitted while signing works
+ 	#[serde(skip_serializing_if = "Option::is_none")]
+ 	pub signing_failure: Option<SigningFailure>,
+ }
+
+ /// Signing of the local station's advertisements is failing, so it isn't being advertised
+ #[derive(Debug, Clone, Serialize)]
+ pub struct SigningFailure {
+ 	pub error: String,
+ 	pub consecutive_failures: u32,
+ 	/// First failure of the current run
+ 	pub since: DateTime<Utc>,
+ }
+
+ #[derive(Debug, Clone, Serialize)]
+ pub struct ResourceStatus {
+ 	/// None where it can't be measured (non-Linux)
+ 	pub memory_available_bytes: Option<u64>,
+ 	/// Free space for the audio debug capture; None without a capture
+ 	pub disk_available_bytes: Option<u64>,
+ 	pub low_memory: bool,
+ 	pub low_disk: bool,
+ 	/// The burst/rewind buffer is configured and not suspended for low memory
+ 	pub burst_enabled: bool,
+ 	/// The audio debug capture is paused for low disk space
+ 	pub capture_paused: bool,
+ 	pub checked_at: DateTime<Utc>,
+ }
+
+ #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+ #[serde(rename_all = "snake_case")]
+ pub enum BlocklistBreaker {
+ 	/// Fetching on schedule
+ 	#[default]
+ 	Closed,
+ 	/// Recent fetches failed; retrying with growing delays
+ 	BackingOff,
+ 	/// Too many failures in a row; fetching is paused for a cooldown
+ 	Open,
+ }
+
+ #[derive(Debug, Clone, Default, Serialize)]
+ pub struct BlocklistStatus {
+ 	pub breaker: BlocklistBreaker,
+ 	/// The blocklist in force is out of date because fetches are failing
+ 	pub stale: bool,
+ 	/// Addresses in the blocklist in force (the last good fetch)
+ 	pub entries: usize,
+ 	pub consecutive_failures: u32,
+ 	pub last_success_at: Option<DateTime<Utc>>,
+ 	pub last_error: Option<String>,
+ 	pub next_fetch_at: Option<DateTime<Utc>>,
+ }
+
+
+ /// A node's clock reading, gossiped for network time
+ #[derive(Debug, Clone, Serialize, Deserialize)]
+ pub struct TimeSample {
+ 	pub sent_at: DateTime<Utc>,
+ }
+
+ /// A node's verdict on a gossiped advertisement, addressed to its owner. Authenticated by the
+ /// gossip message signature, so it counts for the peer that signed it, whoever relayed it.
+ #[derive(Debug, Clone, Serialize, Deserialize)]
+ pub struct AdAck {
+ 	pub message_id: Uuid,
+ 	pub station_id: Uuid,
+ 	pub owner_public_key: String,
+ 	pub accepted: bool,
+ 	/// `RegistryError::code` of a rejection
+ 	#[serde(default, skip_serializing_if = "Option::is_none")]
+ 	pub code: Option<String>,
+ 	#[serde(default, skip_serializing_if = "Option::is_none")]
+ 	pub reason: Option<String>,
+ }
+
+ /// Sampled peer verdicts on a station's recent advertisements, the latest per peer
+ #[derive(Debug, Clone, Serialize)]
+ pub struct AdAckSummary {
+ 	pub accepted: usize,
+ 	pub rejected: usize,
+ 	/// Newest first
+ 	pub rejections: Vec<AdRejection>,
+ }
+
+ #[derive(Debug, Clone, Serialize)]
+ pub struct AdRejection {
+ 	pub peer_id: String,
+ 	pub message_id: Uuid,
+ 	pub code: String,
+ 	pub reason: String,
+ 	pub at: DateTime<Utc>,
  }
 
  #[derive(Debug, Clone, Serialize, Deserialize)]
  #[serde(rename_all = "lowercase")]
  pub enum AdvertiseResponseStatus {
  	Accepted,
+ 	/// Another station or owner holds the frequency (or station id)
  	Conflict,
+ 	/// The advertisement itself was refused (signature, limits, policy)
+ 	Rejected,
  }
 
  #[derive(Debug, Clone, Serialize, Deserialize)]
  pub struct AdvertiseResponse {
  	pub status: AdvertiseResponseStatus,
  	pub assigned_to: Option<StationAssignment>,
+ 	/// Human-readable cause, for operators
  	pub reason: Option<String>,
+ 	/// Stable snake_case cause (see `RegistryError::code`), for machines
+ 	#[serde(default)]
+ 	pub code: Option<String>,
+ }
+
+ impl AdvertiseResponse {
+ 	pub fn accepted(assignment: StationAssignment) -> Self {
+ 		Self { status: AdvertiseResponseStatus::Accepted, assigned_to: Some(assignment), reason: None, code: None }
+ 	}
+
+ 	/// `holder` is the current assignment on the frequency, reported for conflicts
+ 	pub fn refused(err: &RegistryError, holder: Option<StationAssignment>) -> Self {
+ 		let status = if err.is_conflict() { AdvertiseResponseStatus::Conflict } else { AdvertiseResponseStatus::Rejected };
+ 		Self { status, assigned_to: holder, reason: Some(err.to_string()), code: Some(err.code().to_string()) }
+ 	}
  }
 
  #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -148,6 +863,9 @@ pub fn normalize_frequency_key(f: &BigDecimal) -> String {
 	#[serde(with = "serde_decimal")]
 	pub frequency: BigDecimal,
  	pub reason: Option<String>,
+    /// Station sequence when released (see `next_sequence`); a release can't undo a newer advertisement
+    pub sequence: u64,
+    pub released_at: DateTime<Utc>,
     /// Signature by owner over canonical release bytes
     pub signature: String,
  }
@@ -157,25 +875,252 @@ pub fn normalize_frequency_key(f: &BigDecimal) -> String {
  	pub released: bool,
  }
 
+ /// Body of the admin transfer route
+ #[derive(Debug, Clone, Deserialize)]
+ pub struct TransferRequest {
+ 	/// Public URL of the node taking over this node's stations
+ 	pub successor_url: String,
+ }
+
+ /// Per-station outcome of an admin transfer
+ #[derive(Debug, Clone, Serialize)]
+ pub struct StationTransfer {
+ 	pub station_id: Uuid,
+ 	#[serde(with = "serde_decimal")]
+ 	pub frequency: BigDecimal,
+ 	/// Stream URL now advertised (on success) or that would have been
+ 	pub stream_url: Option<String>,
+ 	pub ok: bool,
+ 	pub error: Option<String>,
+ }
+
+ /// Body of the admin peer ban/unban routes
+ #[derive(Debug, Clone, Deserialize)]
+ pub struct PeerBanRequest {
+ 	/// libp2p PeerId, as logged and shown in node info
+ 	pub peer_id: String,
+ }
+
+ /// Result of the admin peer ban routes: the full ban list after the call
+ #[derive(Debug, Clone, Serialize)]
+ pub struct PeerBans {
+ 	pub banned: Vec<String>,
+ 	/// False when the call didn't change anything (already banned/not banned)
+ 	pub changed: bool,
+ }
+
+ /// Result of the admin advertise pause/resume routes
+ #[derive(Debug, Clone, Serialize)]
+ pub struct AdvertiseState {
+ 	pub paused: bool,
+ 	/// False when the call didn't change anything (already paused/resumed)
+ 	pub changed: bool,
+ }
+
  #[derive(Debug, Clone, Serialize, Deserialize)]
  pub struct ErrorResponse {
  	pub error: String,
  }
 
+ /// `available` event of `/api/v1/stations/{frequency}/watch`
+ #[derive(Debug, Clone, Serialize)]
+ pub struct FrequencyAvailable {
+ 	pub frequency: String,
+ 	/// "expired", "released" or "evicted"; omitted when the frequency was already free on connect
+ 	#[serde(skip_serializing_if = "Option::is_none")]
+ 	pub reason: Option<String>,
+ 	/// The assignment that just ended
+ 	#[serde(skip_serializing_if = "Option::is_none")]
+ 	pub previous: Option<StationAssignment>,
+ }
+
  #[derive(Debug, Clone, Serialize, Deserialize)]
  pub struct RegistryEvent {
  	/// "upsert" or "delete"
  	pub event: String,
  	pub assignment: StationAssignment,
+ 	/// Owner proof for upserts, so clients can check them without trusting the relaying node.
+ 	/// Deletes (expiry, eviction, release) are local decisions and carry no proof.
+ 	#[serde(default, skip_serializing_if = "Option::is_none")]
+ 	pub proof: Option<EventProof>,
+ 	/// Why a delete happened: "expired", "evicted" (registry full) or "released"
+ 	#[serde(default, skip_serializing_if = "Option::is_none")]
+ 	pub reason: Option<String>,
+ }
+
+ impl RegistryEvent {
+ 	/// Upserts carry the owner proof (over `domain`, the node's signing domain) when the assignment
+ 	/// has a stored signature
+ 	pub fn new(event: &str, assignment: StationAssignment, domain: &str) -> Self {
+ 		let proof = if event == "upsert" { assignment.proof(domain) } else { None };
+ 		Self { event: event.into(), assignment, proof, reason: None }
+ 	}
+
+ 	pub fn with_reason(mut self, reason: Option<&str>) -> Self {
+ 		self.reason = reason.map(Into::into);
+ 		self
+ 	}
+ }
+
+ /// A broadcast payload shared by all receivers and serialized at most once, when the first SSE
+ /// subscriber needs it; nothing is serialized for events nobody streams
+ #[derive(Debug)]
+ pub struct SharedEvent<T> {
+ 	value: T,
+ 	json: OnceLock<Box<RawValue>>,
+ }
+
+ impl<T: Serialize> SharedEvent<T> {
+ 	pub fn new(value: T) -> Arc<Self> {
+ 		Arc::new(Self { value, json: OnceLock::new() })
+ 	}
+
+ 	/// For a value the sender already had to serialize
+ 	pub fn with_json(value: T, json: Box<RawValue>) -> Arc<Self> {
+ 		Arc::new(Self { value, json: OnceLock::from(json) })
+ 	}
+
+ 	pub fn json(&self) -> &str {
+ 		self.json.get_or_init(|| {
+ 			serde_json::value::to_raw_value(&self.value).unwrap_or_else(|_| RawValue::from_string("{}".into()).expect("valid JSON"))
+ 		}).get()
+ 	}
+ }
+
+ impl<T> std::ops::Deref for SharedEvent<T> {
+ 	type Target = T;
+
+ 	fn deref(&self) -> &T {
+ 		&self.value
+ 	}
+ }
+
+ impl<T: Serialize> Serialize for SharedEvent<T> {
+ 	fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+ 		RawValue::from_string(self.json().to_string()).map_err(serde::ser::Error::custom)?.serialize(s)
+ 	}
+ }
+
+ /// One item of the combined `/api/v1/feed` stream; `type` says which channel it came from
+ #[derive(Debug, Clone, Serialize)]
+ #[serde(tag = "type", content = "data", rename_all = "snake_case")]
+ pub enum FeedEvent {
+ 	Registry(Arc<SharedEvent<RegistryEvent>>),
+ 	NowPlaying(Arc<SharedEvent<NowPlaying>>),
+ 	Source(IngestStatus),
+ }
+
+ impl FeedEvent {
+ 	/// SSE `event:` name, so browser clients can addEventListener per type
+ 	pub fn name(&self) -> &'static str {
+ 		match self {
+ 			FeedEvent::Registry(_) => "registry",
+ 			FeedEvent::NowPlaying(_) => "now_playing",
+ 			FeedEvent::Source(_) => "source",
+ 		}
+ 	}
+ }
+
+ /// The signed advertisement behind an upsert: verify `signature` over the UTF-8 bytes of
+ /// `signed_payload` with `owner_public_key`, then check the payload matches the assignment
+ #[derive(Debug, Clone, Serialize, Deserialize)]
+ pub struct EventProof {
+ 	pub owner_public_key: String,
+ 	pub signature: String,
+ 	pub signed_payload: String,
  }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NowPlaying {
     pub title: Option<String>,
+    /// Display string; derived from `artists` when the source only sends the list
     pub artist: Option<String>,
+    #[serde(default)]
+    pub artists: Vec<String>,
     pub album: Option<String>,
     pub cover_url: Option<String>,
+    pub isrc: Option<String>,
+    pub musicbrainz_id: Option<String>,
+    pub explicit: Option<bool>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl NowPlaying {
+    /// Same metadata, ignoring `updated_at`; sources often re-send the current track periodically
+    pub fn same_track(&self, other: &NowPlaying) -> bool {
+        self.title == other.title
+            && self.artist == other.artist
+            && self.artists == other.artists
+            && self.album == other.album
+            && self.cover_url == other.cover_url
+            && self.isrc == other.isrc
+            && self.musicbrainz_id == other.musicbrainz_id
+            && self.explicit == other.explicit
+    }
+
+    /// Build from a loosely-typed JSON object as sent by IPC/WebSocket sources; unknown fields are ignored
+    pub fn from_json(v: &serde_json::Value) -> Self {
+        let s = |k: &str| v.get(k).and_then(|x| x.as_str()).map(|s| s.to_string());
+        let mut artists: Vec<String> = v.get("artists")
+            .and_then(|x| x.as_array())
+            .map(|a| a.iter().filter_map(|x| x.as_str()).map(|s| s.to_string()).collect())
+            .unwrap_or_default();
+        let mut artist = s("artist");
+        // Keep the legacy single-string field and the list consistent in both directions
+        if artists.is_empty() {
+            if let Some(a) = &artist { artists.push(a.clone()); }
+        } else if artist.is_none() {
+            artist = Some(artists.join(", "));
+        }
+        NowPlaying {
+            title: s("title"),
+            artist,
+            artists,
+            album: s("album"),
+            cover_url: s("cover_url"),
+            isrc: s("isrc"),
+            musicbrainz_id: s("musicbrainz_id"),
+            explicit: v.get("explicit").and_then(|x| x.as_bool()),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// A `{"patch": true, ...}` update merges onto the current track instead of replacing it
+    pub fn is_patch(v: &serde_json::Value) -> bool {
+        v.get("patch").and_then(|x| x.as_bool()).unwrap_or(false)
+    }
+
+    /// Merge a patch onto `base`: fields present in `v` replace, `null` clears, absent fields are kept
+    pub fn merge_json(base: Option<&NowPlaying>, v: &serde_json::Value) -> Self {
+        let mut np = base.cloned().unwrap_or_else(|| NowPlaying::from_json(&serde_json::Value::Null));
+        let set = |k: &str, slot: &mut Option<String>| {
+            if let Some(x) = v.get(k) { *slot = x.as_str().map(|s| s.to_string()); }
+        };
+        set("title", &mut np.title);
+        set("album", &mut np.album);
+        set("cover_url", &mut np.cover_url);
+        set("isrc", &mut np.isrc);
+        set("musicbrainz_id", &mut np.musicbrainz_id);
+        if let Some(x) = v.get("explicit") { np.explicit = x.as_bool(); }
+        // Patching either artist field rederives the other, as a full update would
+        let (artist, artists) = (v.get("artist"), v.get("artists"));
+        if let Some(x) = artists {
+            np.artists = x.as_array()
+                .map(|a| a.iter().filter_map(|x| x.as_str()).map(|s| s.to_string()).collect())
+                .unwrap_or_default();
+        }
+        if let Some(x) = artist {
+            np.artist = x.as_str().map(|s| s.to_string());
+            if artists.is_none() || np.artists.is_empty() {
+                np.artists = np.artist.iter().cloned().collect();
+            }
+        }
+        if artists.is_some() && (artist.is_none() || np.artist.is_none()) {
+            np.artist = (!np.artists.is_empty()).then(|| np.artists.join(", "));
+        }
+        np.updated_at = Utc::now();
+        np
+    }
+}
+
 
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc, Semaphore};
+use tracing::{debug, warn};
+
+use crate::state::AppState;
+use crate::types::{RegistryEvent, SharedEvent};
+
+// Per-request timeout and retry schedule for webhook deliveries
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const WEBHOOK_ATTEMPTS: u32 = 3;
+const WEBHOOK_BACKOFF: Duration = Duration::from_secs(1);
+
+/// POST `body` as JSON, retrying network errors, 429 and 5xx with exponential backoff.
+/// Other 4xx answers are final: the endpoint rejected the payload and retrying won't help.
+pub async fn post_with_retry(client: &reqwest::Client, url: &str, body: &impl serde::Serialize) -> anyhow::Result<()> {
+	let mut delay = WEBHOOK_BACKOFF;
+	for attempt in 1..=WEBHOOK_ATTEMPTS {
+		let err = match client.post(url).json(body).send().await {
+			Ok(resp) if resp.status().is_success() => return Ok(()),
+			Ok(resp) if resp.status().is_server_error() || resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+				anyhow::anyhow!("webhook returned {}", resp.status())
+			}
+			Ok(resp) => anyhow::bail!("webhook rejected delivery with {}", resp.status()),
+			Err(err) => err.into(),
+		};
+		if attempt == WEBHOOK_ATTEMPTS {
+			return Err(err);
+		}
+		debug!(error=%err, attempt, url, "webhook delivery failed; retrying");
+		tokio::time::sleep(delay).await;
+		delay *= 2;
+	}
+	unreachable!()
+}
+
+/// Forward every now-playing change to `url`. Runs off the broadcast so IPC/WS handlers never wait;
+/// at most `concurrency` deliveries are in flight. While saturated it waits for a free slot, so
+/// updates queue in the broadcast buffer; past that buffer they are dropped with a lag warning.
+pub fn spawn_now_webhook(state: Arc<AppState>, url: String, concurrency: usize) {
+	tokio::spawn(async move {
+		let client = match reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build() {
+			Ok(c) => c,
+			Err(err) => {
+				warn!(error=%err, "failed to build now-playing webhook client");
+				return;
+			}
+		};
+		let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+		let mut rx = state.now_tx.subscribe();
+		loop {
+			let np = match rx.recv().await {
+				Ok(np) => np,
+				Err(broadcast::error::RecvError::Lagged(n)) => {
+					warn!(skipped = n, "now-playing webhook is behind; dropping updates");
+					continue;
+				}
+				Err(broadcast::error::RecvError::Closed) => break,
+			};
+			// Waiting here lets the broadcast buffer absorb bursts; beyond that updates lag and are dropped
+			let Ok(permit) = permits.clone().acquire_owned().await else { break };
+			let (client, url) = (client.clone(), url.clone());
+			tokio::spawn(async move {
+				if let Err(err) = post_with_retry(&client, &url, &np).await {
+					warn!(error=%err, url, "dropping now-playing webhook delivery");
+				}
+				drop(permit);
+			});
+		}
+	});
+}
+
+/// Forward registry events whose type is in `events` (all when empty) to `url`, in order, one
+/// delivery at a time. A bounded queue of `queue` events sits between the registry broadcast and
+/// the sender, so a slow endpoint never holds up the registry; when it is full, new events are dropped.
+pub fn spawn_event_webhook(state: Arc<AppState>, url: String, events: Vec<String>, queue: usize) {
+	let client = match reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build() {
+		Ok(c) => c,
+		Err(err) => {
+			warn!(error=%err, "failed to build event webhook client");
+			return;
+		}
+	};
+	let (tx, mut rx) = mpsc::channel::<Arc<SharedEvent<RegistryEvent>>>(queue.max(1));
+	let mut events_rx = state.events_tx.subscribe();
+	tokio::spawn(async move {
+		let mut dropped = 0u64;
+		loop {
+			let ev = match events_rx.recv().await {
+				Ok(ev) => ev,
+				Err(broadcast::error::RecvError::Lagged(n)) => {
+					warn!(skipped = n, "event webhook is behind; dropping registry events");
+					continue;
+				}
+				Err(broadcast::error::RecvError::Closed) => break,
+			};
+			if !events.is_empty() && !events.iter().any(|e| *e == ev.event) {
+				continue;
+			}
+			match tx.try_send(ev) {
+				Ok(()) if dropped > 0 => {
+					warn!(dropped, "event webhook queue drained; dropped registry events while it was full");
+					dropped = 0;
+				}
+				Ok(()) => {}
+				Err(mpsc::error::TrySendError::Full(_)) => dropped += 1,
+				Err(mpsc::error::TrySendError::Closed(_)) => break,
+			}
+		}
+	});
+	tokio::spawn(async move {
+		while let Some(ev) = rx.recv().await {
+			if let Err(err) = post_with_retry(&client, &url, &ev).await {
+				warn!(error=%err, url, event = %ev.event, "dropping registry event webhook delivery");
+			}
+		}
+	});
+}
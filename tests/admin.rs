@@ -0,0 +1,23 @@
+use shortwave::testing::spawn_node;
+
+async fn list_bans(base_url: &str, bearer: Option<&str>) -> reqwest::StatusCode {
+	let mut req = reqwest::Client::new().get(format!("{base_url}/api/v1/admin/peers/bans"));
+	if let Some(token) = bearer {
+		req = req.bearer_auth(token);
+	}
+	req.send().await.unwrap().status()
+}
+
+#[tokio::test]
+async fn admin_routes_need_the_admin_token() {
+	let node = spawn_node(&["--admin-token", "admin-secret"], Vec::new()).await.unwrap();
+	assert_eq!(list_bans(&node.base_url, None).await, reqwest::StatusCode::UNAUTHORIZED);
+	assert_eq!(list_bans(&node.base_url, Some("admin-secre")).await, reqwest::StatusCode::UNAUTHORIZED);
+	assert_eq!(list_bans(&node.base_url, Some("admin-secret")).await, reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn admin_routes_are_off_without_a_token() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	assert_eq!(list_bans(&node.base_url, Some("")).await, reqwest::StatusCode::UNAUTHORIZED);
+}
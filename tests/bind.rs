@@ -0,0 +1,52 @@
+use std::collections::HashSet;
+use std::future::IntoFuture;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use shortwave::config::Cli;
+use shortwave::http;
+use shortwave::state::AppState;
+use tokio::task::JoinHandle;
+
+/// Serve the node's router on a listener bound like `--bind addr` does
+fn serve(addr: SocketAddr, v6_only: bool) -> (Arc<AppState>, SocketAddr, JoinHandle<()>) {
+	let listener = http::bind_http_listener(addr, v6_only).unwrap();
+	let local = listener.local_addr().unwrap();
+	let public_url = format!("http://{local}");
+	let config = Cli::try_parse_from(["shortwave", "--public-url", public_url.as_str()]).unwrap().into_config().unwrap();
+	let state = Arc::new(AppState::new(&config, None));
+	let app = http::router(state.clone(), &config);
+	let server = tokio::spawn(async move {
+		let _ = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).into_future().await;
+	});
+	(state, local, server)
+}
+
+async fn stations_status(url: String) -> u16 {
+	reqwest::get(url).await.unwrap().status().as_u16()
+}
+
+#[tokio::test]
+async fn ipv6_loopback_listener_serves_ipv6_clients() {
+	let (_, local, server) = serve(SocketAddr::from((Ipv6Addr::LOCALHOST, 0)), true);
+	assert_eq!(stations_status(format!("http://[::1]:{}/api/v1/stations", local.port())).await, 200);
+	server.abort();
+}
+
+#[tokio::test]
+async fn dual_stack_listener_serves_both_families() {
+	let (_, local, server) = serve(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)), false);
+	assert_eq!(stations_status(format!("http://[::1]:{}/api/v1/stations", local.port())).await, 200);
+	assert_eq!(stations_status(format!("http://127.0.0.1:{}/api/v1/stations", local.port())).await, 200);
+	server.abort();
+}
+
+#[tokio::test]
+async fn blocklisted_ipv4_client_is_refused_on_a_dual_stack_listener() {
+	let (state, local, server) = serve(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)), false);
+	// The client arrives as ::ffff:127.0.0.1 but is listed by its IPv4 address
+	state.set_blocklist(HashSet::from([IpAddr::V4(Ipv4Addr::LOCALHOST)])).await;
+	assert_eq!(stations_status(format!("http://127.0.0.1:{}/api/v1/stations", local.port())).await, 403);
+	assert_eq!(stations_status(format!("http://[::1]:{}/api/v1/stations", local.port())).await, 200);
+	server.abort();
+}
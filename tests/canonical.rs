@@ -0,0 +1,71 @@
+use shortwave::crypto::signing_domain;
+use shortwave::state::RegistryError;
+use shortwave::testing::{app_state, owner_key, resign, signed_ad};
+use shortwave::types::StationAdvertisement;
+use uuid::Uuid;
+
+fn with_fields(name: &str, stream_url: &str) -> StationAdvertisement {
+	let owner = owner_key();
+	let mut ad = signed_ad(&owner, "101.1", Uuid::new_v4(), 1);
+	ad.name = name.into();
+	ad.stream_url = stream_url.into();
+	resign(&owner, &mut ad);
+	ad
+}
+
+#[tokio::test]
+async fn separators_and_unicode_stay_inside_their_fields() {
+	let state = app_state(&[]);
+	let ad = with_fields("Café ☕;url=http://evil/=", "http://127.0.0.1:8080/stream;ttl=99999;name=x");
+	let signed = String::from_utf8(ad.signed_bytes(&signing_domain(None)).unwrap()).unwrap();
+	assert!(signed.contains(r#""name":"Café ☕;url=http://evil/=""#), "{signed}");
+	assert!(signed.contains(r#""url":"http://127.0.0.1:8080/stream;ttl=99999;name=x""#), "{signed}");
+	state.accept_advertisement(&ad).await.unwrap();
+}
+
+#[tokio::test]
+async fn moving_text_between_fields_breaks_the_signature() {
+	let state = app_state(&[]);
+	// Each pair would read the same if the fields were joined with bare separators
+	let pairs = [
+		(("Morning;url=http://a", "http://b"), ("Morning", "http://a;url=http://b")),
+		(("Déjà=vu", "http://a/ü"), ("Déjà", "=vuhttp://a/ü")),
+		(("A\"B", "http://x"), ("A", "\"Bhttp://x")),
+	];
+	for ((name_a, url_a), (name_b, url_b)) in pairs {
+		let signed = with_fields(name_a, url_a);
+		let mut forged = signed.clone();
+		forged.message_id = Uuid::new_v4();
+		forged.name = name_b.into();
+		forged.stream_url = url_b.into();
+		let domain = signing_domain(None);
+		assert_ne!(signed.signed_bytes(&domain), forged.signed_bytes(&domain));
+		let err = state.accept_advertisement(&forged).await.unwrap_err();
+		assert!(matches!(err, RegistryError::InvalidSignature), "{name_b:?}/{url_b:?}: {err}");
+	}
+}
+
+#[tokio::test]
+async fn minimum_canonical_version_refuses_older_layouts() {
+	let owner = owner_key();
+	let mut legacy = signed_ad(&owner, "101.1", Uuid::new_v4(), 1);
+	legacy.canonical_version = 2;
+	resign(&owner, &mut legacy);
+	let current = signed_ad(&owner, "102.1", Uuid::new_v4(), 1);
+
+	app_state(&[]).accept_advertisement(&legacy).await.unwrap();
+
+	let strict = app_state(&["--min-canonical-version", "3"]);
+	let err = strict.accept_advertisement(&legacy).await.unwrap_err();
+	assert!(matches!(err, RegistryError::VersionTooOld(2, 3)), "{err}");
+	assert_eq!(err.code(), "version_too_old");
+	strict.accept_advertisement(&current).await.unwrap();
+}
+
+#[test]
+fn minimum_canonical_version_above_what_we_sign_is_a_config_error() {
+	let cli = shortwave::config::Cli::try_parse_from([
+		"shortwave", "--public-url", "http://127.0.0.1:8080", "--min-canonical-version", "4",
+	]).unwrap();
+	assert!(cli.into_config().is_err());
+}
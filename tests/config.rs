@@ -0,0 +1,62 @@
+use base64::Engine as _;
+use shortwave::config::{Cli, Config};
+use shortwave::testing::owner_key;
+
+fn load(args: &[&str]) -> anyhow::Result<Config> {
+	let cli = Cli::try_parse_from(["shortwave", "--public-url", "http://127.0.0.1:8080"].into_iter().chain(args.iter().copied()))?;
+	let config = cli.into_config()?;
+	config.validate()?;
+	Ok(config)
+}
+
+const STATION: [&str; 4] = ["--name", "Test FM", "--frequency", "101.1"];
+
+#[test]
+fn station_mode_without_an_owner_key_refuses_to_start() {
+	let err = load(&STATION).unwrap_err();
+	assert!(err.to_string().contains("requires a persistent owner key"), "{err}");
+}
+
+#[test]
+fn station_mode_starts_with_an_owner_key_or_explicit_ephemeral_opt_in() {
+	let secret = base64::engine::general_purpose::STANDARD.encode(owner_key().to_bytes());
+	load(&[&STATION[..], &["--owner-secret-key", &secret]].concat()).unwrap();
+	load(&[&STATION[..], &["--allow-ephemeral-owner-key"]].concat()).unwrap();
+}
+
+#[test]
+fn relay_mode_needs_no_owner_key() {
+	load(&[]).unwrap();
+}
+
+#[test]
+fn http_dscp_takes_class_names_and_the_old_flag_name() {
+	assert_eq!(load(&["--http-dscp", "EF"]).unwrap().http_dscp, Some(46));
+	assert_eq!(load(&["--stream-dscp", "af41"]).unwrap().http_dscp, Some(34));
+	assert!(load(&["--http-dscp", "64"]).is_err());
+}
+
+fn config_file(yaml: &str) -> String {
+	let path = std::env::temp_dir().join(format!("shortwave-test-{}.yaml", uuid::Uuid::new_v4()));
+	std::fs::write(&path, yaml).unwrap();
+	path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn command_line_flags_override_the_config_file() {
+	let path = config_file("bind: 127.0.0.1:9000\nmax_registry_size: 5\nmax_ttl_secs: 120\n");
+	let config = load(&["--config", &path, "--bind", "127.0.0.1:9100", "--max-ttl-secs", "3600"]).unwrap();
+	assert_eq!(config.bind, ["127.0.0.1:9100"]);
+	// Given explicitly, a flag wins even when it repeats its own default
+	assert_eq!(config.max_ttl_secs, 3600);
+	// Settings only in the file still come from it
+	assert_eq!(config.max_registry_size, 5);
+	std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn config_file_overrides_defaults() {
+	let path = config_file("bind: 127.0.0.1:9000\n");
+	assert_eq!(load(&["--config", &path]).unwrap().bind, ["127.0.0.1:9000"]);
+	std::fs::remove_file(path).unwrap();
+}
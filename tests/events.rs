@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::Duration;
+
+use bigdecimal::BigDecimal;
+use shortwave::testing::{owner_key, signed_ad, spawn_node, TestNode};
+use uuid::Uuid;
+
+const STATIONS: usize = 1000;
+
+#[derive(Debug)]
+struct SseEvent {
+	id: Option<String>,
+	event: Option<String>,
+	data: String,
+}
+
+impl SseEvent {
+	fn upsert(&self) -> (BigDecimal, Uuid) {
+		let v: serde_json::Value = serde_json::from_str(&self.data).unwrap();
+		assert_eq!(v["event"], "upsert", "{}", self.data);
+		let a = &v["assignment"];
+		(
+			BigDecimal::from_str(a["frequency"].to_string().trim_matches('"')).unwrap(),
+			a["station_id"].as_str().unwrap().parse().unwrap(),
+		)
+	}
+}
+
+struct SseReader {
+	resp: reqwest::Response,
+	buf: String,
+}
+
+impl SseReader {
+	async fn connect(node: &TestNode, last_event_id: Option<&str>) -> Self {
+		let mut req = reqwest::Client::new().get(format!("{}/api/v1/events?snapshot=1", node.base_url));
+		if let Some(id) = last_event_id {
+			req = req.header("last-event-id", id);
+		}
+		let resp = req.send().await.unwrap().error_for_status().unwrap();
+		Self { resp, buf: String::new() }
+	}
+
+	async fn next(&mut self) -> SseEvent {
+		loop {
+			if let Some(end) = self.buf.find("\n\n") {
+				let block: String = self.buf.drain(..end + 2).collect();
+				let mut event = SseEvent { id: None, event: None, data: String::new() };
+				for line in block.lines() {
+					match line.split_once(':') {
+						Some(("id", v)) => event.id = Some(v.trim().to_string()),
+						Some(("event", v)) => event.event = Some(v.trim().to_string()),
+						Some(("data", v)) => event.data.push_str(v.trim_start()),
+						_ => {}
+					}
+				}
+				// Keep-alive comments carry nothing
+				if event.id.is_some() || event.event.is_some() || !event.data.is_empty() {
+					return event;
+				}
+				continue;
+			}
+			let chunk = tokio::time::timeout(Duration::from_secs(10), self.resp.chunk()).await
+				.expect("event within 10s").unwrap().expect("stream still open");
+			self.buf.push_str(std::str::from_utf8(&chunk).unwrap());
+		}
+	}
+
+	/// Snapshot upserts up to `snapshot_complete` (or the first `limit`), with their ids
+	async fn snapshot(&mut self, limit: usize) -> Vec<SseEvent> {
+		let mut out = Vec::new();
+		while out.len() < limit {
+			let event = self.next().await;
+			if event.event.as_deref() == Some("snapshot_complete") {
+				break;
+			}
+			out.push(event);
+		}
+		out
+	}
+}
+
+/// A node holding `STATIONS` assignments on 100.0, 100.1, ... 199.9. Copies of one accepted
+/// assignment are imported directly, since verifying a thousand signatures adds nothing here.
+async fn big_registry() -> (TestNode, Vec<Uuid>) {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let template = node.state.accept_advertisement(&signed_ad(&owner_key(), "100.0", Uuid::new_v4(), 1)).await.unwrap();
+	let mut ids = Vec::with_capacity(STATIONS);
+	for i in 0..STATIONS {
+		let mut assignment = template.clone();
+		assignment.station_id = Uuid::new_v4();
+		assignment.frequency = BigDecimal::from_str(&format!("{}.{}", 100 + i / 10, i % 10)).unwrap();
+		node.state.import_assignment(assignment.clone()).await;
+		ids.push(assignment.station_id);
+	}
+	(node, ids)
+}
+
+#[tokio::test]
+async fn big_snapshot_arrives_complete_and_in_order_across_pages() {
+	let (node, ids) = big_registry().await;
+	let mut sse = SseReader::connect(&node, None).await;
+	let snapshot = sse.snapshot(usize::MAX).await;
+	assert_eq!(snapshot.len(), STATIONS);
+	let upserts: Vec<(BigDecimal, Uuid)> = snapshot.iter().map(SseEvent::upsert).collect();
+	assert!(upserts.windows(2).all(|w| w[0].0 < w[1].0), "snapshot not in frequency order");
+	let seen: HashSet<Uuid> = upserts.iter().map(|(_, id)| *id).collect();
+	assert_eq!(seen, ids.into_iter().collect());
+	assert!(snapshot.iter().all(|e| e.id.is_some()), "every snapshot event is resumable");
+}
+
+#[tokio::test]
+async fn changes_during_a_big_snapshot_are_not_lost() {
+	let (node, _) = big_registry().await;
+	let mut sse = SseReader::connect(&node, None).await;
+	let first = sse.next().await;
+	assert_eq!(first.event, None);
+
+	// Land below, inside and above the snapshot while it is being paged out
+	let owner = owner_key();
+	let mut added = HashSet::new();
+	for frequency in ["50.5", "150.55", "900.5"] {
+		let station_id = Uuid::new_v4();
+		node.state.accept_advertisement(&signed_ad(&owner, frequency, station_id, 1)).await.unwrap();
+		added.insert(station_id);
+	}
+
+	let mut missing = added.clone();
+	missing.remove(&first.upsert().1);
+	let mut complete = false;
+	while !missing.is_empty() || !complete {
+		let event = sse.next().await;
+		match event.event.as_deref() {
+			Some("snapshot_complete") => complete = true,
+			None => { missing.remove(&event.upsert().1); }
+			Some(other) => panic!("unexpected {other} event"),
+		}
+	}
+}
+
+#[tokio::test]
+async fn interrupted_snapshot_resumes_after_the_last_event_id() {
+	let (node, ids) = big_registry().await;
+	let mut sse = SseReader::connect(&node, None).await;
+	let head = sse.snapshot(300).await;
+	drop(sse);
+
+	let mut sse = SseReader::connect(&node, head.last().unwrap().id.as_deref()).await;
+	let tail = sse.snapshot(usize::MAX).await;
+	assert_eq!(head.len() + tail.len(), STATIONS);
+	let seen: HashSet<Uuid> = head.iter().chain(&tail).map(|e| e.upsert().1).collect();
+	assert_eq!(seen, ids.into_iter().collect());
+}
+
+#[tokio::test]
+async fn cursor_from_an_older_generation_restarts_the_snapshot() {
+	let (node, _) = big_registry().await;
+	let mut sse = SseReader::connect(&node, None).await;
+	let head = sse.snapshot(10).await;
+	drop(sse);
+	node.state.accept_advertisement(&signed_ad(&owner_key(), "99.9", Uuid::new_v4(), 1)).await.unwrap();
+
+	let mut sse = SseReader::connect(&node, head.last().unwrap().id.as_deref()).await;
+	assert_eq!(sse.next().await.event.as_deref(), Some("snapshot_reset"));
+	assert_eq!(sse.snapshot(usize::MAX).await.len(), STATIONS + 1);
+}
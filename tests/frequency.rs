@@ -0,0 +1,58 @@
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use shortwave::testing::{owner_key, signed_ad, spawn_node};
+use shortwave::types::{normalize_frequency_key, parse_frequency};
+use uuid::Uuid;
+
+fn mhz(s: &str) -> String {
+	normalize_frequency_key(&parse_frequency(s).unwrap_or_else(|e| panic!("{s}: {e}")))
+}
+
+#[test]
+fn megahertz_is_the_base_unit() {
+	assert_eq!(mhz("88.5 MHz"), "88.5");
+	assert_eq!(mhz("88.5MHz"), "88.5");
+	assert_eq!(mhz("88.5 mhz"), "88.5");
+	assert_eq!(mhz("0.1 GHz"), "100");
+}
+
+#[test]
+fn kilohertz_and_hertz_are_scaled_to_megahertz() {
+	assert_eq!(mhz("6175 kHz"), "6.175");
+	assert_eq!(mhz("6175kHz"), "6.175");
+	assert_eq!(mhz("198 KHZ"), "0.198");
+	assert_eq!(mhz("1500000 Hz"), "1.5");
+}
+
+#[test]
+fn bare_numbers_are_megahertz() {
+	assert_eq!(mhz("101.1"), "101.1");
+	assert_eq!(mhz(" 101.10 "), "101.1");
+	assert_eq!(parse_frequency("7").unwrap(), BigDecimal::from_str("7").unwrap());
+}
+
+#[test]
+fn unknown_or_ambiguous_units_are_rejected() {
+	assert!(parse_frequency("88.5 mHz").unwrap_err().contains("ambiguous"));
+	assert!(parse_frequency("88.5 THz").unwrap_err().contains("unknown frequency unit"));
+	assert!(parse_frequency("88.5 MHz MHz").is_err());
+	assert!(parse_frequency("MHz").is_err());
+	assert!(parse_frequency("").is_err());
+}
+
+#[tokio::test]
+async fn lookup_by_frequency_accepts_units() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let station_id = Uuid::new_v4();
+	node.state.accept_advertisement(&signed_ad(&owner_key(), "6.175", station_id, 1)).await.unwrap();
+
+	for path in ["6.175", "6175kHz", "6.175MHz"] {
+		let station: serde_json::Value = reqwest::get(format!("{}/api/v1/stations/{path}", node.base_url)).await.unwrap()
+			.error_for_status().unwrap()
+			.json().await.unwrap();
+		assert_eq!(station["station_id"], station_id.to_string(), "{path}");
+	}
+	let resp = reqwest::get(format!("{}/api/v1/stations/6175furlongs", node.base_url)).await.unwrap();
+	assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use shortwave::testing::{owner_key, signed_ad, signed_release, spawn_mesh, wait_for_station, TestNode};
+use shortwave::types::normalize_frequency_key;
+use uuid::Uuid;
+
+const CONVERGE: Duration = Duration::from_secs(20);
+
+/// Wait until no node's registry holds anything on `frequency_key`
+async fn wait_for_vacant(nodes: &[TestNode], frequency_key: &str, timeout: Duration) -> bool {
+	let deadline = tokio::time::Instant::now() + timeout;
+	loop {
+		let mut vacant = true;
+		for node in nodes {
+			if node.state.get_assignment_by_key(frequency_key).await.is_some() {
+				vacant = false;
+			}
+		}
+		if vacant {
+			return true;
+		}
+		if tokio::time::Instant::now() >= deadline {
+			return false;
+		}
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+}
+
+#[tokio::test]
+async fn advertisement_reaches_every_node() {
+	let nodes = spawn_mesh(2, &[]).await.unwrap();
+	let owner = owner_key();
+	let ad = signed_ad(&owner, "101.1", Uuid::new_v4(), 1);
+	nodes[0].state.accept_advertisement(&ad).await.unwrap();
+	nodes[0].p2p.publish_advertisement(ad.clone()).await;
+	assert!(wait_for_station(&nodes, &ad.frequency, ad.station_id, CONVERGE).await, "advertisement didn't propagate");
+}
+
+#[tokio::test]
+async fn advertisement_and_release_are_relayed_across_three_nodes() {
+	// The third node only knows the first, so the second's messages reach it through the first
+	let nodes = spawn_mesh(3, &[]).await.unwrap();
+	let owner = owner_key();
+	let station_id = Uuid::new_v4();
+	let ad = signed_ad(&owner, "94.7", station_id, 1);
+	nodes[1].state.accept_advertisement(&ad).await.unwrap();
+	nodes[1].p2p.publish_advertisement(ad.clone()).await;
+	assert!(wait_for_station(&nodes, &ad.frequency, station_id, CONVERGE).await, "advertisement didn't propagate");
+
+	let release = signed_release(&owner, "94.7", station_id, 2, Utc::now());
+	assert!(nodes[1].state.release_assignment(&release).await);
+	nodes[1].p2p.publish_release(release).await;
+	let key = normalize_frequency_key(&ad.frequency);
+	assert!(wait_for_vacant(&nodes, &key, CONVERGE).await, "release didn't propagate");
+}
+
+#[tokio::test]
+async fn forged_release_does_not_propagate() {
+	let nodes = spawn_mesh(2, &[]).await.unwrap();
+	let (owner, intruder) = (owner_key(), owner_key());
+	let station_id = Uuid::new_v4();
+	let ad = signed_ad(&owner, "88.1", station_id, 1);
+	nodes[0].state.accept_advertisement(&ad).await.unwrap();
+	nodes[0].p2p.publish_advertisement(ad.clone()).await;
+	assert!(wait_for_station(&nodes, &ad.frequency, station_id, CONVERGE).await);
+
+	nodes[0].p2p.publish_release(signed_release(&intruder, "88.1", station_id, 2, Utc::now())).await;
+	tokio::time::sleep(Duration::from_secs(2)).await;
+	let key = normalize_frequency_key(&ad.frequency);
+	assert_eq!(nodes[1].state.get_assignment_by_key(&key).await.map(|a| a.station_id), Some(station_id));
+}
+
+#[tokio::test]
+async fn stations_from_every_node_converge_to_the_same_listing() {
+	let nodes = spawn_mesh(3, &[]).await.unwrap();
+	let mut stations = Vec::new();
+	for (i, node) in nodes.iter().enumerate() {
+		let ad = signed_ad(&owner_key(), &format!("{}.5", 90 + i), Uuid::new_v4(), 1);
+		node.state.accept_advertisement(&ad).await.unwrap();
+		node.p2p.publish_advertisement(ad.clone()).await;
+		stations.push(ad);
+	}
+	for ad in &stations {
+		assert!(wait_for_station(&nodes, &ad.frequency, ad.station_id, CONVERGE).await, "{} didn't reach every node", ad.frequency);
+	}
+
+	// Every node's HTTP listing is the same, in the same order
+	let mut listings = Vec::new();
+	for node in &nodes {
+		let body: Vec<serde_json::Value> = reqwest::get(format!("{}/api/v1/stations", node.base_url)).await.unwrap()
+			.json().await.unwrap();
+		listings.push(body.iter().map(|s| s["station_id"].as_str().unwrap().to_string()).collect::<Vec<_>>());
+	}
+	let expected: Vec<String> = stations.iter().map(|ad| ad.station_id.to_string()).collect();
+	assert!(listings.iter().all(|l| *l == expected), "{listings:?}");
+}
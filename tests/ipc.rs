@@ -0,0 +1,128 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use shortwave::ipc::{bind_ipc_socket, run_audio_ipc_listener, run_ipc_listener};
+use shortwave::state::AppState;
+use shortwave::testing::app_state;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+fn socket_path() -> String {
+	std::env::temp_dir().join(format!("sw-{}.sock", &uuid::Uuid::new_v4().simple().to_string()[..12])).to_string_lossy().into_owned()
+}
+
+async fn now_playing_title(state: &AppState) -> Option<String> {
+	state.now_playing.read().await.as_ref().and_then(|np| np.title.clone())
+}
+
+/// Wait until the current title is `title`; false after a second
+async fn wait_for_title(state: &AppState, title: &str) -> bool {
+	for _ in 0..100 {
+		if now_playing_title(state).await.as_deref() == Some(title) {
+			return true;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	false
+}
+
+fn start_ipc(state: &Arc<AppState>, max_connections: usize) -> String {
+	let path = socket_path();
+	let listener = bind_ipc_socket(&path).unwrap();
+	tokio::spawn(run_ipc_listener(state.clone(), listener, max_connections));
+	path
+}
+
+#[tokio::test]
+async fn oversized_ipc_line_is_discarded_and_the_next_line_applies() {
+	let state = app_state(&[]);
+	let path = start_ipc(&state, 4);
+	let mut updates = state.now_tx.subscribe();
+	let mut client = UnixStream::connect(&path).await.unwrap();
+
+	// Valid JSON, but well past the 64 KiB line cap
+	let huge = format!("{{\"title\":\"{}\"}}\n", "x".repeat(200 * 1024));
+	client.write_all(huge.as_bytes()).await.unwrap();
+	client.write_all(b"{\"title\":\"after\"}\n").await.unwrap();
+	let first = tokio::time::timeout(Duration::from_secs(1), updates.recv()).await.unwrap().unwrap();
+	assert_eq!(first.title.as_deref(), Some("after"), "the oversized line must not be applied");
+	let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn several_json_lines_in_one_write_all_apply() {
+	let state = app_state(&[]);
+	let path = start_ipc(&state, 4);
+	let mut client = UnixStream::connect(&path).await.unwrap();
+	client.write_all(b"{\"title\":\"one\"}\nnot json\n{\"title\":\"two\"}\n").await.unwrap();
+	assert!(wait_for_title(&state, "two").await);
+	let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn connections_over_the_ipc_limit_are_closed() {
+	let state = app_state(&[]);
+	let path = start_ipc(&state, 2);
+	let mut held = Vec::new();
+	for _ in 0..2 {
+		held.push(UnixStream::connect(&path).await.unwrap());
+	}
+	// Accepted and closed straight away: the read sees EOF
+	let mut extra = UnixStream::connect(&path).await.unwrap();
+	let mut buf = [0u8; 1];
+	let read = tokio::time::timeout(Duration::from_secs(1), extra.read(&mut buf)).await.unwrap().unwrap();
+	assert_eq!(read, 0);
+
+	// Once a slot frees, a new client is served again
+	drop(held.pop());
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	let mut client = UnixStream::connect(&path).await.unwrap();
+	client.write_all(b"{\"title\":\"back\"}\n").await.unwrap();
+	assert!(wait_for_title(&state, "back").await);
+	let _ = std::fs::remove_file(path);
+}
+
+async fn next_chunk(audio: &mut tokio::sync::broadcast::Receiver<bytes::Bytes>) -> bytes::Bytes {
+	tokio::time::timeout(Duration::from_secs(1), audio.recv()).await.unwrap().unwrap()
+}
+
+/// Read what the node says to a refused client, up to its closing the connection
+async fn refusal(client: &mut UnixStream) -> String {
+	let mut reply = String::new();
+	tokio::time::timeout(Duration::from_secs(1), client.read_to_string(&mut reply)).await.unwrap().unwrap();
+	reply
+}
+
+#[tokio::test]
+async fn second_audio_ipc_client_is_refused_while_the_first_is_connected() {
+	let state = app_state(&[]);
+	let path = socket_path();
+	tokio::spawn(run_audio_ipc_listener(state.clone(), bind_ipc_socket(&path).unwrap(), 4));
+	let mut audio = state.audio_tx.subscribe();
+
+	let mut first = UnixStream::connect(&path).await.unwrap();
+	first.write_all(b"AAAA").await.unwrap();
+	assert_eq!(&next_chunk(&mut audio).await[..], b"AAAA");
+
+	let mut second = UnixStream::connect(&path).await.unwrap();
+	assert_eq!(refusal(&mut second).await, "BUSY\n");
+	let _ = second.write_all(b"BBBB").await;
+	first.write_all(b"CCCC").await.unwrap();
+	assert_eq!(&next_chunk(&mut audio).await[..], b"CCCC", "only the first client drives audio");
+
+	// Once the first leaves, the slot is free again
+	drop(first);
+	let mut third = None;
+	for _ in 0..100 {
+		let mut client = UnixStream::connect(&path).await.unwrap();
+		client.write_all(b"DDDD").await.unwrap();
+		match tokio::time::timeout(Duration::from_millis(50), client.read_u8()).await {
+			// Refused: the first client's slot isn't released yet
+			Ok(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+			Err(_) => { third = Some(client); break; }
+		}
+	}
+	assert!(third.is_some(), "audio IPC slot never freed");
+	assert_eq!(&next_chunk(&mut audio).await[..], b"DDDD");
+	let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,29 @@
+use chrono::{Duration, Utc};
+use shortwave::testing::spawn_node;
+use shortwave::types::PeerInfo;
+use uuid::Uuid;
+
+async fn peers(base_url: &str) -> (reqwest::StatusCode, serde_json::Value) {
+	let resp = reqwest::get(format!("{base_url}/api/v1/peers")).await.unwrap();
+	(resp.status(), resp.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn empty_peer_table_is_an_empty_array() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	assert_eq!(peers(&node.base_url).await, (reqwest::StatusCode::OK, serde_json::json!([])));
+}
+
+#[tokio::test]
+async fn peers_are_listed_most_recently_seen_first() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let now = Utc::now();
+	for (name, age) in [("http://b.example", 60), ("http://a.example", 5), ("http://c.example", 3600)] {
+		let info = PeerInfo { node_id: Uuid::new_v4(), api_base_url: name.into(), last_seen: now - Duration::seconds(age) };
+		node.state.add_or_update_peer(name.into(), info).await;
+	}
+	let (status, body) = peers(&node.base_url).await;
+	assert_eq!(status, reqwest::StatusCode::OK);
+	let order: Vec<&str> = body.as_array().unwrap().iter().map(|p| p["api_base_url"].as_str().unwrap()).collect();
+	assert_eq!(order, ["http://a.example", "http://b.example", "http://c.example"]);
+}
@@ -0,0 +1,55 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use shortwave::ratelimit::{client_key, RateLimiter, MAX_TRACKED_CLIENTS};
+use shortwave::testing::spawn_node;
+
+fn v6(addr: &str) -> IpAddr {
+	IpAddr::V6(addr.parse::<Ipv6Addr>().unwrap())
+}
+
+#[tokio::test]
+async fn requests_past_the_burst_get_429_with_retry_after() {
+	let node = spawn_node(&["--rate-limit-per-sec", "0.5", "--rate-limit-burst", "3"], Vec::new()).await.unwrap();
+	let client = reqwest::Client::new();
+	let url = format!("{}/api/v1/stations", node.base_url);
+	for _ in 0..3 {
+		assert_eq!(client.get(&url).send().await.unwrap().status(), 200);
+	}
+	let resp = client.get(&url).send().await.unwrap();
+	assert_eq!(resp.status(), 429);
+	let retry_after: u64 = resp.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+	assert!((1..=2).contains(&retry_after), "{retry_after}");
+	assert_eq!(node.state.rate_limiter.as_ref().unwrap().limited(), 1);
+}
+
+#[test]
+fn ipv6_clients_share_a_bucket_per_64() {
+	let limiter = RateLimiter::new(0.001, 1);
+	assert!(limiter.check(v6("2001:db8:1:2::1")).is_ok());
+	assert!(limiter.check(v6("2001:db8:1:2:ffff:ffff:ffff:ffff")).is_err());
+	assert!(limiter.check(v6("2001:db8:1:3::1")).is_ok());
+	assert_eq!(client_key(v6("2001:db8:1:2:abcd::9")), v6("2001:db8:1:2::"));
+}
+
+#[test]
+fn ipv4_mapped_clients_share_the_ipv4_bucket() {
+	let limiter = RateLimiter::new(0.001, 1);
+	assert!(limiter.check(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 7))).is_ok());
+	assert!(limiter.check(v6("::ffff:192.0.2.7")).is_err());
+	assert_eq!(limiter.tracked(), 1);
+}
+
+#[test]
+fn tracked_clients_are_capped_and_the_least_recent_is_evicted() {
+	// Every bucket is still draining, so none would be dropped for having refilled
+	let limiter = RateLimiter::new(0.001, 1);
+	let client = |i: u32| IpAddr::V4(Ipv4Addr::from(0x0a00_0000 + i));
+	for i in 0..MAX_TRACKED_CLIENTS as u32 + 100 {
+		assert!(limiter.check(client(i)).is_ok());
+	}
+	assert_eq!(limiter.tracked(), MAX_TRACKED_CLIENTS);
+	// The first clients were evicted and start over with a full bucket; the latest are still spent
+	assert!(limiter.check(client(0)).is_ok());
+	assert!(limiter.check(client(MAX_TRACKED_CLIENTS as u32 + 99)).is_err());
+	assert_eq!(limiter.tracked(), MAX_TRACKED_CLIENTS);
+}
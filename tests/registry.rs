@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use shortwave::crypto::encode_public_key_b64;
+use shortwave::state::RegistryError;
+use shortwave::testing::{app_state, owner_key, resign, resign_for, signed_ad, signed_release};
+use shortwave::types::next_sequence;
+use uuid::Uuid;
+
+fn temp_file(contents: &str) -> String {
+	let path = std::env::temp_dir().join(format!("shortwave-test-{}", Uuid::new_v4()));
+	std::fs::write(&path, contents).unwrap();
+	path.to_string_lossy().into_owned()
+}
+
+#[tokio::test]
+async fn allowlist_admits_only_listed_owners() {
+	let (allowed, other) = (owner_key(), owner_key());
+	let allowed_b64 = encode_public_key_b64(&allowed.verifying_key());
+	let state = app_state(&["--allowed-owners", &allowed_b64]);
+
+	state.accept_advertisement(&signed_ad(&allowed, "101.1", Uuid::new_v4(), 1)).await.unwrap();
+	let err = state.accept_advertisement(&signed_ad(&other, "102.1", Uuid::new_v4(), 1)).await.unwrap_err();
+	assert!(matches!(err, RegistryError::OwnerNotAllowed), "{err}");
+}
+
+#[tokio::test]
+async fn empty_allowlist_file_admits_no_one() {
+	let path = temp_file("# nobody yet\n");
+	let state = app_state(&["--allowed-owners-file", &path]);
+	let err = state.accept_advertisement(&signed_ad(&owner_key(), "101.1", Uuid::new_v4(), 1)).await.unwrap_err();
+	assert!(matches!(err, RegistryError::OwnerNotAllowed), "{err}");
+	std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn allowlist_reloaded_to_empty_stops_admitting() {
+	let owner = owner_key();
+	let path = temp_file(&format!("{}\n", encode_public_key_b64(&owner.verifying_key())));
+	let state = app_state(&["--allowed-owners-file", &path]);
+	state.set_allowed_owners(shortwave::config::read_allowed_owners_file(&path).unwrap().into_iter().collect()).await;
+	state.accept_advertisement(&signed_ad(&owner, "101.1", Uuid::new_v4(), 1)).await.unwrap();
+
+	// The file is emptied and re-read, as the reload task does
+	std::fs::write(&path, "").unwrap();
+	state.set_allowed_owners(shortwave::config::read_allowed_owners_file(&path).unwrap().into_iter().collect()).await;
+	let err = state.accept_advertisement(&signed_ad(&owner, "102.1", Uuid::new_v4(), 1)).await.unwrap_err();
+	assert!(matches!(err, RegistryError::OwnerNotAllowed), "{err}");
+	std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn no_allowlist_admits_any_owner() {
+	let state = app_state(&[]);
+	state.accept_advertisement(&signed_ad(&owner_key(), "101.1", Uuid::new_v4(), 1)).await.unwrap();
+}
+
+#[tokio::test]
+async fn restarted_station_outranks_its_earlier_sequence() {
+	let state = app_state(&[]);
+	let (sk, station_id) = (owner_key(), Uuid::new_v4());
+	let mut sequence = 0;
+	for _ in 0..3 {
+		sequence = next_sequence(sequence, Utc::now());
+		state.accept_advertisement(&signed_ad(&sk, "101.1", station_id, sequence)).await.unwrap();
+	}
+
+	// Restarted without a hold file, the station's counter starts over from 0
+	tokio::time::sleep(Duration::from_millis(5)).await;
+	let restarted = next_sequence(0, Utc::now());
+	assert!(restarted > sequence, "{restarted} <= {sequence}");
+	state.accept_advertisement(&signed_ad(&sk, "101.1", station_id, restarted)).await.unwrap();
+
+	// And a pre-restart advertisement replayed afterwards is stale
+	let err = state.accept_advertisement(&signed_ad(&sk, "101.1", station_id, sequence)).await.unwrap_err();
+	assert!(matches!(err, RegistryError::StaleSequence(..)), "{err}");
+}
+
+#[tokio::test]
+async fn full_registry_rejects_new_frequencies_by_default() {
+	let state = app_state(&["--max-registry-size", "2"]);
+	state.accept_advertisement(&signed_ad(&owner_key(), "101.1", Uuid::new_v4(), 1)).await.unwrap();
+	state.accept_advertisement(&signed_ad(&owner_key(), "102.1", Uuid::new_v4(), 1)).await.unwrap();
+	let err = state.accept_advertisement(&signed_ad(&owner_key(), "103.1", Uuid::new_v4(), 1)).await.unwrap_err();
+	assert!(matches!(err, RegistryError::RegistryFull), "{err}");
+
+	// Refreshing a frequency already held needs no room
+	let sk = owner_key();
+	let state = app_state(&["--max-registry-size", "1"]);
+	let station_id = Uuid::new_v4();
+	state.accept_advertisement(&signed_ad(&sk, "101.1", station_id, 1)).await.unwrap();
+	state.accept_advertisement(&signed_ad(&sk, "101.1", station_id, 2)).await.unwrap();
+}
+
+#[tokio::test]
+async fn full_registry_evicts_the_soonest_to_expire() {
+	let state = app_state(&["--max-registry-size", "2", "--registry-full-policy", "evict-soonest"]);
+	let mut events = state.events_tx.subscribe();
+	let (short_lived, sk) = (Uuid::new_v4(), owner_key());
+	let mut ad = signed_ad(&sk, "101.1", short_lived, 1);
+	ad.ttl_seconds = 30;
+	resign(&sk, &mut ad);
+	state.accept_advertisement(&ad).await.unwrap();
+	state.accept_advertisement(&signed_ad(&owner_key(), "102.1", Uuid::new_v4(), 1)).await.unwrap();
+	let newcomer = Uuid::new_v4();
+	state.accept_advertisement(&signed_ad(&owner_key(), "103.1", newcomer, 1)).await.unwrap();
+
+	let held: Vec<Uuid> = state.snapshot_registry().await.iter().map(|a| a.station_id).collect();
+	assert_eq!(held.len(), 2);
+	assert!(!held.contains(&short_lived) && held.contains(&newcomer));
+	let evicted = std::iter::from_fn(|| events.try_recv().ok()).find(|e| e.event == "delete").expect("delete event");
+	assert_eq!((evicted.assignment.station_id, evicted.reason.as_deref()), (short_lived, Some("evicted")));
+}
+
+#[tokio::test]
+async fn per_owner_cap_overrides_the_default() {
+	let (big, small) = (owner_key(), owner_key());
+	let cap = format!("{}=4", encode_public_key_b64(&big.verifying_key()));
+	let state = app_state(&["--max-freqs-per-owner", "1", "--owner-cap", &cap]);
+
+	for frequency in ["101.1", "101.3", "101.5", "101.7"] {
+		state.accept_advertisement(&signed_ad(&big, frequency, Uuid::new_v4(), 1)).await.unwrap();
+	}
+	let err = state.accept_advertisement(&signed_ad(&big, "101.9", Uuid::new_v4(), 1)).await.unwrap_err();
+	assert!(matches!(err, RegistryError::OwnerCapExceeded), "{err}");
+
+	state.accept_advertisement(&signed_ad(&small, "102.1", Uuid::new_v4(), 1)).await.unwrap();
+	let err = state.accept_advertisement(&signed_ad(&small, "102.3", Uuid::new_v4(), 1)).await.unwrap_err();
+	assert!(matches!(err, RegistryError::OwnerCapExceeded), "{err}");
+}
+
+#[tokio::test]
+async fn station_id_reused_by_another_owner_is_refused() {
+	let state = app_state(&[]);
+	let station_id = Uuid::new_v4();
+	state.accept_advertisement(&signed_ad(&owner_key(), "101.1", station_id, 1)).await.unwrap();
+
+	// On another frequency as well as on the one it holds
+	let err = state.accept_advertisement(&signed_ad(&owner_key(), "102.1", station_id, 1)).await.unwrap_err();
+	assert!(matches!(err, RegistryError::StationOwnerMismatch(id) if id == station_id), "{err}");
+	let err = state.accept_advertisement(&signed_ad(&owner_key(), "101.1", station_id, 2)).await.unwrap_err();
+	assert!(matches!(err, RegistryError::OwnerMismatch), "{err}");
+}
+
+#[tokio::test]
+async fn release_drops_the_assignment() {
+	let state = app_state(&[]);
+	let (sk, station_id) = (owner_key(), Uuid::new_v4());
+	state.accept_advertisement(&signed_ad(&sk, "101.1", station_id, 5)).await.unwrap();
+	// Not signed by the owner
+	assert!(!state.release_assignment(&signed_release(&owner_key(), "101.1", station_id, 6, Utc::now())).await);
+	assert!(state.release_assignment(&signed_release(&sk, "101.1", station_id, 6, Utc::now())).await);
+	assert!(state.snapshot_registry().await.is_empty());
+}
+
+#[tokio::test]
+async fn replayed_release_does_not_drop_a_resumed_station() {
+	let state = app_state(&[]);
+	let (sk, station_id) = (owner_key(), Uuid::new_v4());
+	state.accept_advertisement(&signed_ad(&sk, "101.1", station_id, 5)).await.unwrap();
+	let release = signed_release(&sk, "101.1", station_id, 6, Utc::now());
+	assert!(state.release_assignment(&release).await);
+
+	// The station resumes; the captured release is then replayed
+	tokio::time::sleep(Duration::from_millis(5)).await;
+	state.accept_advertisement(&signed_ad(&sk, "101.1", station_id, 7)).await.unwrap();
+	assert!(!state.release_assignment(&release).await);
+	assert_eq!(state.snapshot_registry().await.len(), 1);
+}
+
+#[tokio::test]
+async fn release_with_an_older_sequence_or_stale_time_is_refused() {
+	let state = app_state(&[]);
+	let (sk, station_id) = (owner_key(), Uuid::new_v4());
+	state.accept_advertisement(&signed_ad(&sk, "101.1", station_id, 5)).await.unwrap();
+	assert!(!state.release_assignment(&signed_release(&sk, "101.1", station_id, 4, Utc::now())).await);
+	// Signed before the held advertisement was
+	let earlier = Utc::now() - chrono::Duration::seconds(30);
+	assert!(!state.release_assignment(&signed_release(&sk, "101.1", station_id, 6, earlier)).await);
+	let future = Utc::now() + chrono::Duration::hours(1);
+	assert!(!state.release_assignment(&signed_release(&sk, "101.1", station_id, 6, future)).await);
+	assert_eq!(state.snapshot_registry().await.len(), 1);
+}
+
+#[tokio::test]
+async fn owner_flood_stops_at_the_owner_limit() {
+	let state = app_state(&["--max-owners", "3"]);
+	let regular = owner_key();
+	state.accept_advertisement(&signed_ad(&regular, "100.1", Uuid::new_v4(), 1)).await.unwrap();
+	let mut refused = 0;
+	for i in 0..20 {
+		let ad = signed_ad(&owner_key(), &format!("10{}.{}", 1 + i / 10, i % 10), Uuid::new_v4(), 1);
+		match state.accept_advertisement(&ad).await {
+			Ok(_) => {}
+			Err(RegistryError::OwnerLimitReached) => refused += 1,
+			Err(err) => panic!("unexpected error: {err}"),
+		}
+	}
+	assert_eq!(refused, 18);
+	// Owners already tracked keep advertising
+	state.accept_advertisement(&signed_ad(&regular, "100.3", Uuid::new_v4(), 1)).await.unwrap();
+}
+
+#[tokio::test]
+async fn advertisement_signed_for_one_network_is_rejected_on_another() {
+	let alpha = app_state(&["--network-id", "alpha"]);
+	let beta = app_state(&["--network-id", "beta"]);
+	let default = app_state(&[]);
+	let owner = owner_key();
+
+	let mut ad = signed_ad(&owner, "101.1", Uuid::new_v4(), 1);
+	resign_for(&owner, &mut ad, Some("alpha"));
+	alpha.accept_advertisement(&ad).await.unwrap();
+	for other in [&beta, &default] {
+		let err = other.accept_advertisement(&ad).await.unwrap_err();
+		assert!(matches!(err, RegistryError::InvalidSignature), "{err}");
+	}
+
+	// Nor does a default-network signature carry over onto a named network
+	let ad = signed_ad(&owner, "102.1", Uuid::new_v4(), 1);
+	default.accept_advertisement(&ad).await.unwrap();
+	let err = alpha.accept_advertisement(&ad).await.unwrap_err();
+	assert!(matches!(err, RegistryError::InvalidSignature), "{err}");
+}
+
+#[tokio::test]
+async fn expiry_forgets_message_ids_older_than_the_max_ttl() {
+	let state = app_state(&["--max-ttl-secs", "60", "--max-ad-age-fraction", "0"]);
+	let owner = owner_key();
+	let mut old = signed_ad(&owner, "101.1", Uuid::new_v4(), 1);
+	old.advertised_at = Utc::now() - chrono::Duration::seconds(120);
+	resign(&owner, &mut old);
+	let fresh = signed_ad(&owner, "102.1", Uuid::new_v4(), 1);
+	state.accept_advertisement(&old).await.unwrap();
+	state.accept_advertisement(&fresh).await.unwrap();
+	assert_eq!(state.seen_messages.read().await.len(), 2);
+
+	state.expire_assignments().await.unwrap();
+	let seen = state.seen_messages.read().await;
+	assert!(!seen.contains_key(&old.message_id));
+	assert!(seen.contains_key(&fresh.message_id));
+}
@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+use futures_util::SinkExt;
+use shortwave::testing::{spawn_node, TestNode};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Push `frames` binary frames of `size` bytes over the source WebSocket; returns how long the
+/// node took to broadcast all of it
+async fn push_over_ws(node: &TestNode, token: &str, frames: usize, size: usize) -> Duration {
+	let mut audio = node.state.audio_tx.subscribe();
+	let mut req = format!("{}/api/v1/source/ws", node.base_url.replacen("http", "ws", 1)).into_client_request().unwrap();
+	req.headers_mut().insert("authorization", format!("Bearer {token}").parse().unwrap());
+	let (mut ws, _) = tokio_tungstenite::connect_async(req).await.unwrap();
+
+	let started = Instant::now();
+	let sender = tokio::spawn(async move {
+		for _ in 0..frames {
+			ws.send(Message::Binary(vec![0u8; size])).await.unwrap();
+		}
+		ws
+	});
+	let mut received = 0;
+	while received < frames * size {
+		received += tokio::time::timeout(Duration::from_secs(10), audio.recv()).await.unwrap().unwrap().len();
+	}
+	let elapsed = started.elapsed();
+	drop(sender.await.unwrap());
+	elapsed
+}
+
+#[tokio::test]
+async fn websocket_source_is_held_to_its_byte_rate() {
+	// 80 kbit/s is 10 kB/s: the first second's worth goes through at once, the other 20 kB take ~2s
+	let node = spawn_node(&["--source-token", "secret", "--source-ws-max-kbps", "80"], Vec::new()).await.unwrap();
+	let elapsed = push_over_ws(&node, "secret", 10, 3_000).await;
+	assert!(elapsed >= Duration::from_millis(1_500), "30 kB at 10 kB/s took only {elapsed:?}");
+}
+
+#[tokio::test]
+async fn websocket_source_is_unthrottled_when_disabled() {
+	let node = spawn_node(&["--source-token", "secret", "--source-ws-max-kbps", "0"], Vec::new()).await.unwrap();
+	let elapsed = push_over_ws(&node, "secret", 10, 3_000).await;
+	assert!(elapsed < Duration::from_millis(1_000), "30 kB unthrottled took {elapsed:?}");
+}
+
+/// Status of a short PUT /api/v1/source with an optional bearer token
+async fn put_source(node: &TestNode, bearer: Option<&str>) -> reqwest::StatusCode {
+	let mut req = reqwest::Client::new().put(format!("{}/api/v1/source", node.base_url)).body(vec![0u8; 16]);
+	if let Some(token) = bearer {
+		req = req.bearer_auth(token);
+	}
+	req.send().await.unwrap().status()
+}
+
+#[tokio::test]
+async fn prioritized_http_source_requires_a_matching_bearer() {
+	let node = spawn_node(&["--source", "audio-ipc:0", "--source", "main:1:main-token"], Vec::new()).await.unwrap();
+	assert_eq!(put_source(&node, None).await, reqwest::StatusCode::UNAUTHORIZED);
+	assert_eq!(put_source(&node, Some("wrong")).await, reqwest::StatusCode::UNAUTHORIZED);
+	assert_eq!(put_source(&node, Some("main-token")).await, reqwest::StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn tokenless_source_entry_is_not_reachable_over_http() {
+	let node = spawn_node(&["--source", "audio-ipc:0"], Vec::new()).await.unwrap();
+	assert_eq!(put_source(&node, None).await, reqwest::StatusCode::UNAUTHORIZED);
+	assert_eq!(put_source(&node, Some("")).await, reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn source_token_is_accepted_alongside_a_prioritized_list() {
+	let node = spawn_node(&["--source", "audio-ipc:0", "--source-token", "fallback"], Vec::new()).await.unwrap();
+	assert_eq!(put_source(&node, Some("fallback")).await, reqwest::StatusCode::NO_CONTENT);
+	assert_eq!(put_source(&node, Some("other")).await, reqwest::StatusCode::UNAUTHORIZED);
+}
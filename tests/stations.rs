@@ -0,0 +1,243 @@
+use chrono::Utc;
+use shortwave::healthcheck;
+use shortwave::testing::{owner_key, resign, signed_ad, signed_release, spawn_node};
+use shortwave::types::StationAdvertisement;
+use uuid::Uuid;
+
+async fn station_ids(base_url: &str, query: &str) -> Vec<String> {
+	let body: Vec<serde_json::Value> = reqwest::get(format!("{base_url}/api/v1/stations{query}")).await.unwrap()
+		.error_for_status().unwrap()
+		.json().await.unwrap();
+	body.iter().map(|s| s["station_id"].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn stations_are_listed_in_a_stable_frequency_order() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	// Inserted out of order, and numerically rather than lexically sorted (7.1 < 14.2 < 88.5 < 101.1)
+	let mut expected = Vec::new();
+	for frequency in ["101.1", "7.1", "88.5", "14.2"] {
+		let station_id = Uuid::new_v4();
+		node.state.accept_advertisement(&signed_ad(&owner_key(), frequency, station_id, 1)).await.unwrap();
+		expected.push((frequency.parse::<f64>().unwrap(), station_id.to_string()));
+	}
+	expected.sort_by(|a, b| a.0.total_cmp(&b.0));
+	let expected: Vec<String> = expected.into_iter().map(|(_, id)| id).collect();
+
+	for _ in 0..5 {
+		assert_eq!(station_ids(&node.base_url, "").await, expected);
+	}
+	let mut reversed = expected.clone();
+	reversed.reverse();
+	assert_eq!(station_ids(&node.base_url, "?order=desc").await, reversed);
+	// Names are "Test <frequency>", so name order is lexical: 101.1, 14.2, 7.1, 88.5
+	let by_name = station_ids(&node.base_url, "?sort=name").await;
+	assert_eq!(by_name, [expected[3].clone(), expected[1].clone(), expected[0].clone(), expected[2].clone()]);
+}
+
+#[tokio::test]
+async fn unknown_sort_key_is_rejected() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let resp = reqwest::get(format!("{}/api/v1/stations?sort=owner", node.base_url)).await.unwrap();
+	assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+async fn spectrum(base_url: &str, query: &str) -> reqwest::Response {
+	let request = reqwest::get(format!("{base_url}/api/v1/spectrum?{query}"));
+	tokio::time::timeout(std::time::Duration::from_secs(2), request).await.expect("spectrum answered promptly").unwrap()
+}
+
+#[tokio::test]
+async fn spectrum_refuses_huge_exponents_without_expanding_them() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	for query in [
+		"min=0&max=1e5000000&step=1",
+		"min=-1e5000000&max=1&step=1",
+		"min=0&max=1&step=1e-5000000",
+		"min=0&max=100&step=0.01",
+	] {
+		let resp = spectrum(&node.base_url, query).await;
+		assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST, "{query}");
+	}
+}
+
+#[tokio::test]
+async fn numeric_frequencies_are_bounds_checked_like_strings() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let mut ad = serde_json::to_value(signed_ad(&owner_key(), "101.1", Uuid::new_v4(), 1)).unwrap();
+	for frequency in [
+		serde_json::json!(1e300),
+		serde_json::json!(1e-300),
+		serde_json::json!(u64::MAX),
+		serde_json::json!(i64::MIN),
+		serde_json::json!("1e300"),
+	] {
+		ad["frequency"] = frequency.clone();
+		let err = serde_json::from_value::<StationAdvertisement>(ad.clone()).unwrap_err();
+		assert!(err.to_string().contains("out of range"), "{frequency}: {err}");
+		let resp = reqwest::Client::new().post(format!("{}/api/v1/advertise", node.base_url)).json(&ad).send().await.unwrap();
+		assert!(resp.status().is_client_error(), "{frequency}: {}", resp.status());
+	}
+	assert!(node.state.snapshot_registry().await.is_empty());
+
+	ad["frequency"] = serde_json::json!(101.1);
+	assert_eq!(serde_json::from_value::<StationAdvertisement>(ad).unwrap().frequency.to_string(), "101.1");
+}
+
+#[tokio::test]
+async fn spectrum_slots_cover_the_range() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let station_id = Uuid::new_v4();
+	node.state.accept_advertisement(&signed_ad(&owner_key(), "89.5", station_id, 1)).await.unwrap();
+	let slots: Vec<serde_json::Value> = spectrum(&node.base_url, "min=88&max=90&step=1").await.json().await.unwrap();
+	let occupied: Vec<bool> = slots.iter().map(|s| s["occupied"].as_bool().unwrap()).collect();
+	assert_eq!(occupied, [false, true, false]);
+}
+
+#[tokio::test]
+async fn wrong_method_on_stations_lists_the_allowed_ones() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let resp = reqwest::Client::new().delete(format!("{}/api/v1/stations", node.base_url)).send().await.unwrap();
+	assert_eq!(resp.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED);
+	let allow: Vec<String> = resp.headers()["allow"].to_str().unwrap().split(',').map(|m| m.trim().to_string()).collect();
+	for method in ["GET", "HEAD", "OPTIONS"] {
+		assert!(allow.iter().any(|m| m == method), "{method} missing from {allow:?}");
+	}
+	assert!(!allow.iter().any(|m| m == "DELETE"));
+	let body: serde_json::Value = resp.json().await.unwrap();
+	assert!(body["error"].as_str().unwrap().contains("DELETE"));
+}
+
+#[tokio::test]
+async fn status_filter_puts_each_station_in_one_bucket() {
+	let node = spawn_node(&["--stream-healthcheck-secs", "60"], Vec::new()).await.unwrap();
+	let owner = owner_key();
+	let reachable = format!("{}/api/v1/stations", node.base_url);
+	let mut ids = Vec::new();
+	// (frequency, stream URL, seconds since the last heartbeat); a 60 s TTL goes stale after 45 s
+	for (frequency, url, age) in [("90.1", reachable.as_str(), 0), ("91.1", reachable.as_str(), 50), ("92.1", "http://127.0.0.1:1/stream", 0)] {
+		let station_id = Uuid::new_v4();
+		let mut ad = signed_ad(&owner, frequency, station_id, 1);
+		ad.stream_url = url.into();
+		ad.advertised_at = Utc::now() - chrono::Duration::seconds(age);
+		resign(&owner, &mut ad);
+		node.state.accept_advertisement(&ad).await.unwrap();
+		ids.push(station_id.to_string());
+	}
+	let [live, stale, unreachable] = [ids[0].clone(), ids[1].clone(), ids[2].clone()];
+
+	// Nothing is unreachable until a healthcheck has run
+	assert_eq!(station_ids(&node.base_url, "?status=live").await, [live.clone(), unreachable.clone()]);
+	assert!(station_ids(&node.base_url, "?status=unreachable").await.is_empty());
+
+	healthcheck::check_streams(&node.state, &healthcheck::client().unwrap()).await;
+	assert_eq!(station_ids(&node.base_url, "?status=live").await, [live]);
+	assert_eq!(station_ids(&node.base_url, "?status=stale").await, [stale]);
+	assert_eq!(station_ids(&node.base_url, "?status=unreachable").await, [unreachable]);
+	assert_eq!(station_ids(&node.base_url, "").await.len(), 3);
+}
+
+#[tokio::test]
+async fn unreachable_filter_needs_the_healthcheck() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	for (query, status) in [("unreachable", 400), ("live", 200), ("dead", 400)] {
+		let resp = reqwest::get(format!("{}/api/v1/stations?status={query}", node.base_url)).await.unwrap();
+		assert_eq!(resp.status().as_u16(), status, "{query}");
+	}
+}
+
+async fn conditional_get(base_url: &str, if_none_match: Option<&str>) -> (u16, String, String) {
+	let mut req = reqwest::Client::new().get(format!("{base_url}/api/v1/stations"));
+	if let Some(tag) = if_none_match {
+		req = req.header("if-none-match", tag);
+	}
+	let resp = req.send().await.unwrap();
+	let status = resp.status().as_u16();
+	let etag = resp.headers().get("etag").map(|v| v.to_str().unwrap().to_string()).unwrap_or_default();
+	(status, etag, resp.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn unchanged_stations_refetch_is_not_modified() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let owner = owner_key();
+	node.state.accept_advertisement(&signed_ad(&owner, "101.1", Uuid::new_v4(), 1)).await.unwrap();
+
+	let (status, etag, body) = conditional_get(&node.base_url, None).await;
+	assert_eq!(status, 200);
+	assert!(etag.starts_with('"') && !body.is_empty(), "{etag}");
+	let (status, again, body) = conditional_get(&node.base_url, Some(&etag)).await;
+	assert_eq!((status, again.as_str(), body.as_str()), (304, etag.as_str(), ""));
+	// Weak comparison and tag lists, as caches send them
+	assert_eq!(conditional_get(&node.base_url, Some(&format!("\"other\", W/{etag}"))).await.0, 304);
+
+	// Any change to the listing is a new tag
+	let station_id = Uuid::new_v4();
+	node.state.accept_advertisement(&signed_ad(&owner, "102.1", station_id, 1)).await.unwrap();
+	let (status, added, _) = conditional_get(&node.base_url, Some(&etag)).await;
+	assert_eq!(status, 200);
+	assert_ne!(added, etag);
+	let release = signed_release(&owner, "102.1", station_id, 2, Utc::now());
+	assert!(node.state.release_assignment(&release).await);
+	let (status, released, _) = conditional_get(&node.base_url, Some(&added)).await;
+	assert_eq!(status, 200);
+	// Back to the original listing, so back to the original tag
+	assert_eq!(released, etag);
+}
+
+async fn stations_page(base_url: &str, query: &str) -> serde_json::Value {
+	reqwest::get(format!("{base_url}/api/v1/stations?{query}")).await.unwrap()
+		.error_for_status().unwrap()
+		.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn paging_walks_the_frequency_order_in_an_envelope() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	for frequency in ["104.1", "101.1", "103.1", "102.1", "105.1"] {
+		node.state.accept_advertisement(&signed_ad(&owner_key(), frequency, Uuid::new_v4(), 1)).await.unwrap();
+	}
+	let all = station_ids(&node.base_url, "").await;
+	assert_eq!(all.len(), 5, "no paging parameters keeps the bare array");
+
+	let mut paged = Vec::new();
+	for offset in [0, 2, 4] {
+		let page = stations_page(&node.base_url, &format!("limit=2&offset={offset}")).await;
+		assert_eq!((page["total"].as_u64(), page["offset"].as_u64(), page["limit"].as_u64()), (Some(5), Some(offset), Some(2)));
+		paged.extend(page["stations"].as_array().unwrap().iter().map(|s| s["station_id"].as_str().unwrap().to_string()));
+	}
+	assert_eq!(paged, all);
+
+	let past_end = stations_page(&node.base_url, "offset=50").await;
+	assert_eq!(past_end["stations"], serde_json::json!([]));
+	assert_eq!(past_end["total"], 5);
+
+	let clamped = stations_page(&node.base_url, "limit=100000").await;
+	assert_eq!(clamped["limit"], 500);
+	let defaulted = stations_page(&node.base_url, "paginate=true").await;
+	assert_eq!((defaulted["offset"].as_u64(), defaulted["limit"].as_u64()), (Some(0), Some(100)));
+	assert_eq!(defaulted["stations"].as_array().unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn frequency_range_is_inclusive_and_exact() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let mut ids = Vec::new();
+	for frequency in ["87.9", "88.0", "95.55", "108.0", "108.000001"] {
+		let station_id = Uuid::new_v4();
+		node.state.accept_advertisement(&signed_ad(&owner_key(), frequency, station_id, 1)).await.unwrap();
+		ids.push(station_id.to_string());
+	}
+	// 88.0 and 108.0 sit exactly on the band edges; 108.000001 is just past the top
+	assert_eq!(station_ids(&node.base_url, "?min=88&max=108").await, ids[1..4]);
+	assert_eq!(station_ids(&node.base_url, "?min=95.55").await, ids[2..]);
+	assert_eq!(station_ids(&node.base_url, "?max=88000kHz").await, ids[..2]);
+	assert_eq!(station_ids(&node.base_url, "?min=108.0000005&max=108.0000015").await, ids[4..]);
+
+	for query in ["min=abc", "max=88..1", "min=100&max=90"] {
+		let resp = reqwest::get(format!("{}/api/v1/stations?{query}", node.base_url)).await.unwrap();
+		assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST, "{query}");
+		let body: serde_json::Value = resp.json().await.unwrap();
+		assert!(body["error"].is_string(), "{query}: {body}");
+	}
+}
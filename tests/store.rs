@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use shortwave::store::{self, JsonFileStore, MemoryStore, RegistryStore, RegistryStoreSpec, SqliteStore};
+use shortwave::testing::{app_state, owner_key, signed_ad};
+use shortwave::types::StationAssignment;
+use uuid::Uuid;
+
+async fn assignments(frequencies: &[&str]) -> Vec<StationAssignment> {
+	let state = app_state(&[]);
+	let owner = owner_key();
+	for frequency in frequencies {
+		state.accept_advertisement(&signed_ad(&owner, frequency, Uuid::new_v4(), 1)).await.unwrap();
+	}
+	state.snapshot_registry().await
+}
+
+fn station_ids(assignments: &[StationAssignment]) -> Vec<Uuid> {
+	let mut ids: Vec<Uuid> = assignments.iter().map(|a| a.station_id).collect();
+	ids.sort();
+	ids
+}
+
+/// What every backend must do: start empty, return what was saved, and replace on each save
+async fn exercise(store: &dyn RegistryStore) {
+	assert!(store.load().unwrap().is_empty(), "{} should start empty", store.name());
+
+	let two = assignments(&["101.1", "102.1"]).await;
+	store.save(&two).unwrap();
+	assert_eq!(station_ids(&store.load().unwrap()), station_ids(&two));
+
+	let one = assignments(&["103.1"]).await;
+	store.save(&one).unwrap();
+	assert_eq!(station_ids(&store.load().unwrap()), station_ids(&one));
+
+	store.save(&[]).unwrap();
+	assert!(store.load().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn memory_store_round_trips() {
+	exercise(&MemoryStore::default()).await;
+}
+
+#[tokio::test]
+async fn json_file_store_round_trips() {
+	let path = std::env::temp_dir().join(format!("shortwave-store-{}.json", Uuid::new_v4()));
+	exercise(&JsonFileStore::new(&path)).await;
+	std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn sqlite_store_is_selectable_but_fails_loudly() {
+	let spec: RegistryStoreSpec = "sqlite:/tmp/registry.db".parse().unwrap();
+	assert_eq!(spec, RegistryStoreSpec::Sqlite("/tmp/registry.db".into()));
+	assert!("sqlite:".parse::<RegistryStoreSpec>().is_err());
+
+	let sqlite = SqliteStore::new("/tmp/registry.db");
+	assert_eq!(sqlite.name(), "sqlite");
+	assert!(sqlite.load().unwrap_err().to_string().contains("not implemented"));
+	assert!(sqlite.save(&[]).is_err());
+
+	let state = app_state(&["--registry-store", "sqlite:/tmp/registry.db"]);
+	assert_eq!(state.store.name(), "sqlite");
+	assert!(store::load_registry(&state).await.is_err());
+}
+
+#[tokio::test]
+async fn load_registry_restores_signed_entries_and_drops_tampered_ones() {
+	let mut saved = assignments(&["101.1", "102.1"]).await;
+	saved[1].name = "Forged".into();
+
+	let state = app_state(&[]);
+	state.store.save(&saved).unwrap();
+	store::load_registry(&state).await.unwrap();
+
+	let restored = state.snapshot_registry().await;
+	assert_eq!(station_ids(&restored), vec![saved[0].station_id]);
+}
+
+#[tokio::test]
+async fn registry_path_survives_a_restart_without_expired_entries() {
+	let path = std::env::temp_dir().join(format!("shortwave-registry-{}.json", Uuid::new_v4()));
+	let path_arg = path.to_str().unwrap();
+	let before = app_state(&["--registry-path", path_arg]);
+	assert_eq!(before.store.name(), "json");
+	store::spawn_store_sync(before.clone());
+	let kept = before.accept_advertisement(&signed_ad(&owner_key(), "101.1", Uuid::new_v4(), 1)).await.unwrap();
+	let expired = before.accept_advertisement(&signed_ad(&owner_key(), "102.1", Uuid::new_v4(), 1)).await.unwrap();
+
+	// Mutations are written back after the debounce, without any explicit save
+	let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+	while before.store.load().map(|saved| saved.len()).unwrap_or(0) < 2 {
+		assert!(tokio::time::Instant::now() < deadline, "registry never written to {}", path.display());
+		tokio::time::sleep(Duration::from_millis(100)).await;
+	}
+	let after = app_state(&["--registry-path", path_arg]);
+	store::load_registry(&after).await.unwrap();
+	assert_eq!(station_ids(&after.snapshot_registry().await), station_ids(&[kept.clone(), expired.clone()]));
+
+	// The first node may still rewrite its file, so the lapsed copy gets a file of its own
+	let lapsed_path = std::env::temp_dir().join(format!("shortwave-registry-{}.json", Uuid::new_v4()));
+	let mut saved = before.store.load().unwrap();
+	saved.iter_mut().filter(|a| a.station_id == expired.station_id).for_each(|a| a.expires_at = chrono::Utc::now());
+	JsonFileStore::new(&lapsed_path).save(&saved).unwrap();
+	let lapsed = app_state(&["--registry-path", lapsed_path.to_str().unwrap()]);
+	store::load_registry(&lapsed).await.unwrap();
+	assert_eq!(station_ids(&lapsed.snapshot_registry().await), vec![kept.station_id]);
+	std::fs::remove_file(lapsed_path).unwrap();
+	let _ = std::fs::remove_file(path);
+}
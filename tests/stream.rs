@@ -0,0 +1,77 @@
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use shortwave::config::Cli;
+use shortwave::http;
+use shortwave::state::AppState;
+use shortwave::testing::spawn_node;
+use tower_http::compression::CompressionLayer;
+
+/// GET /stream asking for gzip, push a chunk of audio, and return the Content-Encoding and the
+/// first bytes received
+async fn fetch_stream(base_url: &str, state: &AppState) -> (Option<String>, Vec<u8>) {
+	let mut resp = reqwest::Client::new()
+		.get(format!("{base_url}/stream"))
+		.header("accept-encoding", "gzip, br")
+		.send().await.unwrap();
+	assert_eq!(resp.status(), reqwest::StatusCode::OK);
+	let encoding = resp.headers().get("content-encoding").map(|v| v.to_str().unwrap().to_string());
+	state.ingest_audio(shortwave::source::DEFAULT_SOURCE, bytes::Bytes::from_static(b"ID3 raw audio bytes"));
+	let chunk = tokio::time::timeout(Duration::from_secs(5), resp.chunk()).await.unwrap().unwrap().unwrap();
+	(encoding, chunk.to_vec())
+}
+
+#[tokio::test]
+async fn stream_is_not_gzipped_when_the_client_accepts_gzip() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	let (encoding, chunk) = fetch_stream(&node.base_url, &node.state).await;
+	assert_eq!(encoding.as_deref(), Some("identity"));
+	assert_eq!(chunk, b"ID3 raw audio bytes");
+}
+
+#[tokio::test]
+async fn stream_is_not_gzipped_behind_a_compression_layer() {
+	let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+	let base_url = format!("http://{}", listener.local_addr().unwrap());
+	let config = Cli::try_parse_from(["shortwave", "--public-url", base_url.as_str()]).unwrap().into_config().unwrap();
+	let state = Arc::new(AppState::new(&config, None));
+	let app = http::router(state.clone(), &config).layer(CompressionLayer::new());
+	let server = tokio::spawn(axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).into_future());
+
+	let (encoding, chunk) = fetch_stream(&base_url, &state).await;
+	assert_eq!(encoding.as_deref(), Some("identity"));
+	assert_eq!(chunk, b"ID3 raw audio bytes");
+	server.abort();
+}
+
+#[tokio::test]
+async fn listeners_are_closed_when_the_source_switches_from_mp3_to_opus() {
+	let node = spawn_node(&[], Vec::new()).await.unwrap();
+	node.state.begin_source("encoder-a", Some("audio/mpeg"));
+	node.state.ingest_audio("encoder-a", bytes::Bytes::from_static(b"ID3 first mp3 frame"));
+
+	let mut mp3 = reqwest::get(format!("{}/stream", node.base_url)).await.unwrap();
+	assert_eq!(mp3.headers()["content-type"], "audio/mpeg");
+	node.state.ingest_audio("encoder-a", bytes::Bytes::from_static(b"ID3 live mp3 frame"));
+	let mut received = Vec::new();
+	while !received.ends_with(b"ID3 live mp3 frame") {
+		let chunk = tokio::time::timeout(Duration::from_secs(5), mp3.chunk()).await.unwrap().unwrap().unwrap();
+		received.extend_from_slice(&chunk);
+	}
+
+	// The MP3 encoder goes away and an Opus one takes over
+	node.state.begin_source("encoder-b", Some("audio/ogg; codecs=opus"));
+	node.state.ingest_audio("encoder-b", bytes::Bytes::from_static(b"OggS opus page"));
+	let rest = tokio::time::timeout(Duration::from_secs(5), mp3.bytes()).await.unwrap().unwrap();
+	assert!(rest.is_empty(), "MP3 listener was sent {:?} after the switch", rest);
+	assert_eq!(*node.state.active_codec.borrow(), Some("ogg".to_string()));
+
+	// A reconnecting listener is told the new codec and gets none of the old audio
+	let mut ogg = reqwest::get(format!("{}/stream", node.base_url)).await.unwrap();
+	assert_eq!(ogg.headers()["content-type"], "audio/ogg; codecs=opus");
+	node.state.ingest_audio("encoder-b", bytes::Bytes::from_static(b"OggS next opus page"));
+	let first = tokio::time::timeout(Duration::from_secs(5), ogg.chunk()).await.unwrap().unwrap().unwrap();
+	assert!(first.starts_with(b"OggS"), "{first:?}");
+}
@@ -0,0 +1,56 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use shortwave::tcp_ingest::serve_audio_tcp;
+use shortwave::testing::app_state;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+async fn start(token: &str) -> (std::sync::Arc<shortwave::state::AppState>, SocketAddr) {
+	let state = app_state(&[]);
+	let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	tokio::spawn(serve_audio_tcp(state.clone(), listener, Some(token.to_string())));
+	(state, addr)
+}
+
+/// What the server says before closing, or "" if it just closes
+async fn reply(stream: &mut TcpStream) -> String {
+	let mut out = String::new();
+	tokio::time::timeout(Duration::from_secs(2), stream.read_to_string(&mut out)).await.unwrap().unwrap();
+	out
+}
+
+#[tokio::test]
+async fn unauthenticated_connection_does_not_hold_the_source_slot() {
+	let (state, addr) = start("secret").await;
+	let mut audio = state.audio_tx.subscribe();
+	// Connected but never sends its token
+	let _idle = TcpStream::connect(addr).await.unwrap();
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	let mut encoder = TcpStream::connect(addr).await.unwrap();
+	encoder.write_all(b"secret\naudio").await.unwrap();
+	let chunk = tokio::time::timeout(Duration::from_secs(2), audio.recv()).await.unwrap().unwrap();
+	assert_eq!(&chunk[..], b"audio");
+
+	// Only an authenticated second source learns the slot is taken
+	let mut intruder = TcpStream::connect(addr).await.unwrap();
+	intruder.write_all(b"guess\n").await.unwrap();
+	assert_eq!(reply(&mut intruder).await, "UNAUTHORIZED\n");
+	let mut second = TcpStream::connect(addr).await.unwrap();
+	second.write_all(b"secret\n").await.unwrap();
+	assert_eq!(reply(&mut second).await, "BUSY\n");
+}
+
+#[tokio::test]
+async fn pending_handshakes_are_capped() {
+	let (_state, addr) = start("secret").await;
+	let mut idle = Vec::new();
+	for _ in 0..8 {
+		idle.push(TcpStream::connect(addr).await.unwrap());
+	}
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	let mut extra = TcpStream::connect(addr).await.unwrap();
+	assert_eq!(reply(&mut extra).await, "");
+}